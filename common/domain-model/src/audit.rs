@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{Action, Order, PluginEventKind, PluginId, Tick};
+
+/// Trace of a live deployment action from the plugin call to the exchange, the id is the action id
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ActionRecord {
+    pub id: Uuid,
+    pub deployment_id: Uuid,
+    pub plugin_id: PluginId,
+    pub timestamp: DateTime<Utc>,
+    pub trigger: ActionTrigger,
+    pub action: Action,
+    pub risk: RiskDecision,
+    // actions rejected by the risk manager are never executed
+    pub result: Option<ExecutionResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ActionTrigger {
+    Tick(Tick),
+    Event(PluginEventKind),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum RiskDecision {
+    Approved,
+    Rejected(String),
+    /// Shadow deployment actions are never executed, so they are not checked
+    Unchecked,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ExecutionResult {
+    Placed(Order),
+    Cancelled,
+    /// Simulation and shadow deployment actions are not passed to exchanges
+    Skipped,
+    Failed(String),
+}
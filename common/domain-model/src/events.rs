@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{Action, DeploymentInfo, InstrumentId, RiskRejection};
+
+/// Activity of the engine streamed to subscribers, ticks and actions are streamed only for live deployments
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EngineEvent {
+    pub timestamp: DateTime<Utc>,
+    pub deployment_id: Uuid,
+    pub kind: EngineEventKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum EngineEventKind {
+    Deployed(DeploymentInfo),
+    /// Status change or patch of the deployment
+    Updated(DeploymentInfo),
+    Deleted(DeploymentInfo),
+    TickProcessed {
+        tick_id: Uuid,
+        instrument_id: InstrumentId,
+        actions: usize,
+    },
+    ActionsEmitted(Vec<Action>),
+    RiskRejected(RiskRejection),
+    PluginError(String),
+}
@@ -7,7 +7,18 @@ use chrono::{DateTime, Duration, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::params::ParamSchema;
+use crate::schedule::Schedule;
+use crate::warmup::{Warmup, WarmupGap};
+
+pub mod audit;
 pub mod drawing;
+pub mod events;
+pub mod params;
+pub mod schedule;
+pub mod shadow;
+pub mod state;
+pub mod warmup;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Simulation {
@@ -32,6 +43,26 @@ pub struct SimulationDeployment {
     pub params: HashMap<String, String>,
     pub subscriptions: Vec<InstrumentId>,
     pub indicators: Vec<Indicator>,
+    #[serde(default)]
+    pub schedules: Vec<Schedule>,
+    #[serde(default)]
+    pub allocation: Allocation,
+}
+
+/// Part of the simulation capital given to a deployment
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum Allocation {
+    // amount in USDT
+    Fixed(f64),
+    Percent(f64),
+    // share of capital left after fixed and percent allocations, weighted by inverse volatility
+    RiskParity,
+}
+
+impl Default for Allocation {
+    fn default() -> Self {
+        Self::Percent(100.)
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -130,7 +161,7 @@ impl Timeframe {
             Timeframe::OneH => 3600,
             Timeframe::TwoH => 7200,
             Timeframe::FourH => 14400,
-            Timeframe::OneD => 345600,
+            Timeframe::OneD => 86400,
         }
     }
 }
@@ -264,6 +295,8 @@ impl Size {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PluginInfo {
     pub id: PluginId,
+    #[serde(default)]
+    pub params: Vec<ParamSchema>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
@@ -283,7 +316,10 @@ impl PluginId {
 
 impl From<PluginBinary> for PluginInfo {
     fn from(value: PluginBinary) -> Self {
-        Self { id: value.id }
+        Self {
+            id: value.id,
+            params: value.params,
+        }
     }
 }
 
@@ -291,6 +327,7 @@ impl From<&PluginBinary> for PluginInfo {
     fn from(value: &PluginBinary) -> Self {
         Self {
             id: value.id.clone(),
+            params: value.params.clone(),
         }
     }
 }
@@ -298,13 +335,16 @@ impl From<&PluginBinary> for PluginInfo {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct PluginBinary {
     pub id: PluginId,
+    #[serde(default)]
+    pub params: Vec<ParamSchema>,
     pub binary: Vec<u8>,
 }
 
 impl PluginBinary {
-    pub fn new(id: PluginId, binary: &[u8]) -> Self {
+    pub fn new(id: PluginId, params: Vec<ParamSchema>, binary: &[u8]) -> Self {
         Self {
             id,
+            params,
             binary: binary.to_vec(),
         }
     }
@@ -320,11 +360,33 @@ pub struct DeploymentInfo {
     pub params: HashMap<String, String>,
     pub subscriptions: Vec<InstrumentId>,
     pub indicators: Vec<Indicator>,
-}
-
-#[derive(Debug, Deserialize, Serialize, Clone)]
+    #[serde(default)]
+    pub schedules: Vec<Schedule>,
+    #[serde(default)]
+    pub warmup: Vec<Warmup>,
+    // live deployments get ticks only when their warmup history is synced
+    #[serde(default)]
+    pub ready: bool,
+    // warmup windows still missing candles, the sync is retried while the deployment is not ready
+    #[serde(default)]
+    pub warmup_gaps: Vec<WarmupGap>,
+    #[serde(default)]
+    pub execution: ExecutionPolicy,
+    #[serde(default)]
+    pub risk: RiskLimits,
+    #[serde(default)]
+    pub mode: DeploymentMode,
+    #[serde(default)]
+    pub upgrade: UpgradePolicy,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum DeploymentStatus {
     Created,
+    Running,
+    // keeps subscriptions and state, but ticks are not passed to the plugin
+    Paused,
+    Failed,
     Deleted,
 }
 
@@ -334,6 +396,116 @@ pub struct NewDeployment {
     pub plugin_id: PluginId,
     pub state_id: Option<Uuid>,
     pub params: HashMap<String, String>,
+    #[serde(default)]
+    pub execution: ExecutionPolicy,
+    #[serde(default)]
+    pub risk: RiskLimits,
+    #[serde(default)]
+    pub mode: DeploymentMode,
+    #[serde(default)]
+    pub upgrade: UpgradePolicy,
+}
+
+/// How the engine runs ticks of a deployment
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ExecutionPolicy {
+    // tick processing timeout in milliseconds, the engine default is used if not set
+    pub timeout: Option<u64>,
+    #[serde(default)]
+    pub backpressure: Backpressure,
+}
+
+/// What to do with a new tick when the deployment queue is full
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Backpressure {
+    // the oldest queued tick is dropped
+    #[default]
+    DropOldest,
+    // a queued tick of the same instrument is replaced, the queue holds only the latest prices
+    Coalesce,
+}
+
+/// Shadow deployments get the same live ticks, but their actions are only recorded and filled hypothetically
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum DeploymentMode {
+    #[default]
+    Live,
+    Shadow,
+}
+
+/// How a deployment is upgraded when a new version of its plugin is added to the registry
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum UpgradePolicy {
+    // keeps its plugin version, it is redeployed only when the same version is added again
+    #[default]
+    Pinned,
+    Auto,
+    // a part of canary deployments is upgraded first, the rest only after they pass the rollout gate
+    Canary,
+}
+
+/// Pre-trade limits of live deployments, unset limits are taken from the engine global limits
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct RiskLimits {
+    // in the source currency of the order pair
+    pub max_order_notional: Option<f64>,
+    // max absolute position size of the account per currency
+    pub max_positions: HashMap<Currency, f64>,
+    pub max_open_orders: Option<u32>,
+    pub max_orders_per_minute: Option<u32>,
+    // realized loss since the start of the UTC day in the source currency
+    pub max_daily_loss: Option<f64>,
+    // percent between the limit order price and the last tick price
+    pub max_price_deviation: Option<f64>,
+}
+
+impl RiskLimits {
+    pub fn or(&self, defaults: &RiskLimits) -> RiskLimits {
+        let mut max_positions = defaults.max_positions.clone();
+        max_positions.extend(self.max_positions.iter().map(|(currency, size)| (*currency, *size)));
+        RiskLimits {
+            max_order_notional: self.max_order_notional.or(defaults.max_order_notional),
+            max_positions,
+            max_open_orders: self.max_open_orders.or(defaults.max_open_orders),
+            max_orders_per_minute: self.max_orders_per_minute.or(defaults.max_orders_per_minute),
+            max_daily_loss: self.max_daily_loss.or(defaults.max_daily_loss),
+            max_price_deviation: self.max_price_deviation.or(defaults.max_price_deviation),
+        }
+    }
+}
+
+/// Action of a live deployment rejected by the engine risk manager
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RiskRejection {
+    pub deployment_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub action: Action,
+    pub reason: String,
+}
+
+/// Request of the global kill switch, open positions are market-closed to `close_to` currency when it is set
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct KillSwitch {
+    #[serde(default)]
+    pub close_to: Option<Currency>,
+}
+
+/// Result of the global kill switch, failures are described by messages
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct KillSwitchReport {
+    pub paused: Vec<Uuid>,
+    pub cancelled: Vec<String>,
+    pub closed: Vec<Position>,
+    pub failed: Vec<String>,
+    /// What the kill switch doesn't cover
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DeploymentPatch {
+    pub params: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -376,7 +548,7 @@ impl InstrumentId {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, Eq, PartialEq, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, Eq, PartialEq, Hash, Clone, Copy)]
 pub struct CurrencyPair {
     pub target: Currency,
     pub source: Currency,
@@ -397,6 +569,26 @@ impl From<(Currency, Currency)> for CurrencyPair {
     }
 }
 
+/// Event for plugin callbacks, delivered only to the deployment if it's set,
+/// otherwise to deployments subscribed to the event instrument
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PluginEvent {
+    pub simulation_id: Option<Uuid>,
+    pub deployment_id: Option<Uuid>,
+    pub timestamp: DateTime<Utc>,
+    pub kind: PluginEventKind,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub enum PluginEventKind {
+    Start,
+    Stop,
+    OrderUpdate(Order),
+    PositionUpdate(Position),
+    CandleClosed(Candle),
+    Timer(String),
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Tick {
     pub id: Uuid,
@@ -418,7 +610,7 @@ impl Tick {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Copy, Clone)]
 pub enum Exchange {
     OKX,
     BYBIT,
@@ -441,7 +633,7 @@ impl FromStr for Exchange {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Clone, Copy)]
 pub enum Currency {
     BTC,
     USDT,
@@ -588,6 +780,9 @@ pub enum Action {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct OrderAction {
     pub id: Uuid,
+    /// Nil for actions serialized before deployments were tracked.
+    #[serde(default)]
+    pub deployment_id: Uuid,
     pub simulation_id: Option<Uuid>,
     pub plugin_id: PluginId,
     pub timestamp: DateTime<Utc>,
@@ -660,7 +855,7 @@ impl Trigger {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Copy, Clone)]
 pub enum Side {
     Buy,
     Sell,
@@ -715,6 +910,8 @@ pub struct CreateSimulationDeployment {
     pub timeframe: Timeframe,
     pub plugin_id: PluginId,
     pub params: HashMap<String, String>,
+    #[serde(default)]
+    pub allocation: Allocation,
 }
 
 pub fn convert_to_simulation_deployment(value: CreateSimulationDeployment) -> SimulationDeployment {
@@ -725,6 +922,8 @@ pub fn convert_to_simulation_deployment(value: CreateSimulationDeployment) -> Si
         params: value.params,
         subscriptions: Vec::new(),
         indicators: Vec::new(),
+        schedules: Vec::new(),
+        allocation: value.allocation,
     }
 }
 
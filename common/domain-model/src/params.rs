@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Plugin parameter, values are passed to `PluginApi::configure` as strings
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ParamSchema {
+    pub name: String,
+    pub kind: ParamKind,
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// Allowed values, any value of the kind is allowed if empty
+    #[serde(default)]
+    pub choices: Vec<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
+pub enum ParamKind {
+    String,
+    Integer,
+    Float,
+    Bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ParamError {
+    pub name: String,
+    pub reason: String,
+}
+
+impl ParamSchema {
+    pub fn new(name: &str, kind: ParamKind) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            default: None,
+            min: None,
+            max: None,
+            choices: Vec::new(),
+            description: None,
+        }
+    }
+
+    pub fn with_default(mut self, default: &str) -> Self {
+        self.default = Some(default.to_string());
+        self
+    }
+
+    pub fn with_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    pub fn with_choices(mut self, choices: &[&str]) -> Self {
+        self.choices = choices.iter().map(|choice| choice.to_string()).collect();
+        self
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    fn check(&self, value: &str) -> Result<(), String> {
+        let number = match self.kind {
+            ParamKind::String => None,
+            ParamKind::Integer => Some(value.parse::<i64>().map_err(|_| format!("'{value}' is not an integer"))? as f64),
+            ParamKind::Float => Some(value.parse::<f64>().map_err(|_| format!("'{value}' is not a number"))?),
+            ParamKind::Bool => {
+                value.parse::<bool>().map_err(|_| format!("'{value}' is not a boolean"))?;
+                None
+            }
+        };
+        if let (Some(number), Some(min)) = (number, self.min) {
+            if number < min {
+                return Err(format!("{value} is less than the minimum: {min}"));
+            }
+        }
+        if let (Some(number), Some(max)) = (number, self.max) {
+            if number > max {
+                return Err(format!("{value} is greater than the maximum: {max}"));
+            }
+        }
+        if !self.choices.is_empty() && !self.choices.iter().any(|choice| choice == value) {
+            return Err(format!("'{value}' is not one of: {:?}", self.choices));
+        }
+        Ok(())
+    }
+}
+
+/// Checks params against the schema and returns them with defaults of missing params,
+/// params absent in the schema are rejected to catch typos
+pub fn validate_params(schema: &[ParamSchema], params: &HashMap<String, String>) -> Result<HashMap<String, String>, Vec<ParamError>> {
+    let mut errors: Vec<_> = params
+        .keys()
+        .filter(|name| !schema.iter().any(|param| &param.name == *name))
+        .map(|name| ParamError {
+            name: name.clone(),
+            reason: String::from("unknown parameter"),
+        })
+        .collect();
+    let mut result = params.clone();
+    for param in schema {
+        let value = match (params.get(&param.name), &param.default) {
+            (Some(value), _) => value,
+            (None, Some(default)) => {
+                result.insert(param.name.clone(), default.clone());
+                default
+            }
+            (None, None) => {
+                errors.push(ParamError {
+                    name: param.name.clone(),
+                    reason: String::from("required parameter is missing"),
+                });
+                continue;
+            }
+        };
+        if let Err(reason) = param.check(value) {
+            errors.push(ParamError {
+                name: param.name.clone(),
+                reason,
+            });
+        }
+    }
+    if errors.is_empty() {
+        Ok(result)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Vec<ParamSchema> {
+        vec![
+            ParamSchema::new("period", ParamKind::Integer).with_default("20").with_range(Some(2.), Some(200.)),
+            ParamSchema::new("side", ParamKind::String).with_choices(&["Buy", "Sell"]),
+        ]
+    }
+
+    #[test]
+    fn test_defaults_are_added() {
+        let params = HashMap::from([(String::from("side"), String::from("Buy"))]);
+        let result = validate_params(&schema(), &params).unwrap();
+        assert_eq!(result.get("period").unwrap(), "20");
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let params = HashMap::from([
+            (String::from("period"), String::from("1")),
+            (String::from("sied"), String::from("Buy")),
+        ]);
+        let errors = validate_params(&schema(), &params).unwrap_err();
+        let mut names: Vec<_> = errors.iter().map(|error| error.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["period", "side", "sied"]);
+    }
+}
@@ -0,0 +1,194 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Named plugin timer, fired as `PluginEventKind::Timer` with the schedule name
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Schedule {
+    pub name: String,
+    pub trigger: ScheduleTrigger,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum ScheduleTrigger {
+    /// Interval in seconds, fire times are aligned to the unix epoch
+    Interval(u64),
+    /// Cron expression in UTC with five fields: minute, hour, day of month, month, day of week
+    Cron(String),
+}
+
+impl Schedule {
+    pub fn interval(name: &str, seconds: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            trigger: ScheduleTrigger::Interval(seconds),
+        }
+    }
+
+    pub fn cron(name: &str, expression: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            trigger: ScheduleTrigger::Cron(expression.to_string()),
+        }
+    }
+
+    /// First fire time strictly after `time`
+    pub fn next_after(&self, time: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        match &self.trigger {
+            ScheduleTrigger::Interval(seconds) => {
+                if *seconds == 0 {
+                    bail!("Interval of schedule: '{}' must be positive", self.name);
+                }
+                let interval = Duration::seconds(*seconds as i64);
+                Ok(time.duration_trunc(interval)? + interval)
+            }
+            ScheduleTrigger::Cron(expression) => Cron::parse(expression)?.next_after(time),
+        }
+    }
+}
+
+// bit masks of allowed values for each field
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    fn parse(expression: &str) -> Result<Self> {
+        let fields: Vec<_> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            bail!("Cron expression: '{expression}' must have 5 fields");
+        };
+        let mut weekdays_mask = parse_field(weekdays, 0, 7)?;
+        // both 0 and 7 are sunday
+        if weekdays_mask & (1 << 7) != 0 {
+            weekdays_mask |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days: parse_field(days, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            weekdays: weekdays_mask,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+
+    fn next_after(&self, time: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        let mut next = time.duration_trunc(Duration::minutes(1))? + Duration::minutes(1);
+        // a matching time exists within a few years for any valid expression
+        let limit = time + Duration::days(366 * 5);
+        while next <= limit {
+            if !contains(self.months, next.month()) {
+                let (year, month) = if next.month() == 12 { (next.year() + 1, 1) } else { (next.year(), next.month() + 1) };
+                next = next
+                    .with_day(1)
+                    .and_then(|next| next.with_month(month))
+                    .and_then(|next| next.with_year(year))
+                    .unwrap()
+                    .duration_trunc(Duration::days(1))?;
+            } else if !self.matches_day(next) {
+                next = next.duration_trunc(Duration::days(1))? + Duration::days(1);
+            } else if !contains(self.hours, next.hour()) {
+                next = next.duration_trunc(Duration::hours(1))? + Duration::hours(1);
+            } else if !contains(self.minutes, next.minute()) {
+                next += Duration::minutes(1);
+            } else {
+                return Ok(next);
+            }
+        }
+        bail!("Cron expression never fires")
+    }
+
+    // like in cron, restricted day of month and day of week match if either of them matches
+    fn matches_day(&self, time: DateTime<Utc>) -> bool {
+        let day = contains(self.days, time.day());
+        let weekday = contains(self.weekdays, time.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+fn contains(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let mut mask = 0;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>()?),
+            None => (item, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (start.parse()?, end.parse()?),
+                // `a/n` means from `a` to the max value with step `n`
+                None if item.contains('/') => (range.parse()?, max),
+                None => (range.parse()?, range.parse()?),
+            },
+        };
+        if step == 0 || start < min || end > max || start > end {
+            bail!("Invalid cron field: '{field}'");
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn test_interval_is_aligned_to_epoch() {
+        let schedule = Schedule::interval("rebalance", 4 * 60 * 60);
+        let time = Utc.with_ymd_and_hms(2023, 11, 10, 5, 30, 0).unwrap();
+        let next = schedule.next_after(time).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2023, 11, 10, 8, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_cron_daily() {
+        let schedule = Schedule::cron("close", "59 23 * * *");
+        let time = Utc.with_ymd_and_hms(2023, 11, 10, 23, 59, 0).unwrap();
+        let next = schedule.next_after(time).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2023, 11, 11, 23, 59, 0).unwrap());
+    }
+
+    #[test]
+    fn test_cron_steps_and_weekdays() {
+        // every 15 minutes during working hours on mondays
+        let schedule = Schedule::cron("dca", "*/15 9-17 * * 1");
+        let time = Utc.with_ymd_and_hms(2023, 11, 10, 17, 50, 0).unwrap();
+        let next = schedule.next_after(time).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2023, 11, 13, 9, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_cron_next_year() {
+        let schedule = Schedule::cron("new year", "0 0 1 1 *");
+        let time = Utc.with_ymd_and_hms(2023, 11, 10, 0, 0, 0).unwrap();
+        let next = schedule.next_after(time).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_cron() {
+        assert!(Schedule::cron("invalid", "60 * * * *").next_after(Utc::now()).is_err());
+        assert!(Schedule::cron("invalid", "* * *").next_after(Utc::now()).is_err());
+    }
+}
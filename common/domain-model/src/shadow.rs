@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{CurrencyPair, Exchange, Side};
+
+/// Hypothetical fill of a shadow deployment order at live prices, size is in the target currency
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShadowFill {
+    pub order_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub exchange: Exchange,
+    pub pair: CurrencyPair,
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+}
+
+/// Decisions of a shadow deployment compared with a live deployment on the same live ticks
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShadowReport {
+    pub shadow_id: Uuid,
+    pub live_id: Uuid,
+    pub shadow: DecisionSummary,
+    pub live: DecisionSummary,
+    // orders of the same pair and side created by both deployments on the same tick
+    pub agreed: u32,
+    pub shadow_only: u32,
+    pub live_only: u32,
+    pub fills: Vec<ShadowFill>,
+    // profit of the fills in quote currencies, open holdings are valued at the last prices
+    pub profit: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DecisionSummary {
+    pub orders: u32,
+    pub cancels: u32,
+    pub buys: u32,
+    pub sells: u32,
+    pub rejected: u32,
+}
@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Saved plugin state, every change of a state is saved as its next version
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StateSnapshot {
+    pub state_id: String,
+    pub version: i64,
+    pub timestamp: DateTime<Utc>,
+    pub state: String,
+}
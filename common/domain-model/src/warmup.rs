@@ -0,0 +1,122 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{InstrumentId, Timeframe};
+
+/// History required by a plugin before its first tick, the last `bars` closed candles of the timeframe
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Warmup {
+    pub instrument_id: InstrumentId,
+    pub timeframe: Timeframe,
+    pub bars: u64,
+}
+
+/// Closed candles from `from` until `to`, `to` is the open time of the current candle
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarmupWindow {
+    pub instrument_id: InstrumentId,
+    pub timeframe: Timeframe,
+    pub bars: u64,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// Warmup window without all its closed candles in the storage
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct WarmupGap {
+    pub instrument_id: InstrumentId,
+    pub timeframe: Timeframe,
+    pub bars: u64,
+    // stored closed candles of the window
+    pub candles: u64,
+}
+
+impl Warmup {
+    pub fn new(instrument_id: InstrumentId, timeframe: Timeframe, bars: u64) -> Self {
+        Self {
+            instrument_id,
+            timeframe,
+            bars,
+        }
+    }
+
+    pub fn window(&self, now: DateTime<Utc>) -> WarmupWindow {
+        let timeframe = self.timeframe.as_sec();
+        let to = now - Duration::seconds(now.timestamp().rem_euclid(timeframe)) - Duration::nanoseconds(now.timestamp_subsec_nanos() as i64);
+        WarmupWindow {
+            instrument_id: self.instrument_id.clone(),
+            timeframe: self.timeframe,
+            bars: self.bars,
+            from: to - Duration::seconds(timeframe * self.bars as i64),
+            to,
+        }
+    }
+}
+
+/// One window per instrument and timeframe with the longest lookback, empty lookbacks are skipped
+pub fn plan(warmups: &[Warmup], now: DateTime<Utc>) -> Vec<WarmupWindow> {
+    let mut result: Vec<Warmup> = Vec::new();
+    for warmup in warmups.iter().filter(|warmup| warmup.bars > 0) {
+        match result
+            .iter_mut()
+            .find(|planned| planned.instrument_id == warmup.instrument_id && planned.timeframe == warmup.timeframe) {
+            Some(planned) => planned.bars = planned.bars.max(warmup.bars),
+            None => result.push(warmup.clone()),
+        }
+    }
+    result.iter().map(|warmup| warmup.window(now)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use crate::{Currency, CurrencyPair, Exchange, MarketType};
+
+    use super::*;
+
+    fn instrument_id(target: Currency) -> InstrumentId {
+        InstrumentId {
+            exchange: Exchange::OKX,
+            market_type: MarketType::Spot,
+            pair: CurrencyPair {
+                target,
+                source: Currency::USDT,
+            },
+        }
+    }
+
+    #[test]
+    fn test_window_ends_at_current_candle() {
+        let warmup = Warmup::new(instrument_id(Currency::BTC), Timeframe::OneH, 20);
+        let now = Utc.with_ymd_and_hms(2023, 12, 5, 10, 42, 17).unwrap();
+        let window = warmup.window(now);
+        assert_eq!(window.to, Utc.with_ymd_and_hms(2023, 12, 5, 10, 0, 0).unwrap());
+        assert_eq!(window.from, Utc.with_ymd_and_hms(2023, 12, 4, 14, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_daily_window() {
+        let warmup = Warmup::new(instrument_id(Currency::BTC), Timeframe::OneD, 14);
+        let now = Utc.with_ymd_and_hms(2023, 12, 5, 10, 42, 17).unwrap();
+        let window = warmup.window(now);
+        assert_eq!(window.to, Utc.with_ymd_and_hms(2023, 12, 5, 0, 0, 0).unwrap());
+        assert_eq!(window.from, Utc.with_ymd_and_hms(2023, 11, 21, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_plan_keeps_longest_lookback() {
+        let now = Utc.with_ymd_and_hms(2023, 12, 5, 10, 42, 17).unwrap();
+        let warmups = [
+            Warmup::new(instrument_id(Currency::BTC), Timeframe::FourH, 14),
+            Warmup::new(instrument_id(Currency::BTC), Timeframe::FourH, 50),
+            Warmup::new(instrument_id(Currency::BTC), Timeframe::FiveM, 0),
+            Warmup::new(instrument_id(Currency::ETH), Timeframe::FourH, 14),
+        ];
+        let windows = plan(&warmups, now);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].bars, 50);
+        assert_eq!(windows[0].from, Utc.with_ymd_and_hms(2023, 11, 27, 0, 0, 0).unwrap());
+        assert_eq!(windows[1].instrument_id, instrument_id(Currency::ETH));
+    }
+}
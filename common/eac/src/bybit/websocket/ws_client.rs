@@ -15,6 +15,7 @@ use url::Url;
 use crate::bybit::credential::Credential;
 use crate::bybit::websocket::{Command, Message};
 use crate::bybit::websocket::handler::WsMessageHandler;
+use crate::metrics::WS_RECONNECTS;
 
 pub struct BybitWsClient {
     sender: Sender<WsMessage>,
@@ -90,6 +91,7 @@ async fn handling_loop<H: WsMessageHandler>(
                 error!("Websocket failed to connect: {:?}", e);
                 tokio::time::sleep(Duration::from_secs(5)).await;
                 warn!("Websocket reconnecting ...");
+                WS_RECONNECTS.with_label_values(&["bybit"]).inc();
                 continue;
             }
         };
@@ -106,6 +108,7 @@ async fn handling_loop<H: WsMessageHandler>(
         }
         write_task.abort();
         warn!("Websocket reconnecting ...");
+        WS_RECONNECTS.with_label_values(&["bybit"]).inc();
         tokio::time::sleep(Duration::from_secs(2)).await;
     }
 }
@@ -1,2 +1,3 @@
 pub mod okx;
 pub mod bybit;
+mod metrics;
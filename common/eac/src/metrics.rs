@@ -0,0 +1,9 @@
+use metrics::IntCounterVec;
+use once_cell::sync::Lazy;
+
+pub(crate) static WS_RECONNECTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    metrics::counter_vec("exchange_ws_reconnects_total", "Websocket reconnections", &["exchange"])
+});
+pub(crate) static RATE_LIMIT_WAITS: Lazy<IntCounterVec> = Lazy::new(|| {
+    metrics::counter_vec("exchange_rate_limit_waits_total", "REST requests delayed by the rate limit", &["endpoint"])
+});
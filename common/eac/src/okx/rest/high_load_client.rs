@@ -5,6 +5,7 @@ use serde::de::DeserializeOwned;
 use tokio::sync::Mutex;
 use tracing::debug;
 
+use crate::metrics::RATE_LIMIT_WAITS;
 use crate::okx::OkExError;
 use crate::okx::rest::{OkExRest, Request};
 
@@ -35,6 +36,7 @@ impl RateLimitedRestClient {
             if rate.requests >= R::REQUESTS_PER_SECOND {
                 if rate.last_request + 1000 > now_millis {
                     debug!("Rate limit reached for {key} waiting 1 second");
+                    RATE_LIMIT_WAITS.with_label_values(&[&key]).inc();
                     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                 }
                 rate.requests = 0;
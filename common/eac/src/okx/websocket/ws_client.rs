@@ -12,6 +12,7 @@ use tracing::{debug, error, info, trace, warn};
 use tungstenite::Message as WsMessage;
 use url::Url;
 
+use crate::metrics::WS_RECONNECTS;
 use crate::okx::credential::Credential;
 use crate::okx::websocket::{Command, Message};
 use crate::okx::websocket::handler::WsMessageHandler;
@@ -112,6 +113,7 @@ async fn handling_loop<H: WsMessageHandler>(
                 error!("Websocket failed to connect: {:?}", e);
                 tokio::time::sleep(Duration::from_secs(5)).await;
                 warn!("Websocket reconnecting ...");
+                WS_RECONNECTS.with_label_values(&["okx"]).inc();
                 continue;
             }
         };
@@ -128,6 +130,7 @@ async fn handling_loop<H: WsMessageHandler>(
         }
         write_task.abort();
         warn!("Websocket reconnecting ...");
+        WS_RECONNECTS.with_label_values(&["okx"]).inc();
         tokio::time::sleep(Duration::from_secs(2)).await;
     }
 }
@@ -0,0 +1,40 @@
+use prometheus::{Encoder, HistogramOpts, Opts, TextEncoder};
+pub use prometheus::{HistogramVec, IntCounter, IntCounterVec, IntGauge};
+
+/// Path of the metrics endpoint exposed by every service
+pub const GET_METRICS: &str = "/metrics";
+
+// metrics are registered once in the default registry, keep them in statics
+
+pub fn counter(name: &str, help: &str) -> IntCounter {
+    let counter = IntCounter::new(name, help).unwrap();
+    prometheus::register(Box::new(counter.clone())).unwrap();
+    counter
+}
+
+pub fn counter_vec(name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    let counter = IntCounterVec::new(Opts::new(name, help), labels).unwrap();
+    prometheus::register(Box::new(counter.clone())).unwrap();
+    counter
+}
+
+pub fn gauge(name: &str, help: &str) -> IntGauge {
+    let gauge = IntGauge::new(name, help).unwrap();
+    prometheus::register(Box::new(gauge.clone())).unwrap();
+    gauge
+}
+
+pub fn histogram_vec(name: &str, help: &str, labels: &[&str]) -> HistogramVec {
+    let histogram = HistogramVec::new(HistogramOpts::new(name, help), labels).unwrap();
+    prometheus::register(Box::new(histogram.clone())).unwrap();
+    histogram
+}
+
+/// Handler of `GET_METRICS`, all registered metrics in the Prometheus text format
+pub async fn handler() -> String {
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .unwrap();
+    String::from_utf8(buffer).unwrap()
+}
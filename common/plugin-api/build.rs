@@ -0,0 +1,20 @@
+use std::env;
+use std::process::Command;
+
+// rustc commit hash and target triple are compared by the plugin loader, see `abi.rs`
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let output = Command::new(rustc)
+        .arg("-vV")
+        .output()
+        .expect("Error during rustc version reading");
+    let version = String::from_utf8(output.stdout).unwrap();
+    let commit_hash = version
+        .lines()
+        .find_map(|line| line.strip_prefix("commit-hash: "))
+        .or_else(|| version.lines().next())
+        .unwrap_or("unknown");
+    println!("cargo:rustc-env=PLUGIN_API_RUSTC={commit_hash}");
+    println!("cargo:rustc-env=PLUGIN_API_TARGET={}", env::var("TARGET").unwrap());
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
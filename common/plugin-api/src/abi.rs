@@ -0,0 +1,132 @@
+use std::mem::{align_of, size_of};
+
+use anyhow::{bail, Result};
+
+use domain_model::{Action, Candle, Indicator, InstrumentId, Order, PluginEventKind, PluginId, Position, Tick};
+use domain_model::schedule::Schedule;
+
+use crate::{PluginApi, PluginInternalApi};
+
+/// Name of the symbol with the ABI metadata exported by `export_plugin!`
+pub const ABI_SYMBOL: &[u8] = b"plugin_abi";
+
+/// Build metadata of a plugin, the plugin can be loaded only by a host with the same metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginAbi {
+    pub api_version: String,
+    pub rustc: String,
+    pub target: String,
+    pub layout: u64,
+}
+
+impl PluginAbi {
+    /// Metadata of the current build
+    pub fn current() -> Self {
+        Self {
+            api_version: env!("CARGO_PKG_VERSION").to_string(),
+            rustc: env!("PLUGIN_API_RUSTC").to_string(),
+            target: env!("PLUGIN_API_TARGET").to_string(),
+            layout: layout_fingerprint(),
+        }
+    }
+
+    /// Plain text form passed through the C ABI
+    pub fn encode(&self) -> String {
+        format!("{};{};{};{:x}", self.api_version, self.rustc, self.target, self.layout)
+    }
+
+    pub fn decode(value: &str) -> Result<Self> {
+        let [api_version, rustc, target, layout] = value.split(';').collect::<Vec<_>>()[..] else {
+            bail!("Invalid plugin ABI metadata: '{value}'");
+        };
+        Ok(Self {
+            api_version: api_version.to_string(),
+            rustc: rustc.to_string(),
+            target: target.to_string(),
+            layout: u64::from_str_radix(layout, 16)?,
+        })
+    }
+
+    pub fn check(&self, host: &PluginAbi) -> Result<()> {
+        if self.api_version != host.api_version {
+            bail!("Plugin is built with plugin-api: '{}', but host uses: '{}'", self.api_version, host.api_version);
+        }
+        if self.rustc != host.rustc {
+            bail!("Plugin is built with rustc: '{}', but host is built with: '{}'", self.rustc, host.rustc);
+        }
+        if self.target != host.target {
+            bail!("Plugin is built for target: '{}', but host target is: '{}'", self.target, host.target);
+        }
+        if self.layout != host.layout {
+            bail!("Plugin types layout: '{:x}' differs from host: '{:x}', rebuild the plugin with the same dependencies", self.layout, host.layout);
+        }
+        Ok(())
+    }
+}
+
+// FNV-1a over sizes and alignments of the types passed between the host and plugins
+fn layout_fingerprint() -> u64 {
+    let layouts = [
+        (size_of::<Tick>(), align_of::<Tick>()),
+        (size_of::<Action>(), align_of::<Action>()),
+        (size_of::<Candle>(), align_of::<Candle>()),
+        (size_of::<Order>(), align_of::<Order>()),
+        (size_of::<Position>(), align_of::<Position>()),
+        (size_of::<PluginId>(), align_of::<PluginId>()),
+        (size_of::<InstrumentId>(), align_of::<InstrumentId>()),
+        (size_of::<Indicator>(), align_of::<Indicator>()),
+        (size_of::<Schedule>(), align_of::<Schedule>()),
+        (size_of::<PluginEventKind>(), align_of::<PluginEventKind>()),
+        (size_of::<Box<dyn PluginApi>>(), align_of::<Box<dyn PluginApi>>()),
+        (size_of::<std::sync::Arc<dyn PluginInternalApi>>(), align_of::<std::sync::Arc<dyn PluginInternalApi>>()),
+    ];
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for (size, align) in layouts {
+        for byte in (size as u64).to_le_bytes().into_iter().chain((align as u64).to_le_bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Exports the plugin `load` function and its ABI metadata, the plugin type must implement `Default`
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:ty) => {
+        #[allow(improper_ctypes_definitions)]
+        #[no_mangle]
+        pub extern "C" fn load() -> Box<dyn $crate::PluginApi> {
+            Box::<$plugin>::default()
+        }
+
+        #[no_mangle]
+        pub extern "C" fn plugin_abi() -> *const std::os::raw::c_char {
+            static ABI: std::sync::OnceLock<std::ffi::CString> = std::sync::OnceLock::new();
+            ABI.get_or_init(|| std::ffi::CString::new($crate::abi::PluginAbi::current().encode()).unwrap())
+                .as_ptr()
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let abi = PluginAbi::current();
+        assert_eq!(PluginAbi::decode(&abi.encode()).unwrap(), abi);
+    }
+
+    #[test]
+    fn test_check_different_version() {
+        let host = PluginAbi::current();
+        let plugin = PluginAbi {
+            api_version: String::from("0.0.1"),
+            ..host.clone()
+        };
+        assert!(plugin.check(&host).is_err());
+        assert!(host.check(&host).is_ok());
+    }
+}
@@ -4,26 +4,53 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde_json::Value;
 use tokio::time::error::Elapsed;
 use tracing::{error, span};
 use tracing::Level;
 
-use domain_model::{Action, Candle, Currency, CurrencyPair, Exchange, Indicator, InstrumentId, Order, OrderMarketType, OrderType, PluginId, Position, Side, Size, Tick, Timeframe, Trigger};
+use domain_model::{Action, Candle, Currency, CurrencyPair, Exchange, Indicator, InstrumentId, Order, OrderMarketType, OrderType, PluginEventKind, PluginId, Position, Side, Size, Tick, Timeframe, Trigger};
 use domain_model::drawing::{Color, Coord, Icon, LineStyle};
+use domain_model::params::ParamSchema;
+use domain_model::schedule::Schedule;
+use domain_model::warmup::Warmup;
 use indicators::api::BollingerBand;
 
 #[async_trait]
 pub trait PluginApi: Send + Sync {
     fn id(&self) -> PluginId;
+    /// Parameters accepted by `configure`, deployment params are validated against them when not empty
+    fn params(&self) -> Vec<ParamSchema> {
+        Vec::new()
+    }
     fn configure(&mut self, _config: &HashMap<String, String>) {}
     fn instruments(&self) -> Vec<InstrumentId>;
     fn indicators(&self) -> Vec<Indicator> {
         Vec::new()
     }
+    /// History synced before the first tick, live deployments get no ticks until it is present
+    fn warmup(&self) -> Vec<Warmup> {
+        Vec::new()
+    }
+    /// Timers fired to `on_timer`, by wall-clock in live mode and by simulated time in simulations
+    fn schedules(&self) -> Vec<Schedule> {
+        Vec::new()
+    }
     async fn get_state(&self) -> Option<String> { None }
     async fn set_state(&mut self, _state: &str) {}
+    /// Version of the state format, states saved by another version are passed to `migrate_state` first
+    fn state_version(&self) -> u32 {
+        0
+    }
+    fn migrate_state(&mut self, from_version: u32, _state: &str) -> anyhow::Result<String> {
+        anyhow::bail!("no state migration from version: '{from_version}' to '{}'", self.state_version())
+    }
     fn on_tick_sync(&mut self, tick: &Tick, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.on_tick_with_timeout(tick, api, Duration::from_secs(10))
+    }
+
+    fn on_tick_with_timeout(&mut self, tick: &Tick, api: Arc<dyn PluginInternalApi>, timeout: Duration) -> Vec<Action> {
         let tick_id = format!(
             "{} '{}' {}-{}='{}'",
             tick.instrument_id.exchange,
@@ -40,7 +67,7 @@ pub trait PluginApi: Send + Sync {
             tick_id
         )
             .entered();
-        let runtime = with_tokio_runtime(self.on_tick(tick, api));
+        let runtime = with_tokio_runtime(timeout, self.on_tick(tick, api));
         match runtime {
             Ok(actions) => actions,
             Err(error) => {
@@ -55,11 +82,70 @@ pub trait PluginApi: Send + Sync {
     }
 
     async fn on_tick(&mut self, tick: &Tick, api: Arc<dyn PluginInternalApi>) -> Vec<Action>;
+
+    fn on_event_with_timeout(&mut self, event: &PluginEventKind, api: Arc<dyn PluginInternalApi>, timeout: Duration) -> Vec<Action> {
+        let _span = span!(
+            Level::INFO,
+            "strategy",
+            name = self.id().name,
+            version = self.id().version
+        )
+            .entered();
+        let runtime = with_tokio_runtime(timeout, async {
+            match event {
+                PluginEventKind::Start => {
+                    self.on_start(api).await;
+                    Vec::new()
+                }
+                PluginEventKind::Stop => {
+                    self.on_stop(api).await;
+                    Vec::new()
+                }
+                PluginEventKind::OrderUpdate(order) => self.on_order_update(order, api).await,
+                PluginEventKind::PositionUpdate(position) => self.on_position_update(position, api).await,
+                PluginEventKind::CandleClosed(candle) => self.on_candle_closed(candle, api).await,
+                PluginEventKind::Timer(name) => self.on_timer(name, api).await,
+            }
+        });
+        match runtime {
+            Ok(actions) => actions,
+            Err(error) => {
+                error!(
+                    "Timeout during event processing, strategy: '{}:{}'. Error: '{error}'",
+                    self.id().name,
+                    self.id().version
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    async fn on_start(&mut self, _api: Arc<dyn PluginInternalApi>) {}
+    async fn on_stop(&mut self, _api: Arc<dyn PluginInternalApi>) {}
+    async fn on_order_update(&mut self, _order: &Order, _api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        Vec::new()
+    }
+    async fn on_position_update(&mut self, _position: &Position, _api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        Vec::new()
+    }
+    async fn on_candle_closed(&mut self, _candle: &Candle, _api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        Vec::new()
+    }
+    async fn on_timer(&mut self, _name: &str, _api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        Vec::new()
+    }
+}
+
+thread_local! {
+    // plugins are called synchronously, every calling thread builds its runtime once and reuses it
+    static RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Error during plugin runtime creation");
 }
 
-#[tokio::main]
-async fn with_tokio_runtime<T: Default>(future: impl Future<Output=T>) -> Result<T, Elapsed> {
-    tokio::time::timeout(Duration::from_secs(10), future).await
+fn with_tokio_runtime<T: Default>(timeout: Duration, future: impl Future<Output=T>) -> Result<T, Elapsed> {
+    RUNTIME.with(|runtime| runtime.block_on(tokio::time::timeout(timeout, future)))
 }
 
 pub trait PluginInternalApi: Send + Sync {
@@ -70,8 +156,16 @@ pub trait PluginInternalApi: Send + Sync {
     fn indicators(&self) -> Arc<dyn IndicatorsInternalApi>;
     fn drawings(&self) -> Arc<dyn DrawingsInternalApi>;
     fn account(&self) -> Arc<dyn AccountInternalApi>;
+    fn clock(&self) -> Arc<dyn ClockInternalApi>;
+    fn state(&self) -> Arc<dyn StateInternalApi>;
+}
+
+/// Current time of the deployment, use it instead of `Utc::now()` to get the tick time in simulations
+pub trait ClockInternalApi: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
 }
 
+/// Key-value store of the deployment, values are saved with the deployment state and survive plugin updates
 #[async_trait]
 pub trait StateInternalApi: Send + Sync {
     async fn set(&self, key: &str, state: Value);
@@ -1,4 +1,5 @@
 pub use api::*;
 
 mod api;
+pub mod abi;
 pub mod utils;
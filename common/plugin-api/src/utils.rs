@@ -1,5 +1,6 @@
 use std::{env, io};
 
+use chrono::{DateTime, Utc};
 use nanoid::nanoid;
 use tracing_subscriber::{EnvFilter, fmt, Layer};
 use tracing_subscriber::layer::SubscriberExt;
@@ -15,6 +16,11 @@ pub fn string_id() -> String {
     nanoid!(16, &ID_KEYS) // todo fix too small length, can be collision
 }
 
+/// Random id prefixed with the hex timestamp in millis, so ids are ordered by creation time
+pub fn string_id_at(timestamp: DateTime<Utc>) -> String {
+    format!("{:x}{}", timestamp.timestamp_millis(), string_id())
+}
+
 pub fn init_logger(file_name: &str, directives: &str) {
     let mut tmp_dir = env::temp_dir();
     tmp_dir.push("nucane/logs");
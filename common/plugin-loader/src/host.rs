@@ -0,0 +1,164 @@
+//! Host interface shared by sandboxed plugins, functions mirror `PluginInternalApi`
+//! and take a single argument, arguments and results are serialized values.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use domain_model::{Currency, CurrencyPair, Exchange, InstrumentId, OrderMarketType, OrderType, Side, Size, Timeframe, Trigger};
+use domain_model::drawing::{Color, Coord, Icon, LineStyle};
+use plugin_api::{Line, PluginInternalApi, Point};
+
+pub(crate) trait HostFunctions {
+    fn register<A, R, F>(&mut self, name: &str, function: F) -> Result<()>
+        where
+            A: DeserializeOwned + Send + 'static,
+            R: Serialize + Send + Sync + 'static,
+            F: Fn(Arc<dyn PluginInternalApi>, A) -> BoxFuture<'static, R> + Send + Sync + 'static;
+}
+
+pub(crate) fn add_host_functions(host: &mut impl HostFunctions) -> Result<()> {
+    host.register("now", |api, _: ()| async move { api.clock().now() }.boxed())?;
+    host.register("create_order_action", |api, args: CreateOrderArgs| {
+        async move {
+            api.actions().create_order_action(
+                args.exchange,
+                args.pair,
+                args.market_type,
+                args.order_type,
+                args.size,
+                args.side,
+                args.sl,
+                args.tp,
+            )
+        }.boxed()
+    })?;
+    host.register("cancel_order_action", |api, args: CancelOrderArgs| {
+        async move { api.actions().cancel_order_action(args.exchange, args.pair, &args.order_id) }.boxed()
+    })?;
+    host.register("get_order_by_id", |api, args: OrderArgs| {
+        async move { api.orders().get_order_by_id(args.exchange, &args.id).await }.boxed()
+    })?;
+    host.register("get_position", |api, args: PositionArgs| {
+        async move { api.positions().get_position(args.exchange, args.currency).await }.boxed()
+    })?;
+    host.register("total_balance", |api, exchange: Exchange| {
+        async move { api.account().total_balance(exchange).await }.boxed()
+    })?;
+    host.register("get_candles", |api, args: CandlesArgs| {
+        async move { api.candles().get_candles(&args.instrument_id, args.timeframe, args.limit).await }.boxed()
+    })?;
+    host.register("sma", |api, args: IndicatorArgs| {
+        async move { api.indicators().sma(&args.instrument_id, args.timeframe, args.period).await }.boxed()
+    })?;
+    host.register("ema", |api, args: IndicatorArgs| {
+        async move { api.indicators().ema(&args.instrument_id, args.timeframe, args.period).await }.boxed()
+    })?;
+    host.register("bb", |api, args: IndicatorArgs| {
+        async move {
+            let band = api.indicators().bb(&args.instrument_id, args.timeframe, args.period, args.multiplier).await;
+            (band.upper, band.average, band.lower)
+        }.boxed()
+    })?;
+    host.register("psar", |api, args: IndicatorArgs| {
+        async move { api.indicators().psar(&args.instrument_id, args.timeframe).await }.boxed()
+    })?;
+    host.register("save_point", |api, args: PointArgs| {
+        async move {
+            let point = Point::new(args.instrument_id, &args.label, args.icon, args.color, args.text, args.coord);
+            api.drawings().save_point(point).await
+        }.boxed()
+    })?;
+    host.register("save_line", |api, args: LineArgs| {
+        async move {
+            let line = Line::new(args.instrument_id, &args.label, args.style, args.color, args.start, args.end);
+            api.drawings().save_line(line).await
+        }.boxed()
+    })?;
+    host.register("get_value", |api, key: String| {
+        async move { api.state().get(&key).await }.boxed()
+    })?;
+    host.register("set_value", |api, args: ValueArgs| {
+        async move { api.state().set(&args.key, args.value).await }.boxed()
+    })?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct CreateOrderArgs {
+    exchange: Exchange,
+    pair: CurrencyPair,
+    market_type: OrderMarketType,
+    order_type: OrderType,
+    size: Size,
+    side: Side,
+    sl: Option<Trigger>,
+    tp: Option<Trigger>,
+}
+
+#[derive(Deserialize)]
+struct CancelOrderArgs {
+    exchange: Exchange,
+    pair: CurrencyPair,
+    order_id: String,
+}
+
+#[derive(Deserialize)]
+struct OrderArgs {
+    exchange: Exchange,
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct PositionArgs {
+    exchange: Exchange,
+    currency: Currency,
+}
+
+#[derive(Deserialize)]
+struct CandlesArgs {
+    instrument_id: InstrumentId,
+    timeframe: Timeframe,
+    limit: u64,
+}
+
+#[derive(Deserialize)]
+struct IndicatorArgs {
+    instrument_id: InstrumentId,
+    timeframe: Timeframe,
+    #[serde(default)]
+    period: u64,
+    #[serde(default)]
+    multiplier: f64,
+}
+
+#[derive(Deserialize)]
+struct PointArgs {
+    instrument_id: InstrumentId,
+    label: String,
+    icon: Option<Icon>,
+    color: Option<Color>,
+    text: Option<String>,
+    coord: Coord,
+}
+
+#[derive(Deserialize)]
+struct LineArgs {
+    instrument_id: InstrumentId,
+    label: String,
+    style: Option<LineStyle>,
+    color: Option<Color>,
+    start: Coord,
+    end: Coord,
+}
+
+#[derive(Deserialize)]
+struct ValueArgs {
+    key: String,
+    value: Value,
+}
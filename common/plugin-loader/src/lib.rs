@@ -1,17 +1,46 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
 use std::fmt;
 use std::fmt::Formatter;
 use std::fs::File;
 use std::io::Write;
+use std::os::raw::c_char;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use libloading::Library;
 
+use plugin_api::abi::{ABI_SYMBOL, PluginAbi};
+use domain_model::PluginId;
 use plugin_api::PluginApi;
 
+pub use script::{SCRIPT_PARAM, SCRIPT_PLUGIN_NAME, ScriptParamsError};
+pub use wasm::WASM_ABI_VERSION;
+
+mod host;
+mod script;
+#[cfg(test)]
+mod test_api;
+mod wasm;
+
 #[allow(improper_ctypes_definitions)]
 type PluginLoader = extern "C" fn() -> Box<dyn PluginApi + Send + Sync>;
+type PluginAbiLoader = extern "C" fn() -> *const c_char;
 
+/// Loads a dynamic library, a WASM module or a script, the kind is detected by the binary content
 pub fn load(binary: &[u8]) -> Result<Plugin> {
+    if wasm::is_wasm(binary) {
+        return Ok(Plugin {
+            api: Box::new(wasm::load(binary)?),
+            library: None,
+        });
+    }
+    // libraries are never valid UTF-8
+    if let Ok(source) = std::str::from_utf8(binary) {
+        return Ok(Plugin {
+            api: Box::new(script::load(source, None, &HashMap::new())?),
+            library: None,
+        });
+    }
     let file_name = if cfg!(target_os = "windows") {
         "plugin.dll"
     } else {
@@ -25,20 +54,42 @@ pub fn load(binary: &[u8]) -> Result<Plugin> {
     }
     let plugin = unsafe {
         let library = Library::new(file_path)?;
+        check_abi(&library)?;
         let plugin_api = library.get::<PluginLoader>(b"load")?();
         Plugin {
-            library,
+            library: Some(library),
             api: plugin_api,
         }
     };
     Ok(plugin)
 }
 
+/// Compiles and configures the script of the built-in script plugin, errors contain the script position.
+/// Params are checked against the `params()` schema of the script first, see [`ScriptParamsError`]
+pub fn load_script(source: &str, id: PluginId, params: &HashMap<String, String>) -> Result<Plugin> {
+    Ok(Plugin {
+        api: Box::new(script::load(source, Some(id), params)?),
+        library: None,
+    })
+}
+
+/// Plugin ABI metadata must match the host before any plugin type is touched
+unsafe fn check_abi(library: &Library) -> Result<()> {
+    let plugin_abi = library
+        .get::<PluginAbiLoader>(ABI_SYMBOL)
+        .map_err(|_| anyhow!("Plugin doesn't export ABI metadata, build it with `plugin_api::export_plugin!`"))?;
+    let plugin_abi = CStr::from_ptr(plugin_abi()).to_str()?;
+    PluginAbi::decode(plugin_abi)?
+        .check(&PluginAbi::current())
+        .map_err(|err| anyhow!("Incompatible plugin: {err}"))
+}
+
 pub struct Plugin {
     // don't change order, api should drop before library
     pub api: Box<dyn PluginApi + Send + Sync>,
+    // absent for WASM and script plugins
     #[allow(unused)]
-    library: Library,
+    library: Option<Library>,
 }
 
 impl fmt::Debug for Plugin {
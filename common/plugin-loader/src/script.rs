@@ -0,0 +1,451 @@
+//! Strategies written in Rhai, compiled when the plugin is loaded.
+//!
+//! A script defines `instruments()` and `on_tick(tick)`, other functions are optional: `id()` (required
+//! for registry artifacts), `params()`, `indicators()`, `schedules()`, `configure(params)`, `on_start()`, `on_stop()`,
+//! `on_order_update(order)`, `on_position_update(position)`, `on_candle_closed(candle)`, `on_timer(name)`,
+//! `warmup()`, `state_version()` and `migrate_state(from_version, state)` returning the migrated state map.
+//! Domain values are object maps in their serialized form, handlers return arrays of actions.
+//! The script state is the `this` object map, it is saved as the plugin state.
+//! Host functions of the `host` module are available by their names, e.g. `sma(#{ instrument_id: .., timeframe: "OneH", period: 20 })`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Scope};
+use rhai::serde::{from_dynamic, to_dynamic};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::runtime::Handle;
+use tracing::error;
+
+use domain_model::{Action, Candle, Indicator, InstrumentId, Order, PluginId, Position, Tick};
+use domain_model::params::{ParamError, ParamSchema, validate_params};
+use domain_model::schedule::Schedule;
+use domain_model::warmup::Warmup;
+use plugin_api::{PluginApi, PluginInternalApi};
+
+use crate::host::{add_host_functions, HostFunctions};
+
+/// Name of the built-in plugin, its deployments take the script source from the `SCRIPT_PARAM` parameter
+pub const SCRIPT_PLUGIN_NAME: &str = "script";
+pub const SCRIPT_PARAM: &str = "script";
+// limits the script runtime of a single call, infinite loops fail instead of hanging the deployment.
+// Handlers are stopped by the tick timeout too, the limit bounds loading, configuration and migration
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+thread_local! {
+    // set on the blocking thread of a running handler, the handler stops once it is cancelled
+    static CANCELLED: RefCell<Option<Arc<AtomicBool>>> = RefCell::new(None);
+}
+
+type ApiSlot = Arc<Mutex<Option<Arc<dyn PluginInternalApi>>>>;
+type State = Arc<Mutex<Dynamic>>;
+
+/// Params of the deployment don't match the `params()` schema of the script
+#[derive(Debug)]
+pub struct ScriptParamsError(pub Vec<ParamError>);
+
+impl fmt::Display for ScriptParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid script params: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ScriptParamsError {}
+
+pub(crate) fn load(source: &str, id: Option<PluginId>, params: &HashMap<String, String>) -> Result<ScriptPlugin> {
+    let api = ApiSlot::default();
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_progress(|_| {
+        let cancelled = CANCELLED.with(|cancelled| {
+            cancelled
+                .borrow()
+                .as_ref()
+                .is_some_and(|cancelled| cancelled.load(Ordering::Relaxed))
+        });
+        cancelled.then(|| Dynamic::from("Script call timed out"))
+    });
+    add_host_functions(&mut ScriptHost {
+        engine: &mut engine,
+        api: Arc::clone(&api),
+    })?;
+    let ast = engine.compile(source).map_err(|err| anyhow!("Script compilation error: {err}"))?;
+
+    let mut plugin = ScriptPlugin {
+        engine: Arc::new(engine),
+        ast: Arc::new(ast),
+        api,
+        state: Arc::new(Mutex::new(Dynamic::from_map(Map::new()))),
+        id: PluginId::new(SCRIPT_PLUGIN_NAME, 0),
+        params: Vec::new(),
+        instruments: Vec::new(),
+        indicators: Vec::new(),
+        schedules: Vec::new(),
+        warmup: Vec::new(),
+        state_version: 0,
+    };
+    plugin.id = match id {
+        Some(id) => id,
+        None if plugin.has_function("id") => plugin.call_sync("id", ())?,
+        None => bail!("Script doesn't define `id()`"),
+    };
+    if plugin.has_function("params") {
+        plugin.params = plugin.call_sync("params", ())?;
+    }
+    if plugin.has_function("state_version") {
+        plugin.state_version = plugin.call_sync("state_version", ())?;
+    }
+    if !plugin.has_function("on_tick") {
+        bail!("Script doesn't define `on_tick(tick)`");
+    }
+    // the script is configured only with checked params
+    let params = checked_params(&plugin.params, params)?;
+    plugin.configure_script(&params)?;
+    Ok(plugin)
+}
+
+/// Scripts without schema parse params themselves. The script source is not a part of script schemas
+fn checked_params(schema: &[ParamSchema], params: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+    if schema.is_empty() {
+        return Ok(params.clone());
+    }
+    let mut params = params.clone();
+    let source = params.remove(SCRIPT_PARAM);
+    let mut params = validate_params(schema, &params).map_err(ScriptParamsError)?;
+    if let Some(source) = source {
+        params.insert(SCRIPT_PARAM.to_string(), source);
+    }
+    Ok(params)
+}
+
+pub(crate) struct ScriptPlugin {
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+    // set only during tick and event processing
+    api: ApiSlot,
+    // shared with the blocking thread of a running handler, so a handler cancelled by the tick
+    // timeout still writes the state back and the next call waits for it
+    state: State,
+    id: PluginId,
+    params: Vec<ParamSchema>,
+    instruments: Vec<InstrumentId>,
+    indicators: Vec<Indicator>,
+    schedules: Vec<Schedule>,
+    warmup: Vec<Warmup>,
+    state_version: u32,
+}
+
+impl ScriptPlugin {
+    fn has_function(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|function| function.name == name)
+    }
+
+    fn call_sync<T: DeserializeOwned>(&mut self, name: &str, args: impl FuncArgs) -> Result<T> {
+        let mut state = self.state.lock().unwrap();
+        let options = CallFnOptions::new().bind_this_ptr(&mut state);
+        let result = self.engine
+            .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, name, args)
+            .map_err(|err| anyhow!("Error during script function: '{name}' call: {err}"))?;
+        from_dynamic(&result).map_err(|err| anyhow!("Invalid result of script function: '{name}': {err}"))
+    }
+
+    fn configure_script(&mut self, params: &HashMap<String, String>) -> Result<()> {
+        if self.has_function("configure") {
+            self.call_sync::<Dynamic>("configure", (to_dynamic(params).unwrap(),))?;
+        }
+        self.instruments = self.call_sync("instruments", ())?;
+        if self.has_function("indicators") {
+            self.indicators = self.call_sync("indicators", ())?;
+        }
+        if self.has_function("schedules") {
+            self.schedules = self.call_sync("schedules", ())?;
+        }
+        if self.has_function("warmup") {
+            self.warmup = self.call_sync("warmup", ())?;
+        }
+        Ok(())
+    }
+
+    /// Runs an optional handler on a blocking thread, host functions wait for the internal API there
+    async fn call_with_api(&mut self, name: &'static str, args: Vec<Dynamic>, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        if !self.has_function(name) {
+            return Vec::new();
+        }
+        let _api = ApiGuard::set(&self.api, api);
+        let cancel = CancelGuard::default();
+        let cancelled = Arc::clone(&cancel.0);
+        let engine = Arc::clone(&self.engine);
+        let ast = Arc::clone(&self.ast);
+        let state = Arc::clone(&self.state);
+        let result = tokio::task::spawn_blocking(move || {
+            CANCELLED.with(|slot| *slot.borrow_mut() = Some(cancelled));
+            let mut state = state.lock().unwrap();
+            let options = CallFnOptions::new().bind_this_ptr(&mut state);
+            let result = engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &ast, name, args);
+            CANCELLED.with(|slot| *slot.borrow_mut() = None);
+            result
+        })
+            .await
+            .unwrap();
+
+        let result = result
+            .map_err(|err| anyhow!("{err}"))
+            .and_then(|value| if value.is_unit() {
+                Ok(Vec::new())
+            } else {
+                from_dynamic(&value).map_err(|err| anyhow!("Invalid actions: {err}"))
+            });
+        result.unwrap_or_else(|err| {
+            error!("Error during script function: '{name}' call, strategy: '{}:{}'. Error: '{err}'", self.id.name, self.id.version);
+            Vec::new()
+        })
+    }
+}
+
+#[async_trait]
+impl PluginApi for ScriptPlugin {
+    fn id(&self) -> PluginId {
+        self.id.clone()
+    }
+
+    fn params(&self) -> Vec<ParamSchema> {
+        self.params.clone()
+    }
+
+    fn configure(&mut self, config: &HashMap<String, String>) {
+        if let Err(err) = self.configure_script(config) {
+            error!("Error during script configuration, strategy: '{}:{}'. Error: '{err}'", self.id.name, self.id.version);
+        }
+    }
+
+    fn instruments(&self) -> Vec<InstrumentId> {
+        self.instruments.clone()
+    }
+
+    fn indicators(&self) -> Vec<Indicator> {
+        self.indicators.clone()
+    }
+
+    fn schedules(&self) -> Vec<Schedule> {
+        self.schedules.clone()
+    }
+
+    fn warmup(&self) -> Vec<Warmup> {
+        self.warmup.clone()
+    }
+
+    async fn get_state(&self) -> Option<String> {
+        Some(serde_json::to_string(&*self.state.lock().unwrap()).unwrap())
+    }
+
+    async fn set_state(&mut self, state: &str) {
+        match serde_json::from_str(state) {
+            Ok(state) => *self.state.lock().unwrap() = state,
+            Err(err) => error!("Error during script state setting: '{err}'"),
+        }
+    }
+
+    fn state_version(&self) -> u32 {
+        self.state_version
+    }
+
+    fn migrate_state(&mut self, from_version: u32, state: &str) -> Result<String> {
+        if !self.has_function("migrate_state") {
+            bail!("Script doesn't define `migrate_state(from_version, state)`, state version: '{from_version}' can't be migrated");
+        }
+        let state: serde_json::Value = serde_json::from_str(state)?;
+        let args = (from_version as i64, to_dynamic(state).unwrap());
+        let migrated: serde_json::Value = self.call_sync("migrate_state", args)?;
+        Ok(migrated.to_string())
+    }
+
+    async fn on_tick(&mut self, tick: &Tick, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.call_with_api("on_tick", vec![to_dynamic(tick).unwrap()], api).await
+    }
+
+    async fn on_start(&mut self, api: Arc<dyn PluginInternalApi>) {
+        self.call_with_api("on_start", Vec::new(), api).await;
+    }
+
+    async fn on_stop(&mut self, api: Arc<dyn PluginInternalApi>) {
+        self.call_with_api("on_stop", Vec::new(), api).await;
+    }
+
+    async fn on_order_update(&mut self, order: &Order, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.call_with_api("on_order_update", vec![to_dynamic(order).unwrap()], api).await
+    }
+
+    async fn on_position_update(&mut self, position: &Position, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.call_with_api("on_position_update", vec![to_dynamic(position).unwrap()], api).await
+    }
+
+    async fn on_candle_closed(&mut self, candle: &Candle, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.call_with_api("on_candle_closed", vec![to_dynamic(candle).unwrap()], api).await
+    }
+
+    async fn on_timer(&mut self, name: &str, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.call_with_api("on_timer", vec![Dynamic::from(name.to_string())], api).await
+    }
+}
+
+/// Clears the API slot when the handler call ends or its future is dropped by the tick timeout
+struct ApiGuard<'a>(&'a ApiSlot);
+
+impl<'a> ApiGuard<'a> {
+    fn set(slot: &'a ApiSlot, api: Arc<dyn PluginInternalApi>) -> Self {
+        *slot.lock().unwrap() = Some(api);
+        Self(slot)
+    }
+}
+
+impl Drop for ApiGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.lock().unwrap() = None;
+    }
+}
+
+/// Cancels the running handler when its future is dropped by the tick timeout, so it releases the state
+#[derive(Default)]
+struct CancelGuard(Arc<AtomicBool>);
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+struct ScriptHost<'a> {
+    engine: &'a mut Engine,
+    api: ApiSlot,
+}
+
+impl HostFunctions for ScriptHost<'_> {
+    fn register<A, R, F>(&mut self, name: &str, function: F) -> Result<()>
+        where
+            A: DeserializeOwned + Send + 'static,
+            R: Serialize + Send + Sync + 'static,
+            F: Fn(Arc<dyn PluginInternalApi>, A) -> BoxFuture<'static, R> + Send + Sync + 'static,
+    {
+        let function = Arc::new(function);
+        let api = Arc::clone(&self.api);
+        let call = move |args: Dynamic| -> Result<Dynamic, Box<EvalAltResult>> {
+            let args = from_dynamic(&args)?;
+            let api = api
+                .lock()
+                .unwrap()
+                .clone()
+                .ok_or("Plugin internal API is available only during tick and event processing")?;
+            let value = Handle::current().block_on(function(api, args));
+            to_dynamic(value)
+        };
+        // functions without arguments are called with unit
+        let call_unit = call.clone();
+        self.engine.register_fn(name, call);
+        self.engine.register_fn(name, move || call_unit(Dynamic::UNIT));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_api::NoApi;
+
+    use super::*;
+
+    const SCRIPT: &str = r#"
+        fn instruments() {
+            []
+        }
+
+        fn on_tick(tick) {
+            []
+        }
+
+        fn on_start() {
+            this.count = if "count" in this { this.count + 1 } else { 1 };
+        }
+
+        fn spin() {
+            loop {}
+        }
+
+        fn state_version() {
+            2
+        }
+
+        fn migrate_state(from_version, state) {
+            #{ count: state.counter, from: from_version }
+        }
+    "#;
+
+    fn script(source: &str) -> Result<ScriptPlugin> {
+        load(source, Some(PluginId::new("test", 1)), &HashMap::new())
+    }
+
+    #[test]
+    fn test_compilation_error() {
+        let err = script("fn on_tick(tick) {").err().unwrap();
+        assert!(err.to_string().contains("Script compilation error"));
+    }
+
+    #[test]
+    fn test_missing_on_tick() {
+        let err = script("fn instruments() { [] }").err().unwrap();
+        assert!(err.to_string().contains("on_tick"));
+    }
+
+    #[test]
+    fn test_missing_id() {
+        let err = load(SCRIPT, None, &HashMap::new()).err().unwrap();
+        assert!(err.to_string().contains("id()"));
+    }
+
+    #[tokio::test]
+    async fn test_state_round_trip() {
+        let mut plugin = script(SCRIPT).unwrap();
+        plugin.on_start(Arc::new(NoApi)).await;
+        let state = plugin.get_state().await.unwrap();
+        assert_eq!(state, r#"{"count":1}"#);
+
+        let mut restored = script(SCRIPT).unwrap();
+        restored.set_state(&state).await;
+        restored.on_start(Arc::new(NoApi)).await;
+        assert_eq!(restored.get_state().await.unwrap(), r#"{"count":2}"#);
+    }
+
+    #[test]
+    fn test_migrate_state() {
+        let mut plugin = script(SCRIPT).unwrap();
+        assert_eq!(plugin.state_version(), 2);
+        let migrated: serde_json::Value = serde_json::from_str(&plugin.migrate_state(1, r#"{"counter":3}"#).unwrap()).unwrap();
+        assert_eq!(migrated, serde_json::json!({ "count": 3, "from": 1 }));
+    }
+
+    #[test]
+    fn test_missing_migrate_state() {
+        let mut plugin = script("fn instruments() { [] } fn on_tick(tick) { [] }").unwrap();
+        assert!(plugin.migrate_state(0, "{}").is_err());
+    }
+
+    #[test]
+    fn test_cancelled_call_stops() {
+        let mut plugin = script(SCRIPT).unwrap();
+        CANCELLED.with(|slot| *slot.borrow_mut() = Some(Arc::new(AtomicBool::new(true))));
+        let err = plugin.call_sync::<Dynamic>("spin", ()).err().unwrap();
+        CANCELLED.with(|slot| *slot.borrow_mut() = None);
+        assert!(err.to_string().contains("terminated"));
+    }
+
+    #[test]
+    fn test_operations_limit() {
+        let mut plugin = script(SCRIPT).unwrap();
+        assert!(plugin.call_sync::<Dynamic>("spin", ()).is_err());
+    }
+}
@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use plugin_api::{AccountInternalApi, ActionsInternalApi, CandlesInternalApi, ClockInternalApi, DrawingsInternalApi, IndicatorsInternalApi, OrdersInternalApi, PluginInternalApi, PositionsInternalApi, StateInternalApi};
+
+/// Internal API of test plugins, their handlers never call host functions
+pub(crate) struct NoApi;
+
+impl PluginInternalApi for NoApi {
+    fn actions(&self) -> Arc<dyn ActionsInternalApi> { unimplemented!() }
+    fn orders(&self) -> Arc<dyn OrdersInternalApi> { unimplemented!() }
+    fn positions(&self) -> Arc<dyn PositionsInternalApi> { unimplemented!() }
+    fn candles(&self) -> Arc<dyn CandlesInternalApi> { unimplemented!() }
+    fn indicators(&self) -> Arc<dyn IndicatorsInternalApi> { unimplemented!() }
+    fn drawings(&self) -> Arc<dyn DrawingsInternalApi> { unimplemented!() }
+    fn account(&self) -> Arc<dyn AccountInternalApi> { unimplemented!() }
+    fn clock(&self) -> Arc<dyn ClockInternalApi> { unimplemented!() }
+    fn state(&self) -> Arc<dyn StateInternalApi> { unimplemented!() }
+}
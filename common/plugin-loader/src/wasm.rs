@@ -0,0 +1,513 @@
+//! Sandboxed plugins compiled to WebAssembly.
+//!
+//! Values are passed as JSON in the guest memory, a pointer and a length of a value are packed to `i64`
+//! as `ptr << 32 | len`, zero length means `null`. Every guest export has the `(ptr: i32, len: i32) -> i64`
+//! signature except `alloc(len: i32) -> i32` and `abi_version() -> i32`. The guest exports:
+//! `memory`, `alloc`, `abi_version`, `id`, `configure`, `instruments`, `indicators`, `schedules`,
+//! `get_state`, `set_state`, `on_tick` and `on_event`, the last two return `Vec<Action>`. Optional exports are
+//! `params`, `warmup`, `state_version` and `migrate_state`, the last one takes `(from_version, state)` and returns
+//! `Result<String, String>`.
+//! Host functions of `host.rs` are imported from the `nucane` module and have the same signature.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::lock::{Mutex, MutexGuard};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tracing::error;
+use wasmtime::{AsContext, AsContextMut, Caller, Config, Engine, Extern, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+use domain_model::{Action, Candle, Indicator, InstrumentId, Order, PluginEventKind, PluginId, Position, Tick};
+use domain_model::params::ParamSchema;
+use domain_model::schedule::Schedule;
+use domain_model::warmup::Warmup;
+use plugin_api::{PluginApi, PluginInternalApi};
+
+use crate::host::{add_host_functions, HostFunctions};
+
+/// Version of the guest interface, returned by the `abi_version` export
+pub const WASM_ABI_VERSION: i32 = 1;
+const HOST_MODULE: &str = "nucane";
+// fuel is consumed by each executed instruction, it is refilled before every plugin call.
+// Calls yield every interval, so the tick timeout stops them before the fuel is exhausted
+const FUEL_PER_CALL: u64 = 1_000_000_000;
+const FUEL_YIELD_INTERVAL: u64 = 10_000_000;
+const MAX_MEMORY: usize = 256 * 1024 * 1024;
+
+pub(crate) fn is_wasm(binary: &[u8]) -> bool {
+    binary.starts_with(b"\0asm")
+}
+
+pub(crate) fn load(binary: &[u8]) -> Result<WasmPlugin> {
+    futures::executor::block_on(WasmPlugin::new(binary))
+}
+
+struct HostState {
+    // set only during tick and event processing
+    api: Option<Arc<dyn PluginInternalApi>>,
+    limits: StoreLimits,
+}
+
+impl HostState {
+    fn api(&self) -> Result<Arc<dyn PluginInternalApi>> {
+        self.api
+            .clone()
+            .ok_or_else(|| anyhow!("Plugin internal API is available only during tick and event processing"))
+    }
+}
+
+struct WasmInstance {
+    store: Store<HostState>,
+    instance: Instance,
+}
+
+pub(crate) struct WasmPlugin {
+    instance: Mutex<WasmInstance>,
+    id: PluginId,
+    params: Vec<ParamSchema>,
+    instruments: Vec<InstrumentId>,
+    indicators: Vec<Indicator>,
+    schedules: Vec<Schedule>,
+    warmup: Vec<Warmup>,
+    state_version: u32,
+}
+
+impl WasmPlugin {
+    async fn new(binary: &[u8]) -> Result<Self> {
+        let mut config = Config::new();
+        config.async_support(true).consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(&engine, binary)?;
+        let mut linker = Linker::new(&engine);
+        add_host_functions(&mut linker)?;
+
+        let state = HostState {
+            api: None,
+            limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY).instances(1).build(),
+        };
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.fuel_async_yield_interval(Some(FUEL_YIELD_INTERVAL))?;
+        store.set_fuel(FUEL_PER_CALL)?;
+        let instance = linker.instantiate_async(&mut store, &module).await?;
+        let abi_version = instance
+            .get_typed_func::<(), i32>(&mut store, "abi_version")
+            .map_err(|_| anyhow!("WASM plugin doesn't export `abi_version`"))?
+            .call_async(&mut store, ())
+            .await?;
+        if abi_version != WASM_ABI_VERSION {
+            bail!("WASM plugin interface version: '{abi_version}' differs from host: '{WASM_ABI_VERSION}'");
+        }
+
+        let mut instance = WasmInstance { store, instance };
+        let id = instance.call("id", &()).await?;
+        let instruments = instance.call("instruments", &()).await?;
+        let indicators = instance.call("indicators", &()).await?;
+        let schedules = instance.call("schedules", &()).await?;
+        let params = if instance.has_export("params") {
+            instance.call("params", &()).await?
+        } else {
+            Vec::new()
+        };
+        let warmup = instance.warmup().await?;
+        let state_version = if instance.has_export("state_version") {
+            instance.call("state_version", &()).await?
+        } else {
+            0
+        };
+        Ok(Self {
+            instance: Mutex::new(instance),
+            id,
+            params,
+            instruments,
+            indicators,
+            schedules,
+            warmup,
+            state_version,
+        })
+    }
+
+    async fn configure_async(&mut self, config: &HashMap<String, String>) -> Result<()> {
+        let instance = self.instance.get_mut();
+        instance.call::<_, ()>("configure", config).await?;
+        self.instruments = instance.call("instruments", &()).await?;
+        self.indicators = instance.call("indicators", &()).await?;
+        self.schedules = instance.call("schedules", &()).await?;
+        self.warmup = instance.warmup().await?;
+        Ok(())
+    }
+
+    async fn call_with_api<A: Serialize + Sync>(&self, name: &str, args: &A, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        let mut instance = ApiGuard::set(self.instance.lock().await, api);
+        let result = instance.0.call(name, args).await;
+        result.unwrap_or_else(|err| {
+            error!("Error during WASM plugin call: '{name}', strategy: '{}:{}'. Error: '{err}'", self.id.name, self.id.version);
+            Vec::new()
+        })
+    }
+
+    async fn on_event(&self, event: PluginEventKind, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.call_with_api("on_event", &event, api).await
+    }
+}
+
+impl WasmInstance {
+    fn has_export(&mut self, name: &str) -> bool {
+        self.instance.get_func(&mut self.store, name).is_some()
+    }
+
+    async fn warmup(&mut self) -> Result<Vec<Warmup>> {
+        if self.has_export("warmup") {
+            self.call("warmup", &()).await
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    async fn call<A: Serialize + Sync, R: DeserializeOwned>(&mut self, name: &str, args: &A) -> Result<R> {
+        let guest = Guest::from_instance(&self.instance, &mut self.store)?;
+        let function = self.instance.get_typed_func::<(i32, i32), i64>(&mut self.store, name)?;
+        self.store.set_fuel(FUEL_PER_CALL)?;
+        let (ptr, len) = unpack(guest.write(&mut self.store, args).await?);
+        let result = function.call_async(&mut self.store, (ptr, len)).await?;
+        let (ptr, len) = unpack(result);
+        guest.read(&self.store, ptr, len)
+    }
+}
+
+#[async_trait]
+impl PluginApi for WasmPlugin {
+    fn id(&self) -> PluginId {
+        self.id.clone()
+    }
+
+    fn params(&self) -> Vec<ParamSchema> {
+        self.params.clone()
+    }
+
+    fn configure(&mut self, config: &HashMap<String, String>) {
+        if let Err(err) = futures::executor::block_on(self.configure_async(config)) {
+            error!("Error during WASM plugin configuration, strategy: '{}:{}'. Error: '{err}'", self.id.name, self.id.version);
+        }
+    }
+
+    fn instruments(&self) -> Vec<InstrumentId> {
+        self.instruments.clone()
+    }
+
+    fn indicators(&self) -> Vec<Indicator> {
+        self.indicators.clone()
+    }
+
+    fn schedules(&self) -> Vec<Schedule> {
+        self.schedules.clone()
+    }
+
+    fn warmup(&self) -> Vec<Warmup> {
+        self.warmup.clone()
+    }
+
+    async fn get_state(&self) -> Option<String> {
+        let mut instance = self.instance.lock().await;
+        instance.call("get_state", &()).await.unwrap_or_else(|err| {
+            error!("Error during WASM plugin state reading: '{err}'");
+            None
+        })
+    }
+
+    async fn set_state(&mut self, state: &str) {
+        let instance = self.instance.get_mut();
+        if let Err(err) = instance.call::<_, ()>("set_state", &state).await {
+            error!("Error during WASM plugin state setting: '{err}'");
+        }
+    }
+
+    fn state_version(&self) -> u32 {
+        self.state_version
+    }
+
+    fn migrate_state(&mut self, from_version: u32, state: &str) -> Result<String> {
+        let instance = self.instance.get_mut();
+        if !instance.has_export("migrate_state") {
+            bail!("WASM plugin doesn't export `migrate_state`, state version: '{from_version}' can't be migrated");
+        }
+        futures::executor::block_on(instance.call::<_, Result<String, String>>("migrate_state", &(from_version, state)))?
+            .map_err(|err| anyhow!("{err}"))
+    }
+
+    async fn on_tick(&mut self, tick: &Tick, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.call_with_api("on_tick", tick, api).await
+    }
+
+    async fn on_start(&mut self, api: Arc<dyn PluginInternalApi>) {
+        self.on_event(PluginEventKind::Start, api).await;
+    }
+
+    async fn on_stop(&mut self, api: Arc<dyn PluginInternalApi>) {
+        self.on_event(PluginEventKind::Stop, api).await;
+    }
+
+    async fn on_order_update(&mut self, order: &Order, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.on_event(PluginEventKind::OrderUpdate(order.clone()), api).await
+    }
+
+    async fn on_position_update(&mut self, position: &Position, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.on_event(PluginEventKind::PositionUpdate(position.clone()), api).await
+    }
+
+    async fn on_candle_closed(&mut self, candle: &Candle, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.on_event(PluginEventKind::CandleClosed(candle.clone()), api).await
+    }
+
+    async fn on_timer(&mut self, name: &str, api: Arc<dyn PluginInternalApi>) -> Vec<Action> {
+        self.on_event(PluginEventKind::Timer(name.to_string()), api).await
+    }
+}
+
+/// Clears the API of the instance when the call ends or its future is dropped by the tick timeout
+struct ApiGuard<'a>(MutexGuard<'a, WasmInstance>);
+
+impl<'a> ApiGuard<'a> {
+    fn set(mut instance: MutexGuard<'a, WasmInstance>, api: Arc<dyn PluginInternalApi>) -> Self {
+        instance.store.data_mut().api = Some(api);
+        Self(instance)
+    }
+}
+
+impl Drop for ApiGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store.data_mut().api = None;
+    }
+}
+
+/// Memory and allocator exported by the guest
+#[derive(Clone, Copy)]
+struct Guest {
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+}
+
+impl Guest {
+    fn from_instance(instance: &Instance, mut store: impl AsContextMut) -> Result<Self> {
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("WASM plugin doesn't export `memory`"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        Ok(Self { memory, alloc })
+    }
+
+    fn from_caller(caller: &mut Caller<'_, HostState>) -> Result<Self> {
+        let memory = caller
+            .get_export("memory")
+            .and_then(Extern::into_memory)
+            .ok_or_else(|| anyhow!("WASM plugin doesn't export `memory`"))?;
+        let alloc = caller
+            .get_export("alloc")
+            .and_then(Extern::into_func)
+            .ok_or_else(|| anyhow!("WASM plugin doesn't export `alloc`"))?
+            .typed::<i32, i32>(&caller)?;
+        Ok(Self { memory, alloc })
+    }
+
+    async fn write<T: Serialize + Sync>(&self, mut store: impl AsContextMut<Data=HostState> + Send, value: &T) -> Result<i64> {
+        let bytes = serde_json::to_vec(value)?;
+        let ptr = self.alloc.call_async(&mut store, bytes.len() as i32).await?;
+        self.memory.write(&mut store, ptr as u32 as usize, &bytes)?;
+        Ok(pack(ptr, bytes.len() as i32))
+    }
+
+    fn read<T: DeserializeOwned>(&self, store: impl AsContext, ptr: i32, len: i32) -> Result<T> {
+        if len == 0 {
+            return Ok(serde_json::from_slice(b"null")?);
+        }
+        let mut bytes = vec![0; len as u32 as usize];
+        self.memory.read(&store, ptr as u32 as usize, &mut bytes)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+fn pack(ptr: i32, len: i32) -> i64 {
+    ((ptr as u32 as i64) << 32) | len as u32 as i64
+}
+
+fn unpack(value: i64) -> (i32, i32) {
+    ((value >> 32) as i32, value as i32)
+}
+
+impl HostFunctions for Linker<HostState> {
+    fn register<A, R, F>(&mut self, name: &str, function: F) -> Result<()>
+        where
+            A: DeserializeOwned + Send + 'static,
+            R: Serialize + Send + Sync + 'static,
+            F: Fn(Arc<dyn PluginInternalApi>, A) -> BoxFuture<'static, R> + Send + Sync + 'static,
+    {
+        self.func_wrap_async(HOST_MODULE, name, move |mut caller: Caller<'_, HostState>, (ptr, len): (i32, i32)| {
+            let request = Guest::from_caller(&mut caller).and_then(|guest| {
+                let args = guest.read(&caller, ptr, len)?;
+                Ok((guest, function(caller.data().api()?, args)))
+            });
+            Box::new(async move {
+                let (guest, response) = request?;
+                let value = response.await;
+                guest.write(&mut caller, &value).await
+            })
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use wasmtime::Trap;
+
+    use crate::test_api::NoApi;
+
+    use super::*;
+
+    // guest with a bump allocator, `on_event` never returns and `grow` grows the memory above the limit
+    const PLUGIN: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (global $heap (mut i32) (i32.const 1024))
+            (global $state_ptr (mut i32) (i32.const 0))
+            (global $state_len (mut i32) (i32.const 0))
+            (data (i32.const 16) "{\"name\":\"wat\",\"version\":1}")
+            (data (i32.const 64) "[]")
+            (data (i32.const 80) "2")
+            (data (i32.const 96) "{\"Ok\":\"{\\\"migrated\\\":true}\"}")
+
+            (func $pack (param $ptr i32) (param $len i32) (result i64)
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                    (i64.extend_i32_u (local.get $len))))
+
+            (func (export "alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $heap))
+                (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                (if (i32.gt_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536)))
+                    (then
+                        (if (i32.eq (memory.grow (i32.add (i32.div_u (local.get $len) (i32.const 65536)) (i32.const 1))) (i32.const -1))
+                            (then unreachable))))
+                (local.get $ptr))
+
+            (func (export "abi_version") (result i32)
+                (i32.const 1))
+
+            (func (export "id") (param i32 i32) (result i64)
+                (call $pack (i32.const 16) (i32.const 26)))
+
+            (func $empty (param i32 i32) (result i64)
+                (call $pack (i32.const 64) (i32.const 2)))
+            (export "instruments" (func $empty))
+            (export "indicators" (func $empty))
+            (export "schedules" (func $empty))
+            (export "on_tick" (func $empty))
+
+            (func (export "configure") (param i32 i32) (result i64)
+                (i64.const 0))
+
+            (func (export "state_version") (param i32 i32) (result i64)
+                (call $pack (i32.const 80) (i32.const 1)))
+
+            (func (export "migrate_state") (param i32 i32) (result i64)
+                (call $pack (i32.const 96) (i32.const 28)))
+
+            (func (export "set_state") (param $ptr i32) (param $len i32) (result i64)
+                (global.set $state_ptr (local.get $ptr))
+                (global.set $state_len (local.get $len))
+                (i64.const 0))
+
+            (func (export "get_state") (param i32 i32) (result i64)
+                (call $pack (global.get $state_ptr) (global.get $state_len)))
+
+            (func (export "on_event") (param i32 i32) (result i64)
+                (loop $spin (br $spin))
+                (i64.const 0))
+
+            (func (export "grow") (param i32 i32) (result i64)
+                (if (i32.eq (memory.grow (i32.const 4097)) (i32.const -1))
+                    (then unreachable))
+                (i64.const 0)))
+    "#;
+
+    async fn plugin() -> WasmPlugin {
+        WasmPlugin::new(PLUGIN.as_bytes()).await.unwrap()
+    }
+
+    fn trap(err: &anyhow::Error) -> Option<Trap> {
+        err.downcast_ref::<Trap>().copied()
+    }
+
+    #[test]
+    fn test_pack() {
+        assert_eq!(unpack(pack(1024, 26)), (1024, 26));
+        assert_eq!(unpack(pack(i32::MAX, 0)), (i32::MAX, 0));
+    }
+
+    #[tokio::test]
+    async fn test_load_reads_exports() {
+        let plugin = plugin().await;
+        assert_eq!(plugin.id(), PluginId::new("wat", 1));
+        assert!(plugin.instruments().is_empty());
+        assert!(plugin.params().is_empty());
+        assert_eq!(plugin.state_version(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_state_round_trip() {
+        let mut plugin = plugin().await;
+        assert_eq!(plugin.get_state().await, None);
+        plugin.set_state(r#"{"count":1}"#).await;
+        assert_eq!(plugin.get_state().await, Some(r#"{"count":1}"#.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_on_tick_actions() {
+        let mut plugin = plugin().await;
+        let tick: Tick = serde_json::from_value(serde_json::json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "simulation_id": null,
+            "timestamp": "2024-01-01T00:00:00Z",
+            "instrument_id": { "exchange": "OKX", "market_type": "Spot", "pair": { "target": "BTC", "source": "USDT" } },
+            "price": 1.0,
+        }))
+            .unwrap();
+        assert!(plugin.on_tick(&tick, Arc::new(NoApi)).await.is_empty());
+        assert!(plugin.instance.lock().await.store.data().api.is_none());
+    }
+
+    #[test]
+    fn test_migrate_state() {
+        let mut plugin = futures::executor::block_on(plugin());
+        assert_eq!(plugin.migrate_state(1, "{}").unwrap(), r#"{"migrated":true}"#);
+    }
+
+    #[tokio::test]
+    async fn test_fuel_exhaustion() {
+        let plugin = plugin().await;
+        let err = plugin.instance.lock().await.call::<_, Vec<Action>>("on_event", &PluginEventKind::Start).await.unwrap_err();
+        assert_eq!(trap(&err), Some(Trap::OutOfFuel));
+    }
+
+    #[tokio::test]
+    async fn test_memory_limit() {
+        let plugin = plugin().await;
+        let err = plugin.instance.lock().await.call::<_, ()>("grow", &()).await.unwrap_err();
+        assert_eq!(trap(&err), Some(Trap::UnreachableCodeReached));
+    }
+
+    #[tokio::test]
+    async fn test_api_cleared_after_timeout() {
+        let mut plugin = plugin().await;
+        let started = tokio::time::timeout(Duration::from_millis(10), plugin.on_start(Arc::new(NoApi))).await;
+        assert!(started.is_err());
+        assert!(plugin.instance.lock().await.store.data().api.is_none());
+    }
+}
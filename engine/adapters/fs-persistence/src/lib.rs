@@ -0,0 +1,3 @@
+pub use repositories::FsStateRepository;
+
+mod repositories;
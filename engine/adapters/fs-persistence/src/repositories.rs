@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use tracing::{info, warn};
+
+use domain_model::state::StateSnapshot;
+use engine_persistence_api::StateRepository;
+
+const STATE_FOLDER_PATH: &str = "nucane/state";
+// single file of all states written by previous versions
+const LEGACY_STATE_FILE_NAME: &str = "state.json";
+const SNAPSHOT_EXTENSION: &str = "json";
+const TEMP_EXTENSION: &str = "tmp";
+
+/// Snapshots are files `<path>/<state id>/<version>.json`, each file is written to a temporary file
+/// first and renamed, so a crash never leaves a partially written snapshot
+pub struct FsStateRepository {
+    path: PathBuf,
+}
+
+impl Default for FsStateRepository {
+    fn default() -> Self {
+        let mut path = env::temp_dir();
+        path.push(STATE_FOLDER_PATH);
+        Self::new(path)
+    }
+}
+
+impl FsStateRepository {
+    pub fn new(path: PathBuf) -> Self {
+        import_legacy_state(&path);
+        Self { path }
+    }
+
+    fn state_path(&self, state_id: &str) -> PathBuf {
+        self.path.join(state_id)
+    }
+}
+
+#[async_trait]
+impl StateRepository for FsStateRepository {
+    async fn save(&self, snapshot: &StateSnapshot, history: u64) -> Result<()> {
+        let state_path = self.state_path(&snapshot.state_id);
+        tokio::fs::create_dir_all(&state_path).await?;
+        let snapshot_path = state_path.join(format!("{}.{SNAPSHOT_EXTENSION}", snapshot.version));
+        let temp_path = snapshot_path.with_extension(TEMP_EXTENSION);
+        tokio::fs::write(&temp_path, serde_json::to_vec(snapshot)?).await?;
+        tokio::fs::rename(&temp_path, &snapshot_path).await?;
+
+        if history > 0 {
+            for version in versions(&state_path).await? {
+                if version <= snapshot.version - history as i64 {
+                    tokio::fs::remove_file(state_path.join(format!("{version}.{SNAPSHOT_EXTENSION}"))).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_all(&self, state_id: &str) -> Result<Vec<StateSnapshot>> {
+        let state_path = self.state_path(state_id);
+        if !tokio::fs::try_exists(&state_path).await? {
+            return Ok(Vec::new());
+        }
+        let mut snapshots = Vec::new();
+        for version in versions(&state_path).await? {
+            let snapshot_path = state_path.join(format!("{version}.{SNAPSHOT_EXTENSION}"));
+            let content = tokio::fs::read(&snapshot_path).await?;
+            // a corrupt snapshot is skipped, the previous version is used instead
+            match serde_json::from_slice(&content) {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(err) => warn!("Corrupt state snapshot: '{}' is skipped: {err}", snapshot_path.display()),
+            }
+        }
+        Ok(snapshots)
+    }
+
+    async fn delete(&self, state_id: &str) -> Result<()> {
+        let state_path = self.state_path(state_id);
+        if tokio::fs::try_exists(&state_path).await? {
+            tokio::fs::remove_dir_all(state_path).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Saved versions in the state folder in ascending order, temporary files are ignored
+async fn versions(state_path: &Path) -> Result<Vec<i64>> {
+    let mut versions = Vec::new();
+    let mut entries = tokio::fs::read_dir(state_path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some(SNAPSHOT_EXTENSION) {
+            continue;
+        }
+        if let Some(version) = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse().ok()) {
+            versions.push(version);
+        }
+    }
+    versions.sort();
+    Ok(versions)
+}
+
+/// Saves states of the legacy single file as first versions and renames the file, so it's imported once
+fn import_legacy_state(path: &Path) {
+    let legacy_path = path.join(LEGACY_STATE_FILE_NAME);
+    let Ok(content) = fs::read_to_string(&legacy_path) else {
+        return;
+    };
+    let states: HashMap<String, String> = match serde_json::from_str(&content) {
+        Ok(states) => states,
+        Err(err) => {
+            warn!("Legacy state file: '{}' is not imported: {err}", legacy_path.display());
+            return;
+        }
+    };
+    for (state_id, state) in states {
+        let state_path = path.join(&state_id);
+        if state_path.exists() {
+            continue;
+        }
+        let snapshot = StateSnapshot {
+            state_id,
+            version: 1,
+            timestamp: Utc::now(),
+            state,
+        };
+        let result = fs::create_dir_all(&state_path)
+            .and_then(|_| fs::write(state_path.join(format!("1.{SNAPSHOT_EXTENSION}")), serde_json::to_vec(&snapshot).unwrap()));
+        if let Err(err) = result {
+            warn!("State: '{}' is not imported: {err}", snapshot.state_id);
+        }
+    }
+    match fs::rename(&legacy_path, legacy_path.with_extension("json.bak")) {
+        Ok(_) => info!("Legacy state file: '{}' is imported", legacy_path.display()),
+        Err(err) => warn!("Legacy state file: '{}' is not renamed after import: {err}", legacy_path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("nucane-fs-state-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn snapshot(state_id: &str, version: i64, state: &str) -> StateSnapshot {
+        StateSnapshot {
+            state_id: state_id.to_string(),
+            version,
+            timestamp: Utc::now(),
+            state: state.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_snapshot_is_skipped() {
+        let path = test_path("corrupt");
+        let repository = FsStateRepository::new(path.clone());
+        repository.save(&snapshot("state", 1, "a"), 0).await.unwrap();
+        repository.save(&snapshot("state", 2, "b"), 0).await.unwrap();
+        fs::write(path.join("state").join("2.json"), "{ broken").unwrap();
+        fs::write(path.join("state").join("3.tmp"), "{ unfinished").unwrap();
+
+        let snapshots = repository.get_all("state").await.unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].state, "a");
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_history_is_pruned() {
+        let path = test_path("pruned");
+        let repository = FsStateRepository::new(path.clone());
+        for version in 1..=3 {
+            repository.save(&snapshot("state", version, "a"), 2).await.unwrap();
+        }
+
+        let versions: Vec<_> = repository
+            .get_all("state")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| snapshot.version)
+            .collect();
+        assert_eq!(versions, vec![2, 3]);
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_legacy_state_is_imported_once() {
+        let path = test_path("legacy");
+        fs::write(path.join(LEGACY_STATE_FILE_NAME), r#"{"first": "a", "second": "b"}"#).unwrap();
+        let repository = FsStateRepository::new(path.clone());
+
+        let snapshots = repository.get_all("first").await.unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!((snapshots[0].version, snapshots[0].state.as_str()), (1, "a"));
+        assert_eq!(repository.get_all("second").await.unwrap()[0].state, "b");
+        assert!(!path.join(LEGACY_STATE_FILE_NAME).exists());
+        assert!(path.join("state.json.bak").exists());
+        fs::remove_dir_all(path).unwrap();
+    }
+}
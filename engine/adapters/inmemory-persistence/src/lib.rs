@@ -0,0 +1,4 @@
+pub use repositories::InMemoryDeploymentRepository;
+pub use repositories::InMemoryStateRepository;
+
+mod repositories;
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use domain_model::DeploymentInfo;
+use domain_model::state::StateSnapshot;
+use engine_persistence_api::{DeploymentRepository, StateRepository};
+
+#[derive(Default)]
+pub struct InMemoryDeploymentRepository {
+    storage: Arc<Mutex<Vec<DeploymentInfo>>>,
+}
+
+#[async_trait]
+impl DeploymentRepository for InMemoryDeploymentRepository {
+    async fn get_all(&self) -> Vec<DeploymentInfo> {
+        self.storage.lock().await.clone()
+    }
+
+    async fn save(&self, deployment: &DeploymentInfo) -> Result<()> {
+        let mut storage = self.storage.lock().await;
+        match storage.iter_mut().find(|existing| existing.id == deployment.id) {
+            Some(existing) => *existing = deployment.clone(),
+            None => storage.push(deployment.clone()),
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        self.storage
+            .lock()
+            .await
+            .retain(|deployment| deployment.id != id);
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryStateRepository {
+    storage: Arc<Mutex<HashMap<String, Vec<StateSnapshot>>>>,
+}
+
+#[async_trait]
+impl StateRepository for InMemoryStateRepository {
+    async fn save(&self, snapshot: &StateSnapshot, history: u64) -> Result<()> {
+        let mut storage = self.storage.lock().await;
+        let snapshots = storage.entry(snapshot.state_id.clone()).or_default();
+        snapshots.retain(|existing| existing.version != snapshot.version);
+        snapshots.push(snapshot.clone());
+        snapshots.sort_by_key(|snapshot| snapshot.version);
+        if history > 0 && snapshots.len() > history as usize {
+            snapshots.drain(..snapshots.len() - history as usize);
+        }
+        Ok(())
+    }
+
+    async fn get_all(&self, state_id: &str) -> Result<Vec<StateSnapshot>> {
+        let storage = self.storage.lock().await;
+        Ok(storage.get(state_id).cloned().unwrap_or_default())
+    }
+
+    async fn delete(&self, state_id: &str) -> Result<()> {
+        self.storage.lock().await.remove(state_id);
+        Ok(())
+    }
+}
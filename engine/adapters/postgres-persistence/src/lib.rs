@@ -0,0 +1,4 @@
+pub mod entities;
+pub mod initiator;
+pub mod migrations;
+pub mod repositories;
@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Deployment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Deployment::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Deployment::Status).json().not_null())
+                    .col(ColumnDef::new(Deployment::SimulationId).uuid())
+                    .col(ColumnDef::new(Deployment::StateId).uuid())
+                    .col(ColumnDef::new(Deployment::PluginId).json().not_null())
+                    .col(ColumnDef::new(Deployment::Params).json().not_null())
+                    .col(
+                        ColumnDef::new(Deployment::Subscriptions)
+                            .json()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Deployment::Indicators).json().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Deployment::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Deployment {
+    Table,
+    Id,
+    Status,
+    SimulationId,
+    StateId,
+    PluginId,
+    Params,
+    Subscriptions,
+    Indicators,
+}
@@ -0,0 +1,20 @@
+use sea_orm_migration::{MigrationTrait, MigratorTrait};
+
+use crate::migrations::{m20231101_000001_create_tables, m20231105_000001_add_deployment_execution, m20231110_000001_add_deployment_schedules, m20231115_000001_add_deployment_risk, m20231120_000001_add_deployment_mode, m20231205_000001_add_deployment_upgrade, m20231210_000001_add_deployment_warmup};
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20231101_000001_create_tables::Migration),
+            Box::new(m20231105_000001_add_deployment_execution::Migration),
+            Box::new(m20231110_000001_add_deployment_schedules::Migration),
+            Box::new(m20231115_000001_add_deployment_risk::Migration),
+            Box::new(m20231120_000001_add_deployment_mode::Migration),
+            Box::new(m20231205_000001_add_deployment_upgrade::Migration),
+            Box::new(m20231210_000001_add_deployment_warmup::Migration),
+        ]
+    }
+}
@@ -0,0 +1,11 @@
+pub use migrator::Migrator;
+
+mod m20231101_000001_create_tables;
+mod m20231105_000001_add_deployment_execution;
+mod m20231110_000001_add_deployment_schedules;
+mod m20231115_000001_add_deployment_risk;
+mod m20231120_000001_add_deployment_mode;
+mod m20231205_000001_add_deployment_upgrade;
+mod m20231210_000001_add_deployment_warmup;
+
+mod migrator;
@@ -0,0 +1,103 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use sea_orm::{ActiveValue, ConnectionTrait, EntityTrait, sea_query};
+use serde_json::json;
+use uuid::Uuid;
+
+use domain_model::DeploymentInfo;
+use engine_persistence_api::DeploymentRepository;
+
+use crate::entities::*;
+use crate::entities::prelude::Deployment;
+
+pub struct DeploymentPostgresRepository<T: ConnectionTrait> {
+    db: Arc<T>,
+}
+
+impl<T: ConnectionTrait> DeploymentPostgresRepository<T> {
+    pub fn new(db: Arc<T>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl<T: ConnectionTrait + Send + 'static> DeploymentRepository for DeploymentPostgresRepository<T> {
+    async fn get_all(&self) -> Vec<DeploymentInfo> {
+        Deployment::find()
+            .all(self.db.deref())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|model| DeploymentInfo {
+                id: model.id,
+                status: serde_json::from_value(model.status).unwrap(),
+                simulation_id: model.simulation_id,
+                state_id: model.state_id,
+                plugin_id: serde_json::from_value(model.plugin_id).unwrap(),
+                params: serde_json::from_value(model.params).unwrap(),
+                subscriptions: serde_json::from_value(model.subscriptions).unwrap(),
+                indicators: serde_json::from_value(model.indicators).unwrap(),
+                schedules: serde_json::from_value(model.schedules).unwrap(),
+                warmup: serde_json::from_value(model.warmup).unwrap(),
+                // readiness is checked again when the deployment is restored
+                ready: false,
+                warmup_gaps: Vec::new(),
+                execution: serde_json::from_value(model.execution).unwrap(),
+                risk: serde_json::from_value(model.risk).unwrap(),
+                mode: serde_json::from_value(model.mode).unwrap(),
+                upgrade: serde_json::from_value(model.upgrade).unwrap(),
+            })
+            .collect()
+    }
+
+    async fn save(&self, deployment: &DeploymentInfo) -> Result<()> {
+        let deployment = deployment::ActiveModel {
+            id: ActiveValue::Set(deployment.id),
+            status: ActiveValue::Set(json!(deployment.status)),
+            simulation_id: ActiveValue::Set(deployment.simulation_id),
+            state_id: ActiveValue::Set(deployment.state_id),
+            plugin_id: ActiveValue::Set(json!(deployment.plugin_id)),
+            params: ActiveValue::Set(json!(deployment.params)),
+            subscriptions: ActiveValue::Set(json!(deployment.subscriptions)),
+            indicators: ActiveValue::Set(json!(deployment.indicators)),
+            schedules: ActiveValue::Set(json!(deployment.schedules)),
+            warmup: ActiveValue::Set(json!(deployment.warmup)),
+            execution: ActiveValue::Set(json!(deployment.execution)),
+            risk: ActiveValue::Set(json!(deployment.risk)),
+            mode: ActiveValue::Set(json!(deployment.mode)),
+            upgrade: ActiveValue::Set(json!(deployment.upgrade)),
+        };
+        Deployment::insert(deployment)
+            .on_conflict(
+                sea_query::OnConflict::column(deployment::Column::Id)
+                    .update_columns(vec![
+                        deployment::Column::Status,
+                        deployment::Column::StateId,
+                        deployment::Column::PluginId,
+                        deployment::Column::Params,
+                        deployment::Column::Subscriptions,
+                        deployment::Column::Indicators,
+                        deployment::Column::Schedules,
+                        deployment::Column::Warmup,
+                        deployment::Column::Execution,
+                        deployment::Column::Risk,
+                        deployment::Column::Mode,
+                        deployment::Column::Upgrade,
+                    ])
+                    .to_owned(),
+            )
+            .exec(self.db.deref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        Deployment::delete_by_id(id)
+            .exec(self.db.deref())
+            .await?;
+        Ok(())
+    }
+}
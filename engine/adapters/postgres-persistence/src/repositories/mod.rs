@@ -0,0 +1,3 @@
+pub use deployment::DeploymentPostgresRepository;
+
+mod deployment;
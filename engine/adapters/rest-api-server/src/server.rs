@@ -3,15 +3,24 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use axum::{Json, Router};
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::http::StatusCode;
-use axum::routing::{delete, get, post, put};
-use tracing::debug;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, patch, post, put};
+use chrono::{TimeZone, Utc};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tracing::{debug, warn};
 use uuid::Uuid;
 
-use domain_model::{Action, DeploymentInfo, NewDeployment, PluginId, Tick};
+use domain_model::{Action, DeploymentInfo, DeploymentPatch, KillSwitch, KillSwitchReport, NewDeployment, PluginEvent, PluginId, RiskRejection, Tick};
+use domain_model::events::EngineEvent;
+use domain_model::shadow::ShadowReport;
+use domain_model::state::StateSnapshot;
 use engine_core_api::api::{EngineApi, EngineError};
-use engine_rest_api::endpoints::{DELETE_DEPLOYMENT, GET_DEPLOYMENTS, POST_CREATE_ACTIONS, POST_CREATE_DEPLOYMENTS, PUT_UPDATE_PLUGIN};
+use engine_rest_api::endpoints::{DELETE_DEPLOYMENT, GET_DEPLOYMENT_STATE, GET_DEPLOYMENTS, GET_EVENTS_STREAM, GET_RISK_REJECTIONS, GET_SHADOW_REPORT, GET_STATE_HISTORY, PATCH_DEPLOYMENT, POST_CREATE_ACTIONS, POST_CREATE_ACTIONS_BATCH, POST_CREATE_DEPLOYMENTS, POST_CREATE_EVENT, POST_KILL_SWITCH, POST_PAUSE_DEPLOYMENT, POST_RESUME_DEPLOYMENT, POST_ROLLBACK_PLUGIN, POST_ROLLBACK_STATE, PUT_UPDATE_PLUGIN};
+use engine_rest_api::path_queries::{EventsQuery, RejectionsQuery, RollbackStateQuery, ShadowReportQuery};
 
 pub async fn run(port: u16, engine: impl EngineApi) {
     let engine = Arc::new(engine);
@@ -19,8 +28,22 @@ pub async fn run(port: u16, engine: impl EngineApi) {
         .route(GET_DEPLOYMENTS, get(get_deployments))
         .route(POST_CREATE_DEPLOYMENTS, post(create_deployment))
         .route(DELETE_DEPLOYMENT, delete(delete_deployment))
+        .route(PATCH_DEPLOYMENT, patch(update_deployment))
+        .route(POST_PAUSE_DEPLOYMENT, post(pause_deployment))
+        .route(POST_RESUME_DEPLOYMENT, post(resume_deployment))
+        .route(GET_DEPLOYMENT_STATE, get(get_deployment_state))
+        .route(GET_STATE_HISTORY, get(get_state_history))
+        .route(POST_ROLLBACK_STATE, post(rollback_state))
+        .route(POST_ROLLBACK_PLUGIN, post(rollback_plugin))
+        .route(GET_SHADOW_REPORT, get(get_shadow_report))
         .route(POST_CREATE_ACTIONS, post(create_actions))
+        .route(POST_CREATE_ACTIONS_BATCH, post(create_actions_batch))
+        .route(POST_CREATE_EVENT, post(create_event))
+        .route(GET_RISK_REJECTIONS, get(get_risk_rejections))
+        .route(POST_KILL_SWITCH, post(kill_switch))
+        .route(GET_EVENTS_STREAM, get(stream_events))
         .route(PUT_UPDATE_PLUGIN, put(update_plugin))
+        .route(metrics::GET_METRICS, get(metrics::handler))
         .with_state(engine);
 
     let address = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), port);
@@ -35,17 +58,23 @@ async fn get_deployments(State(engine): State<Arc<dyn EngineApi>>) -> Json<Vec<D
     Json(result)
 }
 
-async fn create_deployment(State(engine): State<Arc<dyn EngineApi>>, Json(request): Json<Vec<NewDeployment>>) -> Result<Json<Vec<DeploymentInfo>>, StatusCode> {
+async fn create_deployment(State(engine): State<Arc<dyn EngineApi>>, Json(request): Json<Vec<NewDeployment>>) -> Result<Json<Vec<DeploymentInfo>>, Response> {
     let result = engine.deploy(&request)
         .await
-        .map_err(|err|
-            match err {
-                EngineError::PluginNotFound => StatusCode::NOT_FOUND,
-                EngineError::PluginLoadingError => StatusCode::INTERNAL_SERVER_ERROR
-            })?;
+        .map_err(error_response)?;
     Ok(Json(result))
 }
 
+fn error_response(err: EngineError) -> Response {
+    match err {
+        EngineError::PluginNotFound => (StatusCode::NOT_FOUND, err.to_string()).into_response(),
+        EngineError::PluginLoadingError => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        EngineError::InvalidScript(_) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+        EngineError::InvalidParams(errors) => (StatusCode::BAD_REQUEST, Json(errors)).into_response(),
+        EngineError::StateMigrationError(_) => (StatusCode::CONFLICT, err.to_string()).into_response(),
+    }
+}
+
 async fn delete_deployment(State(engine): State<Arc<dyn EngineApi>>, Path(deployment_id): Path<String>) -> Json<Option<DeploymentInfo>> {
     debug!("Request to delete deployment with id: '{deployment_id}'");
     let deployment_id = Uuid::from_str(&deployment_id).unwrap();
@@ -54,11 +83,119 @@ async fn delete_deployment(State(engine): State<Arc<dyn EngineApi>>, Path(deploy
     Json(deployment_info)
 }
 
+async fn update_deployment(State(engine): State<Arc<dyn EngineApi>>, Path(deployment_id): Path<Uuid>, Json(request): Json<DeploymentPatch>) -> Result<Json<DeploymentInfo>, Response> {
+    let deployment_info = engine.update_deployment(deployment_id, request)
+        .await
+        .map_err(error_response)?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    Ok(Json(deployment_info))
+}
+
+async fn pause_deployment(State(engine): State<Arc<dyn EngineApi>>, Path(deployment_id): Path<Uuid>) -> Result<Json<DeploymentInfo>, StatusCode> {
+    let deployment_info = engine.pause_deployment(deployment_id)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(deployment_info))
+}
+
+async fn resume_deployment(State(engine): State<Arc<dyn EngineApi>>, Path(deployment_id): Path<Uuid>) -> Result<Json<DeploymentInfo>, StatusCode> {
+    let deployment_info = engine.resume_deployment(deployment_id)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(deployment_info))
+}
+
+async fn get_deployment_state(State(engine): State<Arc<dyn EngineApi>>, Path(deployment_id): Path<Uuid>) -> Json<Option<String>> {
+    let state = engine.get_deployment_state(deployment_id).await;
+    Json(state)
+}
+
+async fn get_state_history(State(engine): State<Arc<dyn EngineApi>>, Path(deployment_id): Path<Uuid>) -> Result<Json<Vec<StateSnapshot>>, StatusCode> {
+    let history = engine.get_state_history(deployment_id)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(history))
+}
+
+async fn rollback_state(State(engine): State<Arc<dyn EngineApi>>, Path(deployment_id): Path<Uuid>, Query(query): Query<RollbackStateQuery>) -> Result<Json<String>, StatusCode> {
+    let state = engine.rollback_state(deployment_id, query.version)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(state))
+}
+
+async fn rollback_plugin(State(engine): State<Arc<dyn EngineApi>>, Path(deployment_id): Path<Uuid>) -> Result<Json<DeploymentInfo>, Response> {
+    debug!("Request to rollback plugin of deployment with id: '{deployment_id}'");
+    let deployment = engine.rollback_plugin(deployment_id)
+        .await
+        .map_err(error_response)?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    Ok(Json(deployment))
+}
+
+async fn get_shadow_report(State(engine): State<Arc<dyn EngineApi>>, Path(deployment_id): Path<Uuid>, Query(query): Query<ShadowReportQuery>) -> Result<Json<ShadowReport>, StatusCode> {
+    let from = query.from.map(|millis| Utc.timestamp_millis_opt(millis).unwrap());
+    let to = query.to.map(|millis| Utc.timestamp_millis_opt(millis).unwrap());
+    let report = engine.get_shadow_report(deployment_id, query.live_id, from, to)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(report))
+}
+
 async fn create_actions(State(engine): State<Arc<dyn EngineApi>>, Json(request): Json<Tick>) -> Json<Vec<Action>> {
     let response = engine.get_actions(&request).await;
     Json(response)
 }
 
-async fn update_plugin(State(engine): State<Arc<dyn EngineApi>>, Json(request): Json<PluginId>) {
-    engine.update_plugin(request).await;
+async fn create_actions_batch(State(engine): State<Arc<dyn EngineApi>>, Json(request): Json<Vec<Tick>>) -> Json<Vec<Vec<Action>>> {
+    let response = engine.get_actions_batch(&request).await;
+    Json(response)
+}
+
+async fn create_event(State(engine): State<Arc<dyn EngineApi>>, Json(request): Json<PluginEvent>) -> Json<Vec<Action>> {
+    let response = engine.handle_event(request).await;
+    Json(response)
+}
+
+async fn get_risk_rejections(State(engine): State<Arc<dyn EngineApi>>, Query(query): Query<RejectionsQuery>) -> Json<Vec<RiskRejection>> {
+    let rejections = engine.get_risk_rejections(query.deployment_id).await;
+    Json(rejections)
+}
+
+async fn kill_switch(State(engine): State<Arc<dyn EngineApi>>, Json(request): Json<KillSwitch>) -> Json<KillSwitchReport> {
+    let report = engine.kill_switch(request).await;
+    Json(report)
+}
+
+async fn stream_events(State(engine): State<Arc<dyn EngineApi>>, Query(query): Query<EventsQuery>, ws: WebSocketUpgrade) -> Response {
+    let receiver = engine.subscribe_events();
+    ws.on_upgrade(move |socket| send_events(socket, receiver, query.deployment_id))
+}
+
+async fn send_events(mut socket: WebSocket, mut receiver: Receiver<EngineEvent>, deployment_id: Option<Uuid>) {
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("Events stream subscriber is too slow, '{skipped}' events are skipped");
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if deployment_id.map_or(true, |id| event.deployment_id == id) {
+            let message = Message::Text(serde_json::to_string(&event).unwrap());
+            if socket.send(message).await.is_err() {
+                debug!("Events stream subscriber is disconnected");
+                break;
+            }
+        }
+    }
+}
+
+// canary rollouts wait for their observation, so the upgrade runs in the background
+async fn update_plugin(State(engine): State<Arc<dyn EngineApi>>, Json(request): Json<PluginId>) -> StatusCode {
+    tokio::spawn(async move {
+        engine.update_plugin(request).await;
+    });
+    StatusCode::ACCEPTED
 }
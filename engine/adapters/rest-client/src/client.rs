@@ -1,13 +1,26 @@
 use async_trait::async_trait;
-use reqwest::{Client, Url};
-use tracing::trace;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use reqwest::{Client, Response, StatusCode, Url};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::Receiver;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{error, trace};
 use uuid::Uuid;
 
-use domain_model::{Action, DeploymentInfo, NewDeployment, PluginId, Tick};
-use engine_core_api::api::{EngineApi, EngineError};
+use domain_model::{Action, DeploymentInfo, DeploymentPatch, KillSwitch, KillSwitchReport, NewDeployment, PluginEvent, PluginId, RiskRejection, Tick};
+use domain_model::events::EngineEvent;
+use domain_model::params::ParamError;
+use domain_model::shadow::ShadowReport;
+use domain_model::state::StateSnapshot;
+use engine_core_api::api::{EngineApi, EngineError, EVENTS_CAPACITY};
 use engine_rest_api::endpoints::{
-    DELETE_DEPLOYMENT, GET_DEPLOYMENTS, POST_CREATE_ACTIONS, POST_CREATE_DEPLOYMENTS,
+    DELETE_DEPLOYMENT, GET_DEPLOYMENT_STATE, GET_DEPLOYMENTS, GET_EVENTS_STREAM, GET_RISK_REJECTIONS, GET_SHADOW_REPORT, GET_STATE_HISTORY, PATCH_DEPLOYMENT,
+    POST_CREATE_ACTIONS, POST_CREATE_ACTIONS_BATCH, POST_CREATE_DEPLOYMENTS, POST_CREATE_EVENT, POST_KILL_SWITCH, POST_PAUSE_DEPLOYMENT,
+    POST_RESUME_DEPLOYMENT, POST_ROLLBACK_PLUGIN, POST_ROLLBACK_STATE, PUT_UPDATE_PLUGIN,
 };
+use engine_rest_api::path_queries::{RejectionsQuery, RollbackStateQuery, ShadowReportQuery};
 
 pub struct EngineRestClient {
     url: String,
@@ -56,11 +69,11 @@ impl EngineApi for EngineRestClient {
             .json(&deployments)
             .send()
             .await
-            .unwrap()
-            .json()
-            .await
             .unwrap();
-        Ok(response)
+        match response.status() {
+            status if !status.is_success() => Err(engine_error(response).await),
+            _ => Ok(response.json().await.unwrap()),
+        }
     }
 
     async fn get_actions(&self, tick: &Tick) -> Vec<Action> {
@@ -78,6 +91,42 @@ impl EngineApi for EngineRestClient {
             .unwrap()
     }
 
+    async fn get_actions_batch(&self, ticks: &[Tick]) -> Vec<Vec<Action>> {
+        let endpoint = format!("{}{}", self.url, POST_CREATE_ACTIONS_BATCH);
+        let url = Url::parse(&endpoint).unwrap();
+        trace!("Request url: {url:?}");
+        self.client
+            .post(url)
+            .json(ticks)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    async fn handle_event(&self, event: PluginEvent) -> Vec<Action> {
+        let endpoint = format!("{}{}", self.url, POST_CREATE_EVENT);
+        let url = Url::parse(&endpoint).unwrap();
+        trace!("Request url: {url:?}");
+        // events come from exchange streams, an unavailable engine must not take them down
+        let response = match self.client.post(url).json(&event).send().await {
+            Ok(response) => response,
+            Err(err) => {
+                error!("Error during event: '{:?}' sending, event is dropped: {err}", event.kind);
+                return Vec::new();
+            }
+        };
+        match response.json().await {
+            Ok(actions) => actions,
+            Err(err) => {
+                error!("Error during event: '{:?}' actions parsing: {err}", event.kind);
+                Vec::new()
+            }
+        }
+    }
+
     async fn delete_deployment(&self, id: Uuid) -> Option<DeploymentInfo> {
         let endpoint = format!("{}{}", self.url, DELETE_DEPLOYMENT).replace(":id", &id.to_string());
         let url = Url::parse(&endpoint).unwrap();
@@ -92,10 +141,221 @@ impl EngineApi for EngineRestClient {
             .unwrap()
     }
 
+    async fn get_deployment_state(&self, id: Uuid) -> Option<String> {
+        let endpoint = format!("{}{}", self.url, GET_DEPLOYMENT_STATE).replace(":id", &id.to_string());
+        let url = Url::parse(&endpoint).unwrap();
+        trace!("Request url: {url:?}");
+        self.client
+            .get(url)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    async fn get_state_history(&self, id: Uuid) -> Option<Vec<StateSnapshot>> {
+        let endpoint = format!("{}{}", self.url, GET_STATE_HISTORY).replace(":id", &id.to_string());
+        let url = Url::parse(&endpoint).unwrap();
+        trace!("Request url: {url:?}");
+        self.client
+            .get(url)
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .ok()?
+            .json()
+            .await
+            .ok()
+    }
+
+    async fn rollback_state(&self, id: Uuid, version: i64) -> Option<String> {
+        let query = RollbackStateQuery { version };
+        let endpoint = format!("{}{}", self.url, POST_ROLLBACK_STATE).replace(":id", &id.to_string());
+        let mut url = Url::parse(&endpoint).unwrap();
+        url.set_query(Some(&serde_urlencoded::to_string(&query).unwrap()));
+        trace!("Request url: {url:?}");
+        self.client
+            .post(url)
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .ok()?
+            .json()
+            .await
+            .ok()
+    }
+
+    async fn update_deployment(&self, id: Uuid, patch: DeploymentPatch) -> Result<Option<DeploymentInfo>, EngineError> {
+        let endpoint = format!("{}{}", self.url, PATCH_DEPLOYMENT).replace(":id", &id.to_string());
+        let url = Url::parse(&endpoint).unwrap();
+        trace!("Request url: {url:?}");
+        let response = self.client.patch(url).json(&patch).send().await.unwrap();
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if !status.is_success() => Err(engine_error(response).await),
+            _ => Ok(Some(response.json().await.unwrap())),
+        }
+    }
+
+    async fn pause_deployment(&self, id: Uuid) -> Option<DeploymentInfo> {
+        let endpoint = format!("{}{}", self.url, POST_PAUSE_DEPLOYMENT).replace(":id", &id.to_string());
+        let url = Url::parse(&endpoint).unwrap();
+        trace!("Request url: {url:?}");
+        self.client
+            .post(url)
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .ok()?
+            .json()
+            .await
+            .ok()
+    }
+
+    async fn resume_deployment(&self, id: Uuid) -> Option<DeploymentInfo> {
+        let endpoint = format!("{}{}", self.url, POST_RESUME_DEPLOYMENT).replace(":id", &id.to_string());
+        let url = Url::parse(&endpoint).unwrap();
+        trace!("Request url: {url:?}");
+        self.client
+            .post(url)
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .ok()?
+            .json()
+            .await
+            .ok()
+    }
+
+    async fn get_risk_rejections(&self, deployment_id: Option<Uuid>) -> Vec<RiskRejection> {
+        let query = RejectionsQuery { deployment_id };
+        let endpoint = format!("{}{}", self.url, GET_RISK_REJECTIONS);
+        let mut url = Url::parse(&endpoint).unwrap();
+        url.set_query(Some(&serde_urlencoded::to_string(&query).unwrap()));
+        trace!("Request url: {url:?}");
+        self.client
+            .get(url)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    async fn get_shadow_report(&self, id: Uuid, live_id: Uuid, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Option<ShadowReport> {
+        let query = ShadowReportQuery {
+            live_id,
+            from: from.map(|timestamp| timestamp.timestamp_millis()),
+            to: to.map(|timestamp| timestamp.timestamp_millis()),
+        };
+        let endpoint = format!("{}{}", self.url, GET_SHADOW_REPORT).replace(":id", &id.to_string());
+        let mut url = Url::parse(&endpoint).unwrap();
+        url.set_query(Some(&serde_urlencoded::to_string(&query).unwrap()));
+        trace!("Request url: {url:?}");
+        self.client
+            .get(url)
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .ok()?
+            .json()
+            .await
+            .ok()
+    }
+
+    async fn kill_switch(&self, request: KillSwitch) -> KillSwitchReport {
+        let endpoint = format!("{}{}", self.url, POST_KILL_SWITCH);
+        let url = Url::parse(&endpoint).unwrap();
+        trace!("Request url: {url:?}");
+        self.client
+            .post(url)
+            .json(&request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    /// Events are read from the engine websocket by a background task until the receiver is dropped
+    fn subscribe_events(&self) -> Receiver<EngineEvent> {
+        let (sender, receiver) = broadcast::channel(EVENTS_CAPACITY);
+        let url = format!("{}{}", self.url.replacen("http", "ws", 1), GET_EVENTS_STREAM);
+        trace!("Request url: {url:?}");
+        // the stream ends on any error, subscribers see it as a closed channel
+        tokio::spawn(async move {
+            let mut stream = match connect_async(&url).await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    error!("Error during events stream: '{url}' connecting: {err}");
+                    return;
+                }
+            };
+            while let Some(message) = stream.next().await {
+                let text = match message {
+                    Ok(Message::Text(text)) => text,
+                    Ok(_) => continue,
+                    Err(err) => {
+                        error!("Error during events stream reading: {err}");
+                        break;
+                    }
+                };
+                let event = match serde_json::from_str(&text) {
+                    Ok(event) => event,
+                    Err(err) => {
+                        error!("Invalid engine event: '{text}': {err}");
+                        break;
+                    }
+                };
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+
     async fn update_plugin(&self, plugin_id: PluginId) {
-        let endpoint = format!("{}{}", self.url, POST_CREATE_ACTIONS);
+        let endpoint = format!("{}{}", self.url, PUT_UPDATE_PLUGIN);
         let url = Url::parse(&endpoint).unwrap();
         trace!("Request url: {url:?}");
         self.client.put(url).json(&plugin_id).send().await.unwrap();
     }
+
+    async fn rollback_plugin(&self, id: Uuid) -> Result<Option<DeploymentInfo>, EngineError> {
+        let endpoint = format!("{}{}", self.url, POST_ROLLBACK_PLUGIN).replace(":id", &id.to_string());
+        let url = Url::parse(&endpoint).unwrap();
+        trace!("Request url: {url:?}");
+        let response = self.client.post(url).send().await.unwrap();
+        match response.status() {
+            // not found deployment has no error text
+            StatusCode::NOT_FOUND if response.content_length() == Some(0) => Ok(None),
+            status if !status.is_success() => Err(engine_error(response).await),
+            _ => Ok(Some(response.json().await.unwrap())),
+        }
+    }
+}
+
+async fn engine_error(response: Response) -> EngineError {
+    match response.status() {
+        StatusCode::NOT_FOUND => EngineError::PluginNotFound,
+        StatusCode::BAD_REQUEST => {
+            let text = response.text().await.unwrap();
+            match serde_json::from_str::<Vec<ParamError>>(&text) {
+                Ok(errors) => EngineError::InvalidParams(errors),
+                Err(_) => EngineError::InvalidScript(text),
+            }
+        }
+        StatusCode::CONFLICT => EngineError::StateMigrationError(response.text().await.unwrap()),
+        _ => EngineError::PluginLoadingError,
+    }
 }
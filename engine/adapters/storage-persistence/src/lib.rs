@@ -0,0 +1,3 @@
+pub use repositories::StorageStateRepository;
+
+mod repositories;
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use domain_model::state::StateSnapshot;
+use engine_persistence_api::StateRepository;
+use storage_core_api::StorageApi;
+
+/// States saved by the storage service in its database
+pub struct StorageStateRepository<S: StorageApi> {
+    storage_client: Arc<S>,
+}
+
+impl<S: StorageApi> StorageStateRepository<S> {
+    pub fn new(storage_client: Arc<S>) -> Self {
+        Self { storage_client }
+    }
+}
+
+#[async_trait]
+impl<S: StorageApi> StateRepository for StorageStateRepository<S> {
+    async fn save(&self, snapshot: &StateSnapshot, history: u64) -> Result<()> {
+        self.storage_client.save_state_snapshot(snapshot.clone(), history).await
+    }
+
+    async fn get_all(&self, state_id: &str) -> Result<Vec<StateSnapshot>> {
+        self.storage_client.get_state_snapshots(state_id).await
+    }
+
+    async fn delete(&self, state_id: &str) -> Result<()> {
+        self.storage_client.delete_state_snapshots(state_id).await?;
+        Ok(())
+    }
+}
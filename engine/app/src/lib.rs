@@ -1,9 +1,19 @@
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use tracing::info;
 
-use engine_config::CONFIG;
-use engine_core::Engine;
+use engine_config::{CONFIG, StateBackend};
+use domain_model::{Currency, RiskLimits};
+use engine_core::{Engine, RiskSettings, RolloutSettings, StateManager};
+use engine_fs_persistence::FsStateRepository;
+use engine_inmemory_persistence::InMemoryStateRepository;
+use engine_persistence_api::StateRepository;
+use engine_postgres_persistence::initiator::init_db;
+use engine_postgres_persistence::repositories::DeploymentPostgresRepository;
+use engine_storage_persistence::StorageStateRepository;
 use interactor_rest_client::InteractorRestClient;
 use registry_rest_client::RegistryRestClient;
 use storage_core_api_cache::StorageCoreApiCache;
@@ -14,7 +24,63 @@ pub async fn run() {
     let interactor_client = Arc::new(InteractorRestClient::new(&CONFIG.interactor.url));
     let registry = Arc::new(RegistryRestClient::new(&CONFIG.registry.url));
     let storage_client = Arc::new(StorageRestClient::new(&CONFIG.storage.url));
+    let state_manager = state_manager(Arc::clone(&storage_client));
     let storage_client_cached = Arc::new(StorageCoreApiCache::new(storage_client).await);
-    let engine = Engine::new(interactor_client, registry, storage_client_cached);
+    let db = init_db(&CONFIG.database.url, &CONFIG.application.name).await;
+    let deployment_repository = Arc::new(DeploymentPostgresRepository::new(db));
+    let engine = Engine::new(
+        interactor_client,
+        registry,
+        storage_client_cached,
+        deployment_repository,
+        Duration::from_millis(CONFIG.runtime.timeout),
+        CONFIG.runtime.queue,
+        risk_settings(),
+        state_manager,
+        rollout_settings(),
+    );
+    engine.restore_deployments().await;
     engine_rest_api_server::run(CONFIG.application.port, engine).await;
 }
+
+fn state_manager(storage_client: Arc<StorageRestClient>) -> StateManager {
+    let state = &CONFIG.state;
+    let repository: Arc<dyn StateRepository> = match state.backend {
+        StateBackend::Fs => match &state.path {
+            Some(path) => Arc::new(FsStateRepository::new(PathBuf::from(path))),
+            None => Arc::new(FsStateRepository::default()),
+        },
+        StateBackend::Storage => Arc::new(StorageStateRepository::new(storage_client)),
+        StateBackend::Memory => Arc::new(InMemoryStateRepository::default()),
+    };
+    StateManager::new(repository, state.history)
+}
+
+fn risk_settings() -> RiskSettings {
+    let risk = &CONFIG.risk;
+    RiskSettings {
+        limits: RiskLimits {
+            max_order_notional: risk.notional,
+            max_positions: risk.positions
+                .iter()
+                .map(|(currency, size)| (Currency::from_str(currency).expect("Unknown currency in risk positions"), *size))
+                .collect(),
+            max_open_orders: risk.orders,
+            max_orders_per_minute: risk.rate,
+            max_daily_loss: risk.loss,
+            max_price_deviation: risk.deviation,
+        },
+        account_daily_loss: risk.account,
+        breaches: risk.breaches,
+    }
+}
+
+fn rollout_settings() -> RolloutSettings {
+    let rollout = &CONFIG.rollout;
+    RolloutSettings {
+        canary: rollout.canary,
+        observe: Duration::from_secs(rollout.observe),
+        errors: rollout.errors,
+        pnl: rollout.pnl,
+    }
+}
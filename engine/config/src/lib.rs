@@ -8,9 +8,14 @@ use serde::Deserialize;
 pub struct Config {
     pub logging: Logging,
     pub application: Application,
+    pub database: Database,
     pub interactor: Interactor,
     pub registry: Registry,
     pub storage: Storage,
+    pub runtime: Runtime,
+    pub risk: Risk,
+    pub state: State,
+    pub rollout: Rollout,
 }
 
 #[derive(Deserialize)]
@@ -25,6 +30,55 @@ pub struct Logging {
     crates: HashMap<String, String>,
 }
 
+#[derive(Deserialize)]
+pub struct Runtime {
+    pub timeout: u64,
+    pub queue: usize,
+}
+
+/// Global pre-trade limits, unset limits are not checked
+#[derive(Deserialize)]
+pub struct Risk {
+    pub breaches: u32,
+    pub account: Option<f64>,
+    pub notional: Option<f64>,
+    #[serde(default)]
+    pub positions: HashMap<String, f64>,
+    pub orders: Option<u32>,
+    pub rate: Option<u32>,
+    pub loss: Option<f64>,
+    pub deviation: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct State {
+    pub backend: StateBackend,
+    pub path: Option<String>,
+    pub history: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateBackend {
+    Fs,
+    Storage,
+    Memory,
+}
+
+/// Staged upgrade of canary deployments
+#[derive(Deserialize)]
+pub struct Rollout {
+    pub canary: u32,
+    pub observe: u64,
+    pub errors: u64,
+    pub pnl: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct Database {
+    pub url: String,
+}
+
 #[derive(Deserialize)]
 pub struct Registry {
     pub url: String,
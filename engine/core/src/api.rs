@@ -3,37 +3,105 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use axum::async_trait;
-use chrono::{Duration, Utc};
-use tracing::{debug, error, info};
+use chrono::{DateTime, Duration, Utc};
+use tracing::{debug, error, info, warn};
+use tokio::sync::broadcast::Receiver;
 use uuid::Uuid;
 
-use domain_model::{Action, DeploymentInfo, Indicator, InstrumentId, NewDeployment, PluginId, Tick, Timeframe};
+use domain_model::{Action, DeploymentInfo, DeploymentMode, DeploymentPatch, DeploymentStatus, Indicator, InstrumentId, KillSwitch, KillSwitchReport, NewDeployment, PluginEvent, PluginEventKind, PluginId, RiskRejection, Subscription, Tick, Timeframe, UpgradePolicy};
+use domain_model::events::{EngineEvent, EngineEventKind};
+use domain_model::params::{ParamSchema, validate_params};
+use domain_model::shadow::ShadowReport;
+use domain_model::state::StateSnapshot;
+use domain_model::warmup::{plan, WarmupGap};
 use engine_core_api::api::{Deployment, EngineApi, EngineError};
+use engine_persistence_api::DeploymentRepository;
 use interactor_core_api::InteractorApi;
-use plugin_loader::Plugin;
+use plugin_loader::{Plugin, ScriptParamsError};
 use registry_core_api::RegistryApi;
 use storage_core_api::StorageApi;
 
-use crate::api::EngineError::{PluginLoadingError, PluginNotFound};
+use crate::api::EngineError::{InvalidParams, InvalidScript, PluginLoadingError, PluginNotFound, StateMigrationError};
+use crate::risk::RiskSettings;
+use crate::rollout::RolloutSettings;
 use crate::runtime::Runtime;
+use crate::state::StateManager;
+use crate::warmup;
 
-pub struct Engine<I: InteractorApi, R: RegistryApi, S: StorageApi> {
+pub struct Engine<I: InteractorApi, R: RegistryApi, S: StorageApi, D: DeploymentRepository> {
     interactor_client: Arc<I>,
     registry_client: Arc<R>,
     storage_client: Arc<S>,
+    deployment_repository: Arc<D>,
     runtime: Runtime<S, I>,
+    rollout: RolloutSettings,
 }
 
-impl<I: InteractorApi, R: RegistryApi, S: StorageApi> Engine<I, R, S> {
-    pub fn new(interactor_client: Arc<I>, registry_client: Arc<R>, storage_client: Arc<S>) -> Self {
+impl<I: InteractorApi, R: RegistryApi, S: StorageApi, D: DeploymentRepository> Engine<I, R, S, D> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        interactor_client: Arc<I>,
+        registry_client: Arc<R>,
+        storage_client: Arc<S>,
+        deployment_repository: Arc<D>,
+        tick_timeout: std::time::Duration,
+        queue_size: usize,
+        risk: RiskSettings,
+        state_manager: StateManager,
+        rollout: RolloutSettings,
+    ) -> Self {
         Self {
             interactor_client: Arc::clone(&interactor_client),
             registry_client,
             storage_client: Arc::clone(&storage_client),
-            runtime: Runtime::new(storage_client, Arc::clone(&interactor_client)),
+            deployment_repository,
+            runtime: Runtime::new(storage_client, Arc::clone(&interactor_client), tick_timeout, queue_size, risk, state_manager),
+            rollout,
         }
     }
 
+    /// Deploys again live deployments saved before the engine restart, plugins are loaded
+    /// from the registry and plugin states are restored by their state ids
+    pub async fn restore_deployments(&self) {
+        let deployments = self.deployment_repository.get_all().await;
+        info!("Restore '{}' deployments", deployments.len());
+        for deployment in deployments {
+            let new_deployment = new_deployment(&deployment, deployment.plugin_id.clone());
+            // paused deployments are restored paused, failed ones get another try
+            let status = match deployment.status {
+                DeploymentStatus::Paused => DeploymentStatus::Paused,
+                _ => DeploymentStatus::Running,
+            };
+            if let Err(err) = self.deploy_single(deployment.id, &new_deployment, status).await {
+                error!("Deployment: '{}' is not restored: {err}", deployment.id);
+                let deployment = DeploymentInfo {
+                    status: DeploymentStatus::Failed,
+                    ..deployment
+                };
+                self.save_deployment(&deployment).await;
+            }
+        }
+    }
+
+    async fn save_deployment(&self, deployment: &DeploymentInfo) {
+        // simulation deployments live only until the end of simulation
+        if deployment.simulation_id.is_none() {
+            self.deployment_repository
+                .save(deployment)
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn set_status(&self, id: Uuid, status: DeploymentStatus) -> Option<DeploymentInfo> {
+        let deployment = self
+            .runtime
+            .update_deployment(id, |deployment| deployment.status = status)
+            .await?;
+        self.save_deployment(&deployment).await;
+        Some(deployment)
+    }
+
     async fn load_plugin(
         &self,
         name: &str,
@@ -41,21 +109,47 @@ impl<I: InteractorApi, R: RegistryApi, S: StorageApi> Engine<I, R, S> {
         params: &HashMap<String, String>,
     ) -> Result<Plugin, EngineError> {
         let plugin_id = PluginId::new(name, version);
+        if name == plugin_loader::SCRIPT_PLUGIN_NAME {
+            return load_script(plugin_id, params);
+        }
         let plugin = self.registry_client.get_plugin_binary(plugin_id)
             .await
             .ok_or_else(|| {
                 error!("Error required plugin not found in registry, name: '{name}', versions: '{version}'");
                 PluginNotFound
             })?;
+        let params = check_params(&plugin.params, params, false).map_err(|err| {
+            warn!("Invalid params of plugin: '{name}', version: '{version}': {err}");
+            err
+        })?;
         let mut plugin = plugin_loader::load(&plugin.binary).map_err(|err| {
             error!("Error during plugin loading: {err}");
             PluginLoadingError
         })?;
-        plugin.api.configure(params);
+        plugin.api.configure(&params);
         Ok(plugin)
     }
 
-    async fn sync_data(&self, subscriptions: &[InstrumentId], indicators: &[Indicator]) {
+    /// Plugins with warmup requirements get exactly their windows synced, the others get the history of
+    /// their indicators. Returns the warmup windows still missing candles, simulations run on the history
+    /// loaded by the simulator, so they never wait for their warmup
+    async fn sync_data(&self, deployment: &DeploymentInfo) -> Vec<WarmupGap> {
+        if deployment.warmup.is_empty() {
+            self.sync_indicators(&deployment.subscriptions, &deployment.indicators).await;
+            return Vec::new();
+        }
+        let windows = plan(&deployment.warmup, Utc::now());
+        let gaps = warmup::sync(self.storage_client.as_ref(), &windows).await;
+        if deployment.simulation_id.is_some() {
+            return Vec::new();
+        }
+        if !gaps.is_empty() {
+            warn!("Deployment: '{}' waits for its warmup history, missing: {gaps:?}", deployment.id);
+        }
+        gaps
+    }
+
+    async fn sync_indicators(&self, subscriptions: &[InstrumentId], indicators: &[Indicator]) {
         let timeframes = [
             Timeframe::FiveM,
             Timeframe::FifteenM,
@@ -81,7 +175,9 @@ impl<I: InteractorApi, R: RegistryApi, S: StorageApi> Engine<I, R, S> {
 
     async fn deploy_single(
         &self,
+        id: Uuid,
         deployment: &NewDeployment,
+        status: DeploymentStatus,
     ) -> Result<DeploymentInfo, EngineError> {
         let strategy_name = deployment.plugin_id.name.clone();
         let strategy_version = deployment.plugin_id.version;
@@ -94,26 +190,167 @@ impl<I: InteractorApi, R: RegistryApi, S: StorageApi> Engine<I, R, S> {
         let plugin = self
             .load_plugin(&strategy_name, strategy_version, &params)
             .await?;
-        let deployment = Deployment {
-            id: Uuid::new_v4(),
+        self.deploy_plugin(id, deployment, plugin, status).await
+    }
+
+    async fn deploy_plugin(
+        &self,
+        id: Uuid,
+        deployment: &NewDeployment,
+        plugin: Plugin,
+        status: DeploymentStatus,
+    ) -> Result<DeploymentInfo, EngineError> {
+        let mut deployment = Deployment {
+            id,
+            status,
             simulation_id: deployment.simulation_id,
-            state_id,
-            params: params.clone(),
+            state_id: deployment.state_id,
+            params: deployment.params.clone(),
+            execution: deployment.execution.clone(),
+            risk: deployment.risk.clone(),
+            mode: deployment.mode,
+            upgrade: deployment.upgrade,
+            ready: false,
+            warmup_gaps: Vec::new(),
             plugin,
         };
+        deployment.warmup_gaps = self.sync_data(&(&deployment).into()).await;
+        deployment.ready = deployment.warmup_gaps.is_empty();
         let deployment_info: DeploymentInfo = (&deployment).into();
-        self.runtime.deploy(deployment).await;
-        self.sync_data(&deployment_info.subscriptions, &deployment_info.indicators).await;
+        self.runtime.deploy(deployment).await.map_err(|err| {
+            error!("Error during deployment: '{id}' state restoring: {err}");
+            StateMigrationError(err.to_string())
+        })?;
         self.interactor_client
             .subscribe((&deployment_info).into())
             .await
             .unwrap();
+        self.save_deployment(&deployment_info).await;
+        if deployment_info.simulation_id.is_none() {
+            self.handle_event(live_event(id, PluginEventKind::Start)).await;
+        }
         Ok(deployment_info)
     }
+
+    async fn get_deployment(&self, id: Uuid) -> Option<DeploymentInfo> {
+        self.get_deployments_info()
+            .await
+            .into_iter()
+            .find(|deployment| deployment.id == id)
+    }
+
+    /// The deployment keeps running the old plugin when the new plugin can't load or migrate its state
+    async fn upgrade_deployment(&self, deployment: &DeploymentInfo, plugin_id: &PluginId) -> bool {
+        let plugin = self
+            .load_plugin(&plugin_id.name, plugin_id.version, &deployment.params)
+            .await;
+        let mut plugin = match plugin {
+            Ok(plugin) => plugin,
+            Err(err) => {
+                error!("Deployment: '{}' is not updated: {err}", deployment.id);
+                return false;
+            }
+        };
+        if let Some(state_id) = deployment.state_id {
+            if let Err(err) = self.runtime.check_state(&mut plugin.api, state_id).await {
+                error!("Deployment: '{}' is not updated, state migration failed: {err}", deployment.id);
+                let message = format!("Plugin update is refused, state migration failed: {err}");
+                self.runtime.publish(deployment.id, EngineEventKind::PluginError(message));
+                return false;
+            }
+            self.runtime.pin_state(state_id).await;
+        }
+        self.redeploy(deployment, plugin_id.clone(), plugin, None).await.is_ok()
+    }
+
+    /// Canary passes the rollout gate when it still runs without extra plugin errors or losses
+    async fn check_canary(&self, id: Uuid) -> Result<(), String> {
+        let Some(deployment) = self.get_deployment(id).await else {
+            // deleted canaries don't block the rollout
+            return Ok(());
+        };
+        if deployment.status == DeploymentStatus::Failed {
+            return Err(String::from("is failed"));
+        }
+        let errors = self.runtime.plugin_errors(id);
+        if errors > self.rollout.errors {
+            return Err(format!("has '{errors}' plugin errors"));
+        }
+        let profit = self.runtime.realized_profit(id);
+        match self.rollout.pnl {
+            Some(min) if profit < min => Err(format!("realized profit: '{profit}' is below: '{min}'")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns upgraded canaries to their versions before the rollout
+    async fn rollback_canaries(&self, canaries: &[&DeploymentInfo], plugin_id: &PluginId) {
+        for canary in canaries {
+            if canary.plugin_id == *plugin_id {
+                // the plugin version was replaced in the registry, there is nothing to roll back to
+                warn!("Deployment: '{}' is not rolled back, its plugin version is replaced", canary.id);
+                continue;
+            }
+            let Some(deployment) = self.get_deployment(canary.id).await else {
+                continue;
+            };
+            if let Err(err) = self.rollback_deployment(&deployment, canary.plugin_id.clone()).await {
+                error!("Deployment: '{}' is not rolled back: {err}", canary.id);
+            }
+        }
+    }
+
+    /// The previous plugin gets the latest state it can read, the state pinned before the upgrade
+    /// becomes the latest state version when the current state was already migrated
+    async fn rollback_deployment(&self, deployment: &DeploymentInfo, previous: PluginId) -> Result<DeploymentInfo, EngineError> {
+        info!("Rollback deployment: '{}' to plugin version: '{}'", deployment.id, previous.version);
+        let mut plugin = self
+            .load_plugin(&previous.name, previous.version, &deployment.params)
+            .await?;
+        let mut saved_state = None;
+        if let Some(state_id) = deployment.state_id {
+            if self.runtime.check_state(&mut plugin.api, state_id).await.is_err() {
+                let state = self.runtime
+                    .find_state(state_id, plugin.api.state_version())
+                    .await
+                    .ok_or_else(|| StateMigrationError(format!("no state saved by plugin version: '{}'", previous.version)))?;
+                saved_state = Some((state_id, state));
+            }
+        }
+        self.redeploy(deployment, previous, plugin, saved_state).await
+    }
+
+    /// Replaces the plugin of the deployment keeping its id, status and state, the saved state
+    /// is restored before the new deployment. Deployment is saved as failed when it can't be deployed again
+    async fn redeploy(&self, deployment: &DeploymentInfo, plugin_id: PluginId, plugin: Plugin,
+                      saved_state: Option<(Uuid, String)>) -> Result<DeploymentInfo, EngineError> {
+        self.delete_deployment(deployment.id).await;
+        if let Some((state_id, state)) = saved_state {
+            self.runtime.restore_saved_state(state_id, state).await;
+        }
+        let new_deployment = new_deployment(deployment, plugin_id);
+        let status = match deployment.status {
+            DeploymentStatus::Paused => DeploymentStatus::Paused,
+            _ => DeploymentStatus::Running,
+        };
+        match self.deploy_plugin(deployment.id, &new_deployment, plugin, status).await {
+            Ok(deployment_info) => Ok(deployment_info),
+            Err(err) => {
+                // the state changed by the stop event can't be migrated
+                error!("Deployment: '{}' is not redeployed: {err}", deployment.id);
+                let deployment = DeploymentInfo {
+                    status: DeploymentStatus::Failed,
+                    ..deployment.clone()
+                };
+                self.save_deployment(&deployment).await;
+                Err(err)
+            }
+        }
+    }
 }
 
 #[async_trait]
-impl<I: InteractorApi, R: RegistryApi, S: StorageApi> EngineApi for Engine<I, R, S> {
+impl<I: InteractorApi, R: RegistryApi, S: StorageApi, D: DeploymentRepository> EngineApi for Engine<I, R, S, D> {
     async fn get_deployments_info(&self) -> Vec<DeploymentInfo> {
         self.runtime.get_deployments_info().await
     }
@@ -124,42 +361,361 @@ impl<I: InteractorApi, R: RegistryApi, S: StorageApi> EngineApi for Engine<I, R,
     ) -> Result<Vec<DeploymentInfo>, EngineError> {
         let mut result = Vec::new();
         for deployment in deployments {
-            let deployment_info = self.deploy_single(deployment).await?;
+            let deployment_info = self.deploy_single(Uuid::new_v4(), deployment, DeploymentStatus::Running).await?;
             result.push(deployment_info);
         }
         Ok(result)
     }
 
     async fn get_actions(&self, tick: &Tick) -> Vec<Action> {
-        let actions = self.runtime.get_actions(tick).await;
-        if !actions.is_empty() {
+        self.runtime.get_actions(tick).await
+    }
+
+    async fn handle_event(&self, event: PluginEvent) -> Vec<Action> {
+        self.runtime.handle_event(&event).await
+    }
+
+    async fn get_actions_batch(&self, ticks: &[Tick]) -> Vec<Vec<Action>> {
+        let mut result = Vec::with_capacity(ticks.len());
+        for tick in ticks {
+            result.push(self.get_actions(tick).await);
+        }
+        result
+    }
+
+    async fn delete_deployment(&self, id: Uuid) -> Option<DeploymentInfo> {
+        // simulations stop their deployments by themselves with the simulation time
+        if self.get_deployment(id).await.is_some_and(|deployment| deployment.simulation_id.is_none()) {
+            self.handle_event(live_event(id, PluginEventKind::Stop)).await;
+        }
+        let deployment = self.runtime.delete_deployment(id).await;
+        if deployment.as_ref().is_some_and(|deployment| deployment.simulation_id.is_none()) {
+            self.deployment_repository.delete(id).await.unwrap();
+        }
+        deployment
+    }
+
+    async fn get_deployment_state(&self, id: Uuid) -> Option<String> {
+        self.runtime.get_deployment_state(id).await
+    }
+
+    async fn get_state_history(&self, id: Uuid) -> Option<Vec<StateSnapshot>> {
+        self.runtime.get_state_history(id).await
+    }
+
+    async fn rollback_state(&self, id: Uuid, version: i64) -> Option<String> {
+        info!("Rollback state of deployment: '{id}' to version: '{version}'");
+        self.runtime.rollback_state(id, version).await
+    }
+
+    async fn update_deployment(&self, id: Uuid, patch: DeploymentPatch) -> Result<Option<DeploymentInfo>, EngineError> {
+        debug!("Update deployment: '{id}' with params: '{:?}'", patch.params);
+        let (Some(previous), Some(schema)) = (self.get_deployment(id).await, self.runtime.get_param_schema(id).await) else {
+            return Ok(None);
+        };
+        let is_script = previous.plugin_id.name == plugin_loader::SCRIPT_PLUGIN_NAME;
+        let params = if is_script {
+            script_patch_params(&previous.params, &patch.params)?
+        } else {
+            patch.params
+        };
+        let params = check_params(&schema, &params, is_script)?;
+        let updated = self
+            .runtime
+            .update_deployment(id, |deployment| {
+                deployment.plugin.api.configure(&params);
+                deployment.params = params;
+            })
+            .await;
+        let Some(mut deployment) = updated else {
+            return Ok(None);
+        };
+        // new params can change instruments the plugin is subscribed to and its warmup history
+        let subscriptions_changed = deployment.subscriptions != previous.subscriptions;
+        if subscriptions_changed {
+            let previous_subscription = Subscription {
+                simulation_id: deployment.simulation_id,
+                deployment_id: id,
+                instruments: previous.subscriptions,
+            };
+            self.interactor_client
+                .unsubscribe(previous_subscription)
+                .await
+                .unwrap();
+        }
+        if subscriptions_changed || deployment.warmup != previous.warmup {
+            let gaps = self.sync_data(&deployment).await;
+            let updated = self
+                .runtime
+                .update_deployment(id, |deployment| {
+                    deployment.ready = gaps.is_empty();
+                    deployment.warmup_gaps = gaps;
+                })
+                .await;
+            let Some(updated) = updated else {
+                return Ok(None);
+            };
+            deployment = updated;
+        }
+        if subscriptions_changed {
             self.interactor_client
-                .execute_actions(actions.clone())
+                .subscribe((&deployment).into())
                 .await
                 .unwrap();
         }
-        actions
+        self.save_deployment(&deployment).await;
+        Ok(Some(deployment))
     }
 
-    async fn delete_deployment(&self, id: Uuid) -> Option<DeploymentInfo> {
-        self.runtime.delete_deployment(id).await
+    async fn pause_deployment(&self, id: Uuid) -> Option<DeploymentInfo> {
+        self.set_status(id, DeploymentStatus::Paused).await
     }
 
-    async fn update_plugin(&self, plugin_id: PluginId) {
-        let all_deployments = self.get_deployments_info().await;
-        let outdated_deployments = all_deployments
+    /// Only paused deployments are resumed, failed ones have to be deployed again
+    async fn resume_deployment(&self, id: Uuid) -> Option<DeploymentInfo> {
+        let deployment = self.get_deployment(id).await?;
+        if deployment.status == DeploymentStatus::Failed {
+            warn!("Deployment: '{id}' is failed and can't be resumed");
+            return None;
+        }
+        self.set_status(id, DeploymentStatus::Running).await
+    }
+
+    async fn get_risk_rejections(&self, deployment_id: Option<Uuid>) -> Vec<RiskRejection> {
+        self.runtime.get_risk_rejections(deployment_id)
+    }
+
+    async fn get_shadow_report(
+        &self,
+        id: Uuid,
+        live_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Option<ShadowReport> {
+        let is_shadow = self
+            .get_deployments_info()
+            .await
             .iter()
-            .filter(|deployment| deployment.plugin_id == plugin_id);
+            .any(|deployment| deployment.id == id && deployment.mode == DeploymentMode::Shadow);
+        if !is_shadow {
+            return None;
+        }
+        let shadow_records = self.storage_client
+            .get_action_records(id, from, to)
+            .await
+            .unwrap();
+        let live_records = self.storage_client
+            .get_action_records(live_id, from, to)
+            .await
+            .unwrap();
+        let report = self.runtime.get_shadow_report(id, live_id, &shadow_records, &live_records, from, to);
+        Some(report)
+    }
 
-        for deployment in outdated_deployments {
-            self.delete_deployment(deployment.id).await;
-            let new_deployment = NewDeployment {
-                simulation_id: deployment.simulation_id,
-                state_id: deployment.state_id,
-                plugin_id: plugin_id.clone(),
-                params: deployment.params.clone(),
-            };
-            self.deploy_single(&new_deployment).await.unwrap();
+    async fn kill_switch(&self, request: KillSwitch) -> KillSwitchReport {
+        warn!("Kill switch is activated");
+        let mut paused = Vec::new();
+        // simulations don't trade on exchanges
+        for deployment in self.get_deployments_info().await {
+            if deployment.status == DeploymentStatus::Running && deployment.simulation_id.is_none() {
+                self.set_status(deployment.id, DeploymentStatus::Paused).await;
+                paused.push(deployment.id);
+            }
+        }
+        let mut report = self.interactor_client
+            .stop_trading(request.close_to)
+            .await
+            .unwrap_or_else(|err| KillSwitchReport {
+                failed: vec![format!("Trading is not stopped by interactor: {err}")],
+                ..Default::default()
+            });
+        report.paused = paused;
+        report
+    }
+
+    fn subscribe_events(&self) -> Receiver<EngineEvent> {
+        self.runtime.subscribe_events()
+    }
+
+    /// Pinned deployments are skipped, auto deployments are upgraded right away and canary deployments
+    /// are upgraded in stages, the first canaries are rolled back when they don't pass the rollout gate
+    /// Simulation deployments keep the plugin version they are started with
+    async fn update_plugin(&self, plugin_id: PluginId) {
+        let (pinned, outdated): (Vec<_>, Vec<_>) = self
+            .get_deployments_info()
+            .await
+            .into_iter()
+            .filter(|deployment| deployment.simulation_id.is_none())
+            .filter(|deployment| deployment.plugin_id.name == plugin_id.name && deployment.plugin_id.version <= plugin_id.version)
+            .partition(|deployment| deployment.upgrade == UpgradePolicy::Pinned);
+        for deployment in pinned {
+            if deployment.plugin_id == plugin_id {
+                self.upgrade_deployment(&deployment, &plugin_id).await;
+            } else {
+                info!("Deployment: '{}' is pinned to plugin version: '{}'", deployment.id, deployment.plugin_id.version);
+            }
+        }
+        let (canaries, auto): (Vec<_>, Vec<_>) = outdated
+            .into_iter()
+            .partition(|deployment| deployment.upgrade == UpgradePolicy::Canary);
+        for deployment in &auto {
+            self.upgrade_deployment(deployment, &plugin_id).await;
+        }
+        if canaries.is_empty() {
+            return;
+        }
+
+        let (first, rest) = canaries.split_at(self.rollout.canaries(canaries.len()));
+        let mut upgraded = Vec::new();
+        for deployment in first {
+            if self.upgrade_deployment(deployment, &plugin_id).await {
+                upgraded.push(deployment);
+            }
         }
+        if upgraded.is_empty() {
+            warn!("Rollout of plugin: '{}:{}' is stopped, no canary deployment is upgraded", plugin_id.name, plugin_id.version);
+            return;
+        }
+        info!("Observe '{}' canary deployments of plugin: '{}:{}' for: '{:?}'", upgraded.len(), plugin_id.name, plugin_id.version, self.rollout.observe);
+        tokio::time::sleep(self.rollout.observe).await;
+        for deployment in &upgraded {
+            if let Err(reason) = self.check_canary(deployment.id).await {
+                warn!("Rollout of plugin: '{}:{}' is stopped, canary deployment: '{}' {reason}", plugin_id.name, plugin_id.version, deployment.id);
+                let message = format!("Rollout of plugin version: '{}' is stopped: {reason}", plugin_id.version);
+                self.runtime.publish(deployment.id, EngineEventKind::PluginError(message));
+                self.rollback_canaries(&upgraded, &plugin_id).await;
+                return;
+            }
+        }
+        for deployment in rest {
+            self.upgrade_deployment(deployment, &plugin_id).await;
+        }
+    }
+
+    async fn rollback_plugin(&self, id: Uuid) -> Result<Option<DeploymentInfo>, EngineError> {
+        let Some(deployment) = self.get_deployment(id).await else {
+            return Ok(None);
+        };
+        let previous = self.registry_client
+            .get_plugins_info_by_name(&deployment.plugin_id.name)
+            .await
+            .into_iter()
+            .map(|plugin| plugin.id)
+            .filter(|plugin_id| plugin_id.version < deployment.plugin_id.version)
+            .max_by_key(|plugin_id| plugin_id.version)
+            .ok_or(PluginNotFound)?;
+        self.rollback_deployment(&deployment, previous).await.map(Some)
+    }
+}
+
+/// Built-in script plugin, the source is compiled at deploy time, so script errors fail the deployment.
+/// Params are checked against the `params()` schema of the script
+fn load_script(plugin_id: PluginId, params: &HashMap<String, String>) -> Result<Plugin, EngineError> {
+    let source = params
+        .get(plugin_loader::SCRIPT_PARAM)
+        .ok_or_else(|| InvalidScript(format!("parameter: '{}' with the script source is required", plugin_loader::SCRIPT_PARAM)))?;
+    plugin_loader::load_script(source, plugin_id, params).map_err(|err| match err.downcast::<ScriptParamsError>() {
+        Ok(ScriptParamsError(errors)) => InvalidParams(errors),
+        Err(err) => {
+            error!("Error during script loading: {err}");
+            InvalidScript(err.to_string())
+        }
+    })
+}
+
+/// A patch keeps the script source of the deployment, a changed source needs a new deployment
+/// because the running plugin can't be compiled again
+fn script_patch_params(
+    previous: &HashMap<String, String>,
+    patch: &HashMap<String, String>,
+) -> Result<HashMap<String, String>, EngineError> {
+    let source = previous.get(plugin_loader::SCRIPT_PARAM);
+    let mut params = patch.clone();
+    match (patch.get(plugin_loader::SCRIPT_PARAM), source) {
+        (Some(patched), Some(source)) if patched != source => {
+            return Err(InvalidScript(String::from("script source can't be patched, deploy the new script instead")));
+        }
+        (None, Some(source)) => {
+            params.insert(plugin_loader::SCRIPT_PARAM.to_string(), source.clone());
+        }
+        _ => {}
+    }
+    Ok(params)
+}
+
+/// Plugins without schema parse params themselves. The script source is not a part of script schemas
+fn check_params(schema: &[ParamSchema], params: &HashMap<String, String>, is_script: bool) -> Result<HashMap<String, String>, EngineError> {
+    if schema.is_empty() {
+        return Ok(params.clone());
+    }
+    let mut params = params.clone();
+    let source = if is_script {
+        params.remove(plugin_loader::SCRIPT_PARAM)
+    } else {
+        None
+    };
+    let mut params = validate_params(schema, &params).map_err(InvalidParams)?;
+    if let Some(source) = source {
+        params.insert(plugin_loader::SCRIPT_PARAM.to_string(), source);
+    }
+    Ok(params)
+}
+
+fn new_deployment(deployment: &DeploymentInfo, plugin_id: PluginId) -> NewDeployment {
+    NewDeployment {
+        simulation_id: deployment.simulation_id,
+        state_id: deployment.state_id,
+        plugin_id,
+        params: deployment.params.clone(),
+        execution: deployment.execution.clone(),
+        risk: deployment.risk.clone(),
+        mode: deployment.mode,
+        upgrade: deployment.upgrade,
+    }
+}
+
+fn live_event(deployment_id: Uuid, kind: PluginEventKind) -> PluginEvent {
+    PluginEvent {
+        simulation_id: None,
+        deployment_id: Some(deployment_id),
+        timestamp: Utc::now(),
+        kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use domain_model::params::ParamKind;
+
+    use super::*;
+
+    fn params(values: &[(&str, &str)]) -> HashMap<String, String> {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_patch_keeps_script_source() {
+        let previous = params(&[("script", "fn on_tick(tick) {}"), ("period", "10")]);
+        let patched = script_patch_params(&previous, &params(&[("period", "20")])).unwrap();
+        assert_eq!(patched, params(&[("script", "fn on_tick(tick) {}"), ("period", "20")]));
+
+        let schema = vec![ParamSchema::new("period", ParamKind::Integer)];
+        let checked = check_params(&schema, &patched, true).unwrap();
+        assert_eq!(checked["script"], "fn on_tick(tick) {}");
+        // scripts without schema get the source too
+        assert_eq!(check_params(&[], &patched, true).unwrap()["script"], "fn on_tick(tick) {}");
+    }
+
+    #[test]
+    fn test_patch_rejects_changed_script_source() {
+        let previous = params(&[("script", "fn on_tick(tick) {}")]);
+        let same = params(&[("script", "fn on_tick(tick) {}")]);
+        assert!(script_patch_params(&previous, &same).is_ok());
+
+        let changed = params(&[("script", "fn on_tick(tick) { [] }")]);
+        assert!(matches!(script_patch_params(&previous, &changed), Err(InvalidScript(_))));
     }
 }
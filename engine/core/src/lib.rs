@@ -1,5 +1,14 @@
 pub use api::Engine;
+pub use risk::RiskSettings;
+pub use rollout::RolloutSettings;
+pub use state::StateManager;
 
-mod fs_state_manager;
 mod runtime;
 mod api;
+mod worker;
+mod risk;
+mod rollout;
+mod shadow;
+mod metrics;
+mod state;
+mod warmup;
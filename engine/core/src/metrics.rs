@@ -0,0 +1,50 @@
+use metrics::{HistogramVec, IntCounter, IntCounterVec, IntGauge};
+use once_cell::sync::Lazy;
+
+use engine_core_api::api::Deployment;
+
+// per deployment metrics are labeled by the deployment id
+const DEPLOYMENT: &str = "deployment";
+// simulation deployments share one label, so simulations don't grow the number of series
+const SIMULATION: &str = "simulation";
+
+pub static TICK_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    metrics::histogram_vec("engine_on_tick_duration_seconds", "Duration of plugin tick processing", &[DEPLOYMENT])
+});
+pub static TICK_LAG: Lazy<HistogramVec> = Lazy::new(|| {
+    metrics::histogram_vec("engine_tick_lag_seconds", "Delay between the live tick time and its processing", &[DEPLOYMENT])
+});
+pub static TICKS: Lazy<IntCounterVec> = Lazy::new(|| {
+    metrics::counter_vec("engine_ticks_total", "Ticks received by deployments", &[DEPLOYMENT])
+});
+pub static ACTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    metrics::counter_vec("engine_actions_total", "Actions emitted by plugins", &[DEPLOYMENT])
+});
+pub static TIMEOUTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    metrics::counter_vec("engine_plugin_timeouts_total", "Plugin calls reaching the timeout", &[DEPLOYMENT])
+});
+pub static ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    metrics::counter_vec("engine_plugin_errors_total", "Plugin panics", &[DEPLOYMENT])
+});
+pub static STATE_SAVES: Lazy<IntCounter> = Lazy::new(|| {
+    metrics::counter("engine_state_saves_total", "Saves of plugin states")
+});
+pub static STATE_SIZE: Lazy<IntGauge> = Lazy::new(|| {
+    metrics::gauge("engine_state_size_bytes", "Size of the last saved plugin state")
+});
+
+pub fn deployment_label(deployment: &Deployment) -> String {
+    match deployment.simulation_id {
+        Some(_) => SIMULATION.to_string(),
+        None => deployment.id.to_string(),
+    }
+}
+
+pub fn remove_deployment(deployment_id: &str) {
+    for metric in [&*TICKS, &*ACTIONS, &*TIMEOUTS, &*ERRORS] {
+        let _ = metric.remove_label_values(&[deployment_id]);
+    }
+    for metric in [&*TICK_DURATION, &*TICK_LAG] {
+        let _ = metric.remove_label_values(&[deployment_id]);
+    }
+}
@@ -0,0 +1,551 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use tracing::warn;
+use uuid::Uuid;
+
+use domain_model::{Action, CreateOrder, Currency, CurrencyPair, Exchange, Order, OrderActionType, OrderStatus, OrderType, Position, RiskLimits, RiskRejection, Side, Size, Tick};
+
+// only the latest rejections are kept in memory
+const MAX_REJECTIONS: usize = 1000;
+
+/// Global risk settings of the engine
+#[derive(Default)]
+pub struct RiskSettings {
+    pub limits: RiskLimits,
+    // realized daily loss limit of all live deployments together
+    pub account_daily_loss: Option<f64>,
+    // consecutive rejections of a deployment before it is paused, 0 never pauses
+    pub breaches: u32,
+}
+
+pub struct RiskReport {
+    pub approved: Vec<Action>,
+    pub rejected: Vec<RiskRejection>,
+    pub pause: bool,
+}
+
+/// Validates actions of live deployments before they are passed to the interactor.
+/// Prices, positions and order fills are taken from live ticks and plugin events.
+pub struct RiskManager {
+    settings: RiskSettings,
+    state: Mutex<RiskState>,
+}
+
+#[derive(Default)]
+struct RiskState {
+    prices: HashMap<(Exchange, CurrencyPair), f64>,
+    positions: HashMap<(Exchange, Currency), f64>,
+    // open orders with their deployments
+    orders: HashMap<String, Uuid>,
+    deployments: HashMap<Uuid, DeploymentRisk>,
+    account: DailyLoss,
+    rejections: VecDeque<RiskRejection>,
+}
+
+#[derive(Default)]
+struct DeploymentRisk {
+    order_times: VecDeque<DateTime<Utc>>,
+    holdings: HashMap<(Exchange, CurrencyPair), Holding>,
+    daily: DailyLoss,
+    // realized profit since the deployment start
+    realized: f64,
+    breaches: u32,
+}
+
+#[derive(Default)]
+struct Holding {
+    quantity: f64,
+    average_price: f64,
+}
+
+#[derive(Default)]
+struct DailyLoss {
+    day: Option<NaiveDate>,
+    realized: f64,
+}
+
+impl RiskManager {
+    pub fn new(settings: RiskSettings) -> Self {
+        Self {
+            settings,
+            state: Default::default(),
+        }
+    }
+
+    pub fn on_tick(&self, tick: &Tick) {
+        if tick.simulation_id.is_none() {
+            let key = (tick.instrument_id.exchange, tick.instrument_id.pair);
+            self.state.lock().unwrap().prices.insert(key, tick.price);
+        }
+    }
+
+    pub fn on_position(&self, position: &Position) {
+        if position.simulation_id.is_none() {
+            let size = match position.side {
+                Side::Buy => position.size,
+                Side::Sell => -position.size,
+            };
+            self.state.lock().unwrap().positions.insert((position.exchange, position.currency), size);
+        }
+    }
+
+    /// Frees finished orders and adds the profit of filled orders to the daily results
+    pub fn on_order(&self, order: &Order) {
+        if order.simulation_id.is_some() || !order.status.is_finished() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let Some(deployment_id) = state.orders.remove(&order.id) else {
+            return;
+        };
+        if order.status != OrderStatus::Completed {
+            return;
+        }
+        let deployment = state.deployments.entry(deployment_id).or_default();
+        let holding = deployment.holdings.entry((order.exchange, order.pair)).or_default();
+        let profit = holding.fill(order);
+        deployment.daily.add(order.timestamp, profit);
+        deployment.realized += profit;
+        state.account.add(order.timestamp, profit);
+    }
+
+    /// Frees the order of a create action that didn't reach the exchange
+    pub fn on_failed(&self, order_id: &str) {
+        self.state.lock().unwrap().orders.remove(order_id);
+    }
+
+    pub fn check(&self, deployment_id: Uuid, limits: &RiskLimits, actions: Vec<Action>) -> RiskReport {
+        let limits = limits.or(&self.settings.limits);
+        let now = Utc::now();
+        let mut state = self.state.lock().unwrap();
+        let mut approved = Vec::new();
+        let mut rejected = Vec::new();
+        // positions are updated only by exchange events, so orders approved earlier in the batch are added here
+        let mut batch_positions = HashMap::new();
+        for action in actions {
+            match state.check_action(deployment_id, &limits, self.settings.account_daily_loss, &action, &batch_positions, now) {
+                Ok(position_change) => {
+                    if let Some((key, size)) = position_change {
+                        *batch_positions.entry(key).or_default() += size;
+                    }
+                    state.approve(deployment_id, &action, now);
+                    approved.push(action);
+                }
+                Err(reason) => {
+                    warn!("Action of deployment: '{deployment_id}' is rejected: {reason}");
+                    state.deployments.entry(deployment_id).or_default().breaches += 1;
+                    if state.rejections.len() == MAX_REJECTIONS {
+                        state.rejections.pop_front();
+                    }
+                    let rejection = RiskRejection {
+                        deployment_id,
+                        timestamp: now,
+                        action,
+                        reason,
+                    };
+                    state.rejections.push_back(rejection.clone());
+                    rejected.push(rejection);
+                }
+            }
+        }
+        let deployment = state.deployments.entry(deployment_id).or_default();
+        let pause = self.settings.breaches > 0 && deployment.breaches >= self.settings.breaches;
+        if pause {
+            deployment.breaches = 0;
+        }
+        RiskReport { approved, rejected, pause }
+    }
+
+    pub fn get_rejections(&self, deployment_id: Option<Uuid>) -> Vec<RiskRejection> {
+        self.state
+            .lock()
+            .unwrap()
+            .rejections
+            .iter()
+            .filter(|rejection| deployment_id.map_or(true, |id| rejection.deployment_id == id))
+            .cloned()
+            .collect()
+    }
+
+    pub fn realized(&self, deployment_id: Uuid) -> f64 {
+        self.state
+            .lock()
+            .unwrap()
+            .deployments
+            .get(&deployment_id)
+            .map_or(0., |deployment| deployment.realized)
+    }
+
+    pub fn remove_deployment(&self, deployment_id: Uuid) {
+        let mut state = self.state.lock().unwrap();
+        state.deployments.remove(&deployment_id);
+        state.orders.retain(|_, id| *id != deployment_id);
+    }
+}
+
+impl RiskState {
+    /// Returns the position change of an approved order limited by `max_positions`
+    fn check_action(
+        &mut self,
+        deployment_id: Uuid,
+        limits: &RiskLimits,
+        account_daily_loss: Option<f64>,
+        action: &Action,
+        batch_positions: &HashMap<(Exchange, Currency), f64>,
+        now: DateTime<Utc>,
+    ) -> Result<Option<((Exchange, Currency), f64)>, String> {
+        let Action::OrderAction(order_action) = action;
+        // cancellations only reduce the risk
+        let OrderActionType::CreateOrder(create_order) = &order_action.order else {
+            return Ok(None);
+        };
+        let exchange = order_action.exchange;
+        let open_orders = self.orders.values().filter(|id| **id == deployment_id).count() as u32;
+        let deployment = self.deployments.entry(deployment_id).or_default();
+        deployment.order_times.retain(|time| *time > now - Duration::minutes(1));
+
+        if let Some(max) = limits.max_orders_per_minute {
+            if deployment.order_times.len() as u32 >= max {
+                return Err(format!("orders per minute limit: {max} is reached"));
+            }
+        }
+        if let Some(max) = limits.max_open_orders {
+            if open_orders >= max {
+                return Err(format!("open orders limit: {max} is reached"));
+            }
+        }
+        if let Some(max) = limits.max_daily_loss {
+            let loss = deployment.daily.loss(now);
+            if loss >= max {
+                return Err(format!("deployment daily loss: {loss} reached the limit: {max}"));
+            }
+        }
+        if let Some(max) = account_daily_loss {
+            let loss = self.account.loss(now);
+            if loss >= max {
+                return Err(format!("account daily loss: {loss} reached the limit: {max}"));
+            }
+        }
+
+        // limits that can't be evaluated without the last price reject the order
+        let last_price = self.prices.get(&(exchange, create_order.pair)).copied();
+        let price = match create_order.order_type {
+            OrderType::Limit(price) => Some(price),
+            OrderType::Market => last_price,
+        };
+        if let (Some(max), OrderType::Limit(price)) = (limits.max_price_deviation, create_order.order_type) {
+            let last_price = last_price.ok_or("price deviation can't be checked without the last price")?;
+            let deviation = (price - last_price).abs() / last_price * 100.;
+            if deviation > max {
+                return Err(format!("price: {price} deviates from the last price: {last_price} by {deviation:.2}%, limit: {max}%"));
+            }
+        }
+        if let Some(max) = limits.max_order_notional {
+            let notional = notional(create_order, price).ok_or("order notional can't be checked without the last price")?;
+            if notional > max {
+                return Err(format!("order notional: {notional} exceeds the limit: {max}"));
+            }
+        }
+        let target = create_order.pair.target;
+        let Some(max) = limits.max_positions.get(&target) else {
+            return Ok(None);
+        };
+        let size = target_size(create_order, price).ok_or(format!("{target} position can't be checked without the last price"))?;
+        let change = match create_order.side {
+            Side::Buy => size,
+            Side::Sell => -size,
+        };
+        let key = (exchange, target);
+        let current = self.positions.get(&key).copied().unwrap_or_default() + batch_positions.get(&key).copied().unwrap_or_default();
+        let position = current + change;
+        if position.abs() > *max {
+            return Err(format!("{target} position: {position} exceeds the limit: {max}"));
+        }
+        Ok(Some((key, change)))
+    }
+
+    fn approve(&mut self, deployment_id: Uuid, action: &Action, now: DateTime<Utc>) {
+        let deployment = self.deployments.entry(deployment_id).or_default();
+        deployment.breaches = 0;
+        let Action::OrderAction(order_action) = action;
+        if let OrderActionType::CreateOrder(create_order) = &order_action.order {
+            deployment.order_times.push_back(now);
+            self.orders.insert(create_order.id.clone(), deployment_id);
+        }
+    }
+}
+
+impl Holding {
+    /// Updates the average price with the filled order, returns realized profit minus fees
+    fn fill(&mut self, order: &Order) -> f64 {
+        let price = order.avg_fill_price;
+        let quantity = match order.size {
+            Size::Target(size) => size,
+            Size::Source(size) if price > 0. => size / price,
+            Size::Source(_) => 0.,
+        };
+        match order.side {
+            Side::Buy => {
+                let total = self.quantity + quantity;
+                if total > 0. {
+                    self.average_price = (self.average_price * self.quantity + price * quantity) / total;
+                }
+                self.quantity = total;
+                -order.fee
+            }
+            Side::Sell => {
+                let closed = quantity.min(self.quantity);
+                self.quantity -= closed;
+                (price - self.average_price) * closed - order.fee
+            }
+        }
+    }
+}
+
+impl DailyLoss {
+    fn add(&mut self, timestamp: DateTime<Utc>, profit: f64) {
+        let day = timestamp.date_naive();
+        if self.day != Some(day) {
+            self.day = Some(day);
+            self.realized = 0.;
+        }
+        self.realized += profit;
+    }
+
+    fn loss(&self, now: DateTime<Utc>) -> f64 {
+        if self.day == Some(now.date_naive()) {
+            (-self.realized).max(0.)
+        } else {
+            0.
+        }
+    }
+}
+
+fn notional(order: &CreateOrder, price: Option<f64>) -> Option<f64> {
+    match order.size {
+        Size::Source(size) => Some(size),
+        Size::Target(size) => price.map(|price| size * price),
+    }
+}
+
+fn target_size(order: &CreateOrder, price: Option<f64>) -> Option<f64> {
+    match order.size {
+        Size::Target(size) => Some(size),
+        Size::Source(size) => price.filter(|price| *price > 0.).map(|price| size / price),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use domain_model::{InstrumentId, MarketType, OrderAction, OrderMarketType, PluginId};
+
+    use super::*;
+
+    const PAIR: CurrencyPair = CurrencyPair { target: Currency::BTC, source: Currency::USDT };
+
+    fn tick(price: f64) -> Tick {
+        let instrument_id = InstrumentId {
+            exchange: Exchange::OKX,
+            market_type: MarketType::Spot,
+            pair: PAIR,
+        };
+        Tick::new(None, Utc::now(), instrument_id, price)
+    }
+
+    fn create_order(id: &str, order_type: OrderType, size: Size) -> Action {
+        Action::OrderAction(OrderAction {
+            id: Uuid::new_v4(),
+            deployment_id: Uuid::new_v4(),
+            simulation_id: None,
+            plugin_id: PluginId::new("test", 1),
+            timestamp: Utc::now(),
+            exchange: Exchange::OKX,
+            status: OrderStatus::Created,
+            order: OrderActionType::CreateOrder(CreateOrder {
+                id: id.to_string(),
+                pair: PAIR,
+                market_type: OrderMarketType::Spot,
+                order_type,
+                side: Side::Buy,
+                size,
+                stop_loss: None,
+                take_profit: None,
+            }),
+        })
+    }
+
+    fn market(id: &str, size: Size) -> Action {
+        create_order(id, OrderType::Market, size)
+    }
+
+    fn filled(id: &str, side: Side, size: f64, price: f64, timestamp: DateTime<Utc>) -> Order {
+        Order {
+            id: id.to_string(),
+            timestamp,
+            simulation_id: None,
+            status: OrderStatus::Completed,
+            exchange: Exchange::OKX,
+            pair: PAIR,
+            market_type: OrderMarketType::Spot,
+            order_type: OrderType::Market,
+            side,
+            size: Size::Target(size),
+            fee: 0.,
+            avg_fill_price: price,
+            stop_loss: None,
+            avg_sl_price: 0.,
+            take_profit: None,
+            avg_tp_price: 0.,
+        }
+    }
+
+    fn manager(limits: RiskLimits) -> RiskManager {
+        RiskManager::new(RiskSettings {
+            limits,
+            ..Default::default()
+        })
+    }
+
+    /// Approves and fills a buy and a sell order, so the deployment loses `loss`
+    fn lose(manager: &RiskManager, deployment_id: Uuid, loss: f64) {
+        let actions = vec![market("buy", Size::Target(1.)), market("sell", Size::Target(1.))];
+        assert_eq!(manager.check(deployment_id, &RiskLimits::default(), actions).approved.len(), 2);
+        manager.on_order(&filled("buy", Side::Buy, 1., 100., Utc::now()));
+        manager.on_order(&filled("sell", Side::Sell, 1., 100. - loss, Utc::now()));
+    }
+
+    #[test]
+    fn test_orders_per_minute() {
+        let manager = manager(RiskLimits { max_orders_per_minute: Some(1), ..Default::default() });
+        let actions = vec![market("1", Size::Source(10.)), market("2", Size::Source(10.))];
+        let report = manager.check(Uuid::new_v4(), &RiskLimits::default(), actions);
+        assert_eq!(report.approved.len(), 1);
+        assert_eq!(report.rejected.len(), 1);
+    }
+
+    #[test]
+    fn test_open_orders_are_freed() {
+        let manager = manager(RiskLimits { max_open_orders: Some(1), ..Default::default() });
+        let deployment_id = Uuid::new_v4();
+        let check = |id: &str| manager.check(deployment_id, &RiskLimits::default(), vec![market(id, Size::Source(10.))]).approved.len();
+        assert_eq!(check("1"), 1);
+        assert_eq!(check("2"), 0);
+        manager.on_order(&filled("1", Side::Buy, 0.1, 100., Utc::now()));
+        assert_eq!(check("3"), 1);
+        manager.on_failed("3");
+        assert_eq!(check("4"), 1);
+    }
+
+    #[test]
+    fn test_daily_loss() {
+        let manager = manager(RiskLimits { max_daily_loss: Some(5.), ..Default::default() });
+        let deployment_id = Uuid::new_v4();
+        lose(&manager, deployment_id, 10.);
+        assert_eq!(manager.realized(deployment_id), -10.);
+        let report = manager.check(deployment_id, &RiskLimits::default(), vec![market("1", Size::Source(10.))]);
+        assert_eq!(report.rejected.len(), 1);
+        // other deployments have their own daily loss
+        let report = manager.check(Uuid::new_v4(), &RiskLimits::default(), vec![market("2", Size::Source(10.))]);
+        assert_eq!(report.approved.len(), 1);
+    }
+
+    #[test]
+    fn test_account_daily_loss() {
+        let manager = RiskManager::new(RiskSettings {
+            account_daily_loss: Some(5.),
+            ..Default::default()
+        });
+        lose(&manager, Uuid::new_v4(), 10.);
+        let report = manager.check(Uuid::new_v4(), &RiskLimits::default(), vec![market("1", Size::Source(10.))]);
+        assert_eq!(report.rejected.len(), 1);
+    }
+
+    #[test]
+    fn test_daily_loss_rollover() {
+        let now = Utc::now();
+        let mut daily = DailyLoss::default();
+        daily.add(now - Duration::days(1), -10.);
+        assert_eq!(daily.loss(now), 0.);
+        daily.add(now, -3.);
+        assert_eq!(daily.loss(now), 3.);
+        daily.add(now, 5.);
+        assert_eq!(daily.loss(now), 0.);
+    }
+
+    #[test]
+    fn test_price_deviation() {
+        let manager = manager(RiskLimits { max_price_deviation: Some(1.), ..Default::default() });
+        let deployment_id = Uuid::new_v4();
+        let check = |id: &str, price: f64| manager.check(deployment_id, &RiskLimits::default(), vec![create_order(id, OrderType::Limit(price), Size::Target(0.1))]).approved.len();
+        assert_eq!(check("1", 100.), 0);
+        manager.on_tick(&tick(100.));
+        assert_eq!(check("2", 100.5), 1);
+        assert_eq!(check("3", 110.), 0);
+    }
+
+    #[test]
+    fn test_order_notional() {
+        let manager = manager(RiskLimits { max_order_notional: Some(50.), ..Default::default() });
+        let deployment_id = Uuid::new_v4();
+        let check = |id: &str, size: Size| manager.check(deployment_id, &RiskLimits::default(), vec![market(id, size)]).approved.len();
+        assert_eq!(check("1", Size::Source(10.)), 1);
+        assert_eq!(check("2", Size::Target(0.1)), 0);
+        manager.on_tick(&tick(100.));
+        assert_eq!(check("3", Size::Target(0.1)), 1);
+        assert_eq!(check("4", Size::Target(1.)), 0);
+    }
+
+    #[test]
+    fn test_positions() {
+        let mut limits = RiskLimits::default();
+        limits.max_positions.insert(Currency::BTC, 1.);
+        let manager = manager(limits);
+        let deployment_id = Uuid::new_v4();
+        let check = |id: &str, size: Size| manager.check(deployment_id, &RiskLimits::default(), vec![market(id, size)]).approved.len();
+        manager.on_position(&Position::new(None, Exchange::OKX, Currency::BTC, Side::Buy, 0.5));
+        assert_eq!(check("1", Size::Target(0.4)), 1);
+        assert_eq!(check("2", Size::Target(0.6)), 0);
+        assert_eq!(check("3", Size::Source(10.)), 0);
+        manager.on_tick(&tick(100.));
+        assert_eq!(check("4", Size::Source(10.)), 1);
+    }
+
+    #[test]
+    fn test_positions_of_batch() {
+        let mut limits = RiskLimits::default();
+        limits.max_positions.insert(Currency::BTC, 1.);
+        let manager = manager(limits);
+        manager.on_position(&Position::new(None, Exchange::OKX, Currency::BTC, Side::Buy, 0.25));
+        let actions = vec![
+            market("1", Size::Target(0.5)),
+            market("2", Size::Target(0.5)),
+            market("3", Size::Target(0.25)),
+        ];
+        let report = manager.check(Uuid::new_v4(), &RiskLimits::default(), actions);
+        // the second order exceeds the limit with the first one, the third one fits
+        assert_eq!(report.approved.len(), 2);
+        assert_eq!(report.rejected.len(), 1);
+        assert!(report.rejected[0].reason.contains("1.25"));
+    }
+
+    #[test]
+    fn test_breaches_pause_deployment() {
+        let manager = RiskManager::new(RiskSettings {
+            limits: RiskLimits { max_order_notional: Some(50.), ..Default::default() },
+            breaches: 2,
+            ..Default::default()
+        });
+        let deployment_id = Uuid::new_v4();
+        let check = |id: &str, size: f64| manager.check(deployment_id, &RiskLimits::default(), vec![market(id, Size::Source(size))]).pause;
+        assert!(!check("1", 100.));
+        // an approved action resets the breaches
+        assert!(!check("2", 10.));
+        assert!(!check("3", 100.));
+        assert!(check("4", 100.));
+        assert!(!check("5", 100.));
+        assert_eq!(manager.get_rejections(Some(deployment_id)).len(), 4);
+    }
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+/// Staged upgrade of deployments with the canary upgrade policy
+pub struct RolloutSettings {
+    // percent of canary deployments upgraded first, at least one deployment
+    pub canary: u32,
+    // how long the first canaries run the new version before the rest is upgraded
+    pub observe: Duration,
+    // plugin errors of a canary deployment allowed during the observation
+    pub errors: u64,
+    // min realized profit of a canary deployment during the observation, not checked if not set
+    pub pnl: Option<f64>,
+}
+
+impl Default for RolloutSettings {
+    fn default() -> Self {
+        Self {
+            canary: 10,
+            observe: Duration::from_secs(600),
+            errors: 0,
+            pnl: None,
+        }
+    }
+}
+
+impl RolloutSettings {
+    /// Number of the first canaries out of all canary deployments
+    pub fn canaries(&self, total: usize) -> usize {
+        let canaries = (total * self.canary as usize + 99) / 100;
+        canaries.clamp(total.min(1), total)
+    }
+}
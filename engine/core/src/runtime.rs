@@ -1,127 +1,784 @@
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use tokio::sync::RwLock;
-use tracing::debug;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use futures::future::join_all;
+use tokio::sync::{broadcast, Mutex, oneshot, RwLock};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use domain_model::{Action, DeploymentInfo, PluginId, Tick};
-use engine_core_api::api::Deployment;
+use domain_model::{Action, DeploymentInfo, DeploymentMode, DeploymentStatus, InstrumentId, OrderActionType, PluginEvent, PluginEventKind, PluginId, RiskRejection, Tick};
+use domain_model::audit::{ActionRecord, ActionTrigger, ExecutionResult, RiskDecision};
+use domain_model::events::{EngineEvent, EngineEventKind};
+use domain_model::params::ParamSchema;
+use domain_model::schedule::Schedule;
+use domain_model::shadow::ShadowReport;
+use domain_model::state::StateSnapshot;
+use domain_model::warmup::plan;
+use engine_core_api::api::{Deployment, EVENTS_CAPACITY};
 use engine_plugin_internals::api::DefaultPluginInternals;
 use interactor_core_api::InteractorApi;
-use plugin_api::PluginApi;
+use plugin_api::{PluginApi, PluginInternalApi};
 use storage_core_api::StorageApi;
 
-use crate::fs_state_manager::FsStateManager;
+use crate::metrics;
+use crate::risk::{RiskManager, RiskSettings};
+use crate::shadow::ShadowTracker;
+use crate::state::{DeploymentStateInternals, restore_state, StateEnvelope, StateManager};
+use crate::warmup;
+use crate::worker::{Task, TaskQueue};
+
+// how often a deployment without its warmup history syncs it again
+const WARMUP_RETRY: Duration = Duration::from_secs(60);
 
 pub struct Runtime<S: StorageApi, I: InteractorApi> {
-    deployments: Arc<RwLock<Vec<Deployment>>>,
+    workers: Arc<RwLock<Vec<Arc<Worker>>>>,
+    processor: Arc<TickProcessor<S, I>>,
+    state_manager: Arc<StateManager>,
+    queue_size: usize,
+}
+
+/// Deployment running on its own thread, ticks and events are passed through the queue
+struct Worker {
+    id: Uuid,
+    simulation_id: Option<Uuid>,
+    instruments: std::sync::RwLock<Vec<InstrumentId>>,
+    schedules: std::sync::RwLock<Vec<Schedule>>,
+    deployment: Arc<Mutex<Deployment>>,
+    queue: Arc<TaskQueue>,
+}
+
+struct TickProcessor<S: StorageApi, I: InteractorApi> {
     storage_client: Arc<S>,
     interactor_client: Arc<I>,
-    state_manager: Arc<FsStateManager>,
+    state_manager: Arc<StateManager>,
+    tick_timeout: Duration,
+    // deployment of each order created by plugin actions
+    order_owners: DashMap<String, Uuid>,
+    risk: RiskManager,
+    // approved actions of live deployments waiting for their execution results
+    pending_records: DashMap<Uuid, ActionRecord>,
+    shadow: ShadowTracker,
+    // last warmup sync of deployments that are not ready
+    warmup_syncs: DashMap<Uuid, Instant>,
+    events: broadcast::Sender<EngineEvent>,
 }
 
 impl<S: StorageApi, I: InteractorApi> Runtime<S, I> {
-    pub fn new(storage_client: Arc<S>, interactor_client: Arc<I>) -> Self {
+    pub fn new(storage_client: Arc<S>, interactor_client: Arc<I>, tick_timeout: Duration, queue_size: usize,
+               risk: RiskSettings, state_manager: StateManager) -> Self {
+        let state_manager = Arc::new(state_manager);
         Self {
-            deployments: Default::default(),
-            storage_client,
-            interactor_client,
-            state_manager: Default::default(),
+            workers: Default::default(),
+            processor: Arc::new(TickProcessor {
+                storage_client,
+                interactor_client,
+                state_manager: Arc::clone(&state_manager),
+                tick_timeout,
+                order_owners: DashMap::new(),
+                risk: RiskManager::new(risk),
+                pending_records: DashMap::new(),
+                shadow: Default::default(),
+                warmup_syncs: DashMap::new(),
+                events: broadcast::channel(EVENTS_CAPACITY).0,
+            }),
+            state_manager,
+            queue_size,
         }
     }
 
     pub async fn get_deployments_info(&self) -> Vec<DeploymentInfo> {
-        self.deployments
-            .read()
-            .await
-            .iter()
-            .map(|deployment| deployment.into())
-            .collect()
+        let workers = self.workers.read().await.clone();
+        let mut result = Vec::new();
+        for worker in workers {
+            result.push((&*worker.deployment.lock().await).into());
+        }
+        result
     }
 
-    pub async fn deploy(&self, deployment: Deployment) {
-        self.deployments.write().await.push(deployment);
+    /// Fails when the saved state can't be restored by the plugin, the deployment is not started then
+    pub async fn deploy(&self, mut deployment: Deployment) -> Result<()> {
+        if let Some(state_id) = deployment.state_id {
+            let state_id = state_id.to_string();
+            if let Some(state) = self.state_manager.get(&state_id).await {
+                if let Some(migrated) = restore_state(&mut deployment.plugin.api, &state).await? {
+                    self.state_manager.set(&state_id, migrated).await;
+                }
+            }
+        }
+        if deployment.simulation_id.is_none() && deployment.mode == DeploymentMode::Shadow {
+            let shadow_state_id = shadow_state_id(&deployment);
+            let saved = self.state_manager.get(&shadow_state_id).await;
+            self.processor.shadow.add_deployment(deployment.id, shadow_state_id, saved);
+        }
+        let deployment_info: DeploymentInfo = (&deployment).into();
+        let worker = Arc::new(Worker {
+            id: deployment.id,
+            simulation_id: deployment.simulation_id,
+            instruments: std::sync::RwLock::new(deployment.plugin.api.instruments()),
+            schedules: std::sync::RwLock::new(valid_schedules(deployment.plugin.api.schedules())),
+            queue: Arc::new(TaskQueue::new(self.queue_size, deployment.execution.backpressure)),
+            deployment: Arc::new(Mutex::new(deployment)),
+        });
+        self.spawn_worker(&worker);
+        self.workers.write().await.push(worker);
+        self.processor.publish(deployment_info.id, EngineEventKind::Deployed(deployment_info));
+        Ok(())
+    }
+
+    /// Checks that the plugin can restore the saved state, nothing is saved
+    pub async fn check_state(&self, plugin: &mut Box<dyn PluginApi + Send + Sync>, state_id: Uuid) -> Result<()> {
+        if let Some(state) = self.state_manager.get(&state_id.to_string()).await {
+            restore_state(plugin, &state).await?;
+        }
+        Ok(())
     }
 
     pub async fn delete_deployment(&self, id: Uuid) -> Option<DeploymentInfo> {
-        let index = self
-            .deployments
+        let mut workers = self.workers.write().await;
+        let index = workers.iter().position(|worker| worker.id == id)?;
+        let removed_worker = workers.remove(index);
+        removed_worker.queue.close();
+        self.processor.order_owners.retain(|_, deployment_id| *deployment_id != id);
+        self.processor.risk.remove_deployment(id);
+        self.processor.pending_records.retain(|_, record| record.deployment_id != id);
+        self.processor.shadow.remove_deployment(id);
+        self.processor.warmup_syncs.remove(&id);
+        metrics::remove_deployment(&id.to_string());
+        let deployment = removed_worker.deployment.lock().await;
+        if deployment.state_id.is_none() {
+            self.state_manager.remove(&values_state_id(&deployment)).await;
+            self.state_manager.remove(&shadow_state_id(&deployment)).await;
+        }
+        let removed_deployment: DeploymentInfo = (&*deployment).into();
+        self.processor.publish(id, EngineEventKind::Deleted(removed_deployment.clone()));
+        Some(removed_deployment)
+    }
+
+    /// Applies the update under the deployment lock, so a tick never sees a half updated deployment
+    pub async fn update_deployment<F>(&self, id: Uuid, update: F) -> Option<DeploymentInfo>
+        where F: FnOnce(&mut Deployment) {
+        let worker = self.get_worker(id).await?;
+        Some(self.processor.update(&worker, update).await)
+    }
+
+    pub async fn get_param_schema(&self, id: Uuid) -> Option<Vec<ParamSchema>> {
+        let worker = self.get_worker(id).await?;
+        let deployment = worker.deployment.lock().await;
+        Some(deployment.plugin.api.params())
+    }
+
+    pub async fn get_deployment_state(&self, id: Uuid) -> Option<String> {
+        let worker = self.get_worker(id).await?;
+        let deployment = worker.deployment.lock().await;
+        deployment.plugin.api.get_state().await
+    }
+
+    /// Saved versions of the deployment state, `None` if the deployment is not found or has no state id
+    pub async fn get_state_history(&self, id: Uuid) -> Option<Vec<StateSnapshot>> {
+        let worker = self.get_worker(id).await?;
+        let state_id = worker.deployment.lock().await.state_id?;
+        Some(self.state_manager.history(&state_id.to_string()).await)
+    }
+
+    /// Restores the state version under the deployment lock and saves it as the latest version
+    pub async fn rollback_state(&self, id: Uuid, version: i64) -> Option<String> {
+        let worker = self.get_worker(id).await?;
+        let mut deployment = worker.deployment.lock().await;
+        let state_id = deployment.state_id?;
+        let state_id = state_id.to_string();
+        let saved = self.state_manager.rollback(&state_id, version).await?;
+        match restore_state(&mut deployment.plugin.api, &saved).await {
+            Ok(Some(migrated)) => self.state_manager.set(&state_id, migrated).await,
+            Ok(None) => {}
+            Err(err) => error!("Error during state: '{state_id}' version: '{version}' restoring: {err}"),
+        }
+        Some(StateEnvelope::parse(&saved).state)
+    }
+
+    // Ticks are queued to all subscribed deployments of the tick's simulation at once and every
+    // deployment executes its own actions, so a slow deployment delays only its own execution.
+    // The returned actions are still complete only when the slowest deployment has replied.
+    pub async fn get_actions(&self, tick: &Tick) -> Vec<Action> {
+        self.processor.risk.on_tick(tick);
+        for (state_id, state) in self.processor.shadow.on_tick(tick) {
+            self.state_manager.set(&state_id, state).await;
+        }
+        let workers = self.workers.read().await.clone();
+        let receivers: Vec<_> = workers
+            .iter()
+            .filter(|worker| worker.simulation_id == tick.simulation_id)
+            .filter(|worker| is_subscribed(&worker.instruments.read().unwrap(), tick))
+            .map(|worker| worker.queue.push(Task::Tick(tick.clone())))
+            .collect();
+        self.collect_actions(receivers).await
+    }
+
+    /// Passes the event to its deployment or to all deployments subscribed to the event instrument
+    pub async fn handle_event(&self, event: &PluginEvent) -> Vec<Action> {
+        match &event.kind {
+            PluginEventKind::OrderUpdate(order) => self.processor.risk.on_order(order),
+            PluginEventKind::PositionUpdate(position) => self.processor.risk.on_position(position),
+            _ => {}
+        }
+        let deployment_id = event.deployment_id.or_else(|| match &event.kind {
+            PluginEventKind::OrderUpdate(order) => self.processor.order_owners.get(&order.id).map(|owner| *owner),
+            _ => None,
+        });
+        let workers = self.workers.read().await.clone();
+        let receivers: Vec<_> = workers
+            .iter()
+            .filter(|worker| worker.simulation_id == event.simulation_id)
+            .filter(|worker| match deployment_id {
+                Some(deployment_id) => worker.id == deployment_id,
+                None => is_interested(&worker.instruments.read().unwrap(), &event.kind),
+            })
+            .map(|worker| worker.queue.push(Task::Event(event.clone())))
+            .collect();
+        self.collect_actions(receivers).await
+    }
+
+    /// Latest saved state readable by a plugin with the state version
+    pub async fn find_state(&self, state_id: Uuid, state_version: u32) -> Option<String> {
+        self.state_manager.latest_of(&state_id.to_string(), state_version).await
+    }
+
+    /// Saves the state as the latest version, it is restored by the next deployment of the state
+    pub async fn restore_saved_state(&self, state_id: Uuid, state: String) {
+        self.state_manager.set(&state_id.to_string(), state).await;
+    }
+
+    /// Keeps the current state for a rollback of the plugin upgrade
+    pub async fn pin_state(&self, state_id: Uuid) {
+        self.state_manager.pin(&state_id.to_string()).await;
+    }
+
+    /// Plugin errors of the deployment since its start
+    pub fn plugin_errors(&self, deployment_id: Uuid) -> u64 {
+        metrics::ERRORS.with_label_values(&[&deployment_id.to_string()]).get()
+    }
+
+    pub fn publish(&self, deployment_id: Uuid, kind: EngineEventKind) {
+        self.processor.publish(deployment_id, kind);
+    }
+
+    pub fn subscribe_events(&self) -> broadcast::Receiver<EngineEvent> {
+        self.processor.events.subscribe()
+    }
+
+    /// Realized profit of the live deployment since its start
+    pub fn realized_profit(&self, deployment_id: Uuid) -> f64 {
+        self.processor.risk.realized(deployment_id)
+    }
+
+    pub fn get_risk_rejections(&self, deployment_id: Option<Uuid>) -> Vec<RiskRejection> {
+        self.processor.risk.get_rejections(deployment_id)
+    }
+
+    pub fn get_shadow_report(
+        &self,
+        shadow_id: Uuid,
+        live_id: Uuid,
+        shadow_records: &[ActionRecord],
+        live_records: &[ActionRecord],
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> ShadowReport {
+        self.processor.shadow.report(shadow_id, live_id, shadow_records, live_records, from, to)
+    }
+
+    async fn collect_actions(&self, receivers: Vec<oneshot::Receiver<Vec<Action>>>) -> Vec<Action> {
+        join_all(receivers)
+            .await
+            .into_iter()
+            // dropped ticks and failed deployments give no actions
+            .flat_map(|actions| actions.unwrap_or_default())
+            .collect()
+    }
+
+    async fn get_worker(&self, id: Uuid) -> Option<Arc<Worker>> {
+        self.workers
             .read()
             .await
             .iter()
-            .position(|deployment| deployment.id == id);
-        if let Some(index) = index {
-            let removed_deployment = self.deployments.write().await.remove(index);
-            Some((&removed_deployment).into())
-        } else {
-            None
-        }
+            .find(|worker| worker.id == id)
+            .cloned()
     }
 
-    // todo refactoring
-    pub async fn get_actions(&self, tick: &Tick) -> Vec<Action> {
-        let mut result = Vec::new();
-        for deployment in self.deployments.write().await.iter_mut() {
-            let is_simulation = deployment.simulation_id == tick.simulation_id;
-            let plugin = &mut deployment.plugin.api;
-            if is_subscribed(plugin.as_ref(), tick) && is_simulation {
-                debug!(
-                    "Processing tick: '{} {}-{}={}' for plugin: '{}:{}'",
-                    tick.instrument_id.exchange,
-                    tick.instrument_id.pair.target,
-                    tick.instrument_id.pair.source,
-                    tick.price,
-                    plugin.id().name,
-                    plugin.id().version
-                );
-
-                if let Some(state_id) = deployment.state_id {
-                    let state = self.state_manager.get(&state_id.to_string());
-                    if let Some(state) = state {
-                        plugin.set_state(&state).await;
+    fn spawn_worker(&self, worker: &Arc<Worker>) {
+        let processor = Arc::clone(&self.processor);
+        let worker = Arc::clone(worker);
+        thread::Builder::new()
+            .name(format!("deployment-{}", worker.id))
+            .spawn(move || {
+                // the worker thread only drives the loop, so plugins can be called on it in `block_in_place`
+                let runtime = tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(1)
+                    .enable_all()
+                    .build()
+                    .expect("Error during deployment runtime creation");
+                runtime.block_on(async move {
+                    // simulation deployments get timer events from the simulator with simulated time
+                    let has_timers = worker.simulation_id.is_none();
+                    let mut last_timer = Utc::now();
+                    loop {
+                        let next_timer = has_timers
+                            .then(|| next_timer(&worker.schedules.read().unwrap(), last_timer))
+                            .flatten();
+                        let sleep = next_timer
+                            .as_ref()
+                            .map(|(_, time)| (*time - Utc::now()).to_std().unwrap_or_default());
+                        tokio::select! {
+                            job = worker.queue.pop() => {
+                                let Some(job) = job else {
+                                    break;
+                                };
+                                let mut deployment = worker.deployment.lock().await;
+                                if matches!(job.task, Task::Tick(_)) && deployment.status == DeploymentStatus::Running && !deployment.ready {
+                                    processor.warm_up(&worker, &deployment);
+                                }
+                                let actions = match &job.task {
+                                    Task::Tick(tick) => processor.process_tick(&mut deployment, tick).await,
+                                    Task::Event(event) => processor.process_event(&mut deployment, event).await,
+                                };
+                                // the simulator fills simulation actions itself, the interactor would only skip them
+                                if worker.simulation_id.is_none() {
+                                    processor.execute(&actions).await;
+                                }
+                                let _ = job.reply.send(actions);
+                            }
+                            _ = tokio::time::sleep(sleep.unwrap_or_default()), if sleep.is_some() => {
+                                let (names, time) = next_timer.unwrap();
+                                last_timer = time;
+                                let mut deployment = worker.deployment.lock().await;
+                                for name in names {
+                                    let event = PluginEvent {
+                                        simulation_id: None,
+                                        deployment_id: Some(worker.id),
+                                        timestamp: time,
+                                        kind: PluginEventKind::Timer(name),
+                                    };
+                                    processor.fire_timer(&mut deployment, &event).await;
+                                }
+                            }
+                        }
                     }
-                }
+                });
+            })
+            .expect("Error during deployment worker spawning");
+    }
+}
 
-                let plugin_internal_api = self.build_plugin_internal_api(
-                    deployment.id,
-                    plugin.id(),
-                    deployment.simulation_id,
-                    tick,
-                );
-                let mut actions = plugin.on_tick_sync(tick, plugin_internal_api);
-                result.append(&mut actions);
-
-                if let Some(state_id) = deployment.state_id {
-                    if let Some(state) = plugin.get_state().await {
-                        self.state_manager.set(&state_id.to_string(), state);
-                    }
+impl<S: StorageApi, I: InteractorApi> TickProcessor<S, I> {
+    async fn process_tick(&self, deployment: &mut Deployment, tick: &Tick) -> Vec<Action> {
+        let label = metrics::deployment_label(deployment);
+        metrics::TICKS.with_label_values(&[&label]).inc();
+        if tick.simulation_id.is_none() {
+            let lag = (Utc::now() - tick.timestamp).num_milliseconds() as f64 / 1000.;
+            metrics::TICK_LAG.with_label_values(&[&label]).observe(lag);
+        }
+        if deployment.status != DeploymentStatus::Running || !deployment.ready {
+            return Vec::new();
+        }
+        debug!(
+            "Processing tick: '{} {}-{}={}' for plugin: '{}:{}'",
+            tick.instrument_id.exchange,
+            tick.instrument_id.pair.target,
+            tick.instrument_id.pair.source,
+            tick.price,
+            deployment.plugin.api.id().name,
+            deployment.plugin.api.id().version
+        );
+        let trigger = ActionTrigger::Tick(tick.clone());
+        let actions = self.run_plugin(deployment, tick.timestamp, trigger, |plugin, api, timeout| {
+            plugin.on_tick_with_timeout(tick, api, timeout)
+        })
+            .await;
+        if deployment.simulation_id.is_none() {
+            self.publish(deployment.id, EngineEventKind::TickProcessed {
+                tick_id: tick.id,
+                instrument_id: tick.instrument_id.clone(),
+                actions: actions.len(),
+            });
+        }
+        actions
+    }
+
+    async fn process_event(&self, deployment: &mut Deployment, event: &PluginEvent) -> Vec<Action> {
+        // lifecycle callbacks are called for paused deployments too
+        let is_lifecycle = matches!(event.kind, PluginEventKind::Start | PluginEventKind::Stop);
+        if (deployment.status != DeploymentStatus::Running || !deployment.ready) && !is_lifecycle {
+            return Vec::new();
+        }
+        debug!("Processing event: '{:?}' for deployment: '{}'", event.kind, deployment.id);
+        let trigger = ActionTrigger::Event(event.kind.clone());
+        self.run_plugin(deployment, event.timestamp, trigger, |plugin, api, timeout| {
+            plugin.on_event_with_timeout(&event.kind, api, timeout)
+        })
+            .await
+    }
+
+    /// Syncs the warmup history of a not ready deployment again in the background, at most once per
+    /// `WARMUP_RETRY`, so ticks are not held by the sync. The result is applied like any deployment update
+    fn warm_up(self: &Arc<Self>, worker: &Arc<Worker>, deployment: &Deployment) {
+        let now = Instant::now();
+        if self.warmup_syncs.get(&deployment.id).is_some_and(|synced| now - *synced < WARMUP_RETRY) {
+            return;
+        }
+        self.warmup_syncs.insert(deployment.id, now);
+        let windows = plan(&deployment.plugin.api.warmup(), Utc::now());
+        let processor = Arc::clone(self);
+        let worker = Arc::clone(worker);
+        tokio::spawn(async move {
+            let gaps = warmup::sync(processor.storage_client.as_ref(), &windows).await;
+            if gaps.is_empty() {
+                info!("Warmup history of deployment: '{}' is synced, deployment is ready", worker.id);
+                processor.warmup_syncs.remove(&worker.id);
+            }
+            processor
+                .update(&worker, |deployment| {
+                    deployment.ready = gaps.is_empty();
+                    deployment.warmup_gaps = gaps;
+                })
+                .await;
+        });
+    }
+
+    async fn update<F>(&self, worker: &Worker, update: F) -> DeploymentInfo
+        where F: FnOnce(&mut Deployment) {
+        let mut deployment = worker.deployment.lock().await;
+        update(&mut deployment);
+        *worker.instruments.write().unwrap() = deployment.plugin.api.instruments();
+        *worker.schedules.write().unwrap() = valid_schedules(deployment.plugin.api.schedules());
+        let deployment_info: DeploymentInfo = (&*deployment).into();
+        self.publish(worker.id, EngineEventKind::Updated(deployment_info.clone()));
+        deployment_info
+    }
+
+    async fn fire_timer(&self, deployment: &mut Deployment, event: &PluginEvent) {
+        let actions = self.process_event(deployment, event).await;
+        self.execute(&actions).await;
+    }
+
+    /// Executes the deployment actions right away, without waiting for other deployments
+    async fn execute(&self, actions: &[Action]) {
+        if !actions.is_empty() {
+            let results = self.interactor_client
+                .execute_actions(actions.to_vec())
+                .await;
+            self.audit(actions, results).await;
+        }
+    }
+
+    /// Completes audit records of executed actions with their exchange results, orders that failed or
+    /// finished right away are freed, no order update may come for them
+    async fn audit(&self, actions: &[Action], results: Result<Vec<ExecutionResult>>) {
+        let results = results.unwrap_or_else(|err| {
+            error!("Error during actions execution: {err}");
+            vec![ExecutionResult::Failed(err.to_string()); actions.len()]
+        });
+        for (action, result) in actions.iter().zip(results) {
+            let Action::OrderAction(order_action) = action;
+            match (&order_action.order, &result) {
+                (OrderActionType::CreateOrder(create_order), ExecutionResult::Failed(_)) => self.risk.on_failed(&create_order.id),
+                (_, ExecutionResult::Placed(order)) => self.risk.on_order(order),
+                _ => {}
+            }
+            if let Some((_, mut record)) = self.pending_records.remove(&order_action.id) {
+                record.result = Some(result);
+                self.save_action_record(record).await;
+            }
+        }
+    }
+
+    fn publish(&self, deployment_id: Uuid, kind: EngineEventKind) {
+        // sending fails only when nobody is subscribed
+        let _ = self.events.send(EngineEvent {
+            timestamp: Utc::now(),
+            deployment_id,
+            kind,
+        });
+    }
+
+    async fn save_action_record(&self, record: ActionRecord) {
+        if let Err(err) = self.storage_client.save_action_record(record).await {
+            error!("Error during action record saving: {err}");
+        }
+    }
+
+    /// Calls the plugin with its restored state, a panic inside the plugin marks the deployment as failed
+    async fn run_plugin<F>(&self, deployment: &mut Deployment, timestamp: DateTime<Utc>, trigger: ActionTrigger, call: F) -> Vec<Action>
+        where F: FnOnce(&mut Box<dyn PluginApi + Send + Sync>, Arc<dyn PluginInternalApi>, Duration) -> Vec<Action> + Send {
+        let values_state_id = values_state_id(deployment);
+        let plugin = &mut deployment.plugin.api;
+        if let Some(state_id) = deployment.state_id {
+            let state = self.state_manager.get(&state_id.to_string()).await;
+            if let Some(state) = state {
+                if let Err(err) = restore_state(plugin, &state).await {
+                    self.fail(deployment, &format!("State restoring failed: {err}"));
+                    return Vec::new();
                 }
             }
         }
-        result
+
+        let plugin_internal_api = self.build_plugin_internal_api(
+            deployment.id,
+            plugin.id(),
+            deployment.simulation_id,
+            timestamp,
+            values_state_id,
+        );
+        let timeout = deployment
+            .execution
+            .timeout
+            .map(Duration::from_millis)
+            .unwrap_or(self.tick_timeout);
+        // plugin runs its own runtime, so it is called outside of the worker runtime context,
+        // the worker thread is blocked until the call is handled
+        let started = Instant::now();
+        let actions = panic::catch_unwind(AssertUnwindSafe(|| {
+            tokio::task::block_in_place(|| call(plugin, plugin_internal_api, timeout))
+        }));
+        let elapsed = started.elapsed();
+        let label = metrics::deployment_label(deployment);
+        if matches!(trigger, ActionTrigger::Tick(_)) {
+            metrics::TICK_DURATION.with_label_values(&[&label]).observe(elapsed.as_secs_f64());
+        }
+        if elapsed >= timeout {
+            metrics::TIMEOUTS.with_label_values(&[&label]).inc();
+        }
+        let mut actions = match actions {
+            Ok(actions) => actions,
+            Err(_) => {
+                self.fail(deployment, "Plugin panicked");
+                return Vec::new();
+            }
+        };
+        metrics::ACTIONS.with_label_values(&[&label]).inc_by(actions.len() as u64);
+        let mut paused = false;
+        if deployment.simulation_id.is_none() && deployment.mode == DeploymentMode::Shadow {
+            // shadow actions never reach the interactor, they are only recorded and filled hypothetically
+            if let Some((state_id, state)) = self.shadow.on_actions(deployment.id, &actions) {
+                self.state_manager.set(&state_id, state).await;
+            }
+            for action in std::mem::take(&mut actions) {
+                let mut record = action_record(&trigger, action, RiskDecision::Unchecked);
+                record.result = Some(ExecutionResult::Skipped);
+                self.save_action_record(record).await;
+            }
+        } else if deployment.simulation_id.is_none() && !actions.is_empty() {
+            let report = self.risk.check(deployment.id, &deployment.risk, actions);
+            if report.pause {
+                warn!("Deployment: '{}' is paused after repeated risk limit breaches", deployment.id);
+                deployment.status = DeploymentStatus::Paused;
+                paused = true;
+            }
+            for rejection in report.rejected {
+                self.publish(deployment.id, EngineEventKind::RiskRejected(rejection.clone()));
+                let record = action_record(&trigger, rejection.action, RiskDecision::Rejected(rejection.reason));
+                self.save_action_record(record).await;
+            }
+            for action in &report.approved {
+                let record = action_record(&trigger, action.clone(), RiskDecision::Approved);
+                self.pending_records.insert(record.id, record);
+            }
+            actions = report.approved;
+        }
+
+        if let Some(state_id) = deployment.state_id {
+            if let Some(state) = plugin.get_state().await {
+                let state = StateEnvelope::new(plugin.state_version(), state).to_json();
+                self.state_manager.set(&state_id.to_string(), state).await;
+            }
+        }
+        for action in &actions {
+            let Action::OrderAction(order_action) = action;
+            if let OrderActionType::CreateOrder(create_order) = &order_action.order {
+                self.order_owners.insert(create_order.id.clone(), deployment.id);
+            }
+        }
+        if deployment.simulation_id.is_none() && !actions.is_empty() {
+            self.publish(deployment.id, EngineEventKind::ActionsEmitted(actions.clone()));
+        }
+        if paused {
+            self.publish(deployment.id, EngineEventKind::Updated((&*deployment).into()));
+        }
+        actions
+    }
+
+    fn fail(&self, deployment: &mut Deployment, reason: &str) {
+        metrics::ERRORS.with_label_values(&[&metrics::deployment_label(deployment)]).inc();
+        error!("{reason}, deployment: '{}' is failed", deployment.id);
+        deployment.status = DeploymentStatus::Failed;
+        self.publish(deployment.id, EngineEventKind::PluginError(format!("{reason}, deployment is failed")));
+        self.publish(deployment.id, EngineEventKind::Updated((&*deployment).into()));
     }
 
-    fn build_plugin_internal_api(&self, deployment_id: Uuid, plugin_id: PluginId,
-                                 simulation_id: Option<Uuid>, tick: &Tick) -> Arc<DefaultPluginInternals<S, I>> {
+    fn build_plugin_internal_api(&self, deployment_id: Uuid, plugin_id: PluginId, simulation_id: Option<Uuid>,
+                                 timestamp: DateTime<Utc>, values_state_id: String) -> Arc<DefaultPluginInternals<S, I>> {
         let storage_client = Arc::clone(&self.storage_client);
         let interactor_client = Arc::clone(&self.interactor_client);
+        let state = Arc::new(DeploymentStateInternals::new(Arc::clone(&self.state_manager), values_state_id));
         Arc::new(DefaultPluginInternals::new(
             deployment_id,
             plugin_id,
             simulation_id,
             storage_client,
             interactor_client,
-            tick.timestamp,
+            timestamp,
+            state,
         ))
     }
 }
 
-fn is_subscribed(plugin: &(dyn PluginApi + Send), tick: &Tick) -> bool {
+fn action_record(trigger: &ActionTrigger, action: Action, risk: RiskDecision) -> ActionRecord {
+    let Action::OrderAction(order_action) = &action;
+    ActionRecord {
+        id: order_action.id,
+        deployment_id: order_action.deployment_id,
+        plugin_id: order_action.plugin_id.clone(),
+        timestamp: order_action.timestamp,
+        trigger: trigger.clone(),
+        action,
+        risk,
+        result: None,
+    }
+}
+
+// key-value store of the deployment, values of deployments without a state id are deleted with the deployment
+fn values_state_id(deployment: &Deployment) -> String {
+    format!("{}.kv", deployment.state_id.unwrap_or(deployment.id))
+}
+
+// hypothetical orders and fills of a shadow deployment, deleted with the deployment like its values
+fn shadow_state_id(deployment: &Deployment) -> String {
+    format!("{}.shadow", deployment.state_id.unwrap_or(deployment.id))
+}
+
+fn is_subscribed(instruments: &[InstrumentId], tick: &Tick) -> bool {
     let instrument_id = &tick.instrument_id;
-    plugin.instruments().iter().any(|subscription| {
+    instruments.iter().any(|subscription| {
         subscription.exchange.eq(&instrument_id.exchange)
             && subscription.market_type.eq(&instrument_id.market_type)
             && subscription.pair.target.eq(&instrument_id.pair.target)
             && subscription.pair.source.eq(&instrument_id.pair.source)
     })
 }
+
+fn valid_schedules(schedules: Vec<Schedule>) -> Vec<Schedule> {
+    schedules
+        .into_iter()
+        .filter(|schedule| match schedule.next_after(Utc::now()) {
+            Ok(_) => true,
+            Err(err) => {
+                warn!("Schedule: '{}' is skipped: {err}", schedule.name);
+                false
+            }
+        })
+        .collect()
+}
+
+/// Names of the schedules firing first after `after` and their fire time
+fn next_timer(schedules: &[Schedule], after: DateTime<Utc>) -> Option<(Vec<String>, DateTime<Utc>)> {
+    let fire_times: Vec<_> = schedules
+        .iter()
+        .filter_map(|schedule| Some((schedule.name.clone(), schedule.next_after(after).ok()?)))
+        .collect();
+    let time = fire_times.iter().map(|(_, time)| *time).min()?;
+    let names = fire_times
+        .into_iter()
+        .filter(|(_, fire_time)| *fire_time == time)
+        .map(|(name, _)| name)
+        .collect();
+    Some((names, time))
+}
+
+fn is_interested(instruments: &[InstrumentId], event: &PluginEventKind) -> bool {
+    match event {
+        PluginEventKind::OrderUpdate(order) => instruments
+            .iter()
+            .any(|instrument_id| instrument_id.exchange == order.exchange && instrument_id.pair == order.pair),
+        PluginEventKind::PositionUpdate(position) => instruments.iter().any(|instrument_id| {
+            instrument_id.exchange == position.exchange
+                && (instrument_id.pair.target == position.currency || instrument_id.pair.source == position.currency)
+        }),
+        PluginEventKind::CandleClosed(candle) => instruments.contains(&candle.instrument_id),
+        PluginEventKind::Start | PluginEventKind::Stop | PluginEventKind::Timer(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use domain_model::{Currency, CurrencyPair, Exchange, MarketType};
+    use engine_inmemory_persistence::InMemoryStateRepository;
+    use interactor_rest_client::InteractorRestClient;
+    use storage_rest_client::StorageRestClient;
+
+    use super::*;
+
+    const SCRIPT: &str = r#"
+        fn instruments() {
+            [#{ exchange: "OKX", market_type: "Spot", pair: #{ target: "BTC", source: "USDT" } }]
+        }
+
+        fn on_tick(tick) {
+            [create_order_action(#{
+                exchange: "OKX",
+                pair: #{ target: "BTC", source: "USDT" },
+                market_type: "Spot",
+                order_type: "Market",
+                size: #{ Target: 0.1 },
+                side: "Buy",
+            })]
+        }
+    "#;
+
+    fn runtime() -> Runtime<StorageRestClient, InteractorRestClient> {
+        // simulation deployments don't call storage and interactor
+        Runtime::new(
+            Arc::new(StorageRestClient::new("localhost:0")),
+            Arc::new(InteractorRestClient::new("localhost:0")),
+            Duration::from_secs(5),
+            4,
+            RiskSettings::default(),
+            StateManager::new(Arc::new(InMemoryStateRepository::default()), 1),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_deployment_returns_plugin_actions() {
+        let runtime = runtime();
+        let simulation_id = Some(Uuid::new_v4());
+        let plugin = plugin_loader::load_script(SCRIPT, PluginId::new("script", 0), &HashMap::new()).unwrap();
+        let deployment = Deployment {
+            id: Uuid::new_v4(),
+            status: DeploymentStatus::Running,
+            simulation_id,
+            state_id: None,
+            params: HashMap::new(),
+            execution: Default::default(),
+            risk: Default::default(),
+            mode: Default::default(),
+            upgrade: Default::default(),
+            ready: true,
+            warmup_gaps: Vec::new(),
+            plugin,
+        };
+        let deployment_id = deployment.id;
+        runtime.deploy(deployment).await.unwrap();
+
+        let pair = CurrencyPair { target: Currency::BTC, source: Currency::USDT };
+        let tick = Tick::new(simulation_id, Utc::now(), InstrumentId::new(Exchange::OKX, MarketType::Spot, pair), 40_000.);
+        let actions = runtime.get_actions(&tick).await;
+        assert_eq!(actions.len(), 1);
+        let info = runtime.get_deployments_info().await;
+        assert_eq!(info[0].status, DeploymentStatus::Running);
+        runtime.delete_deployment(deployment_id).await;
+    }
+}
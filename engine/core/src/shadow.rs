@@ -0,0 +1,427 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use uuid::Uuid;
+
+use domain_model::{Action, CreateOrder, CurrencyPair, Exchange, OrderActionType, OrderType, Side, Size, Tick};
+use domain_model::audit::{ActionRecord, ActionTrigger, RiskDecision};
+use domain_model::shadow::{DecisionSummary, ShadowFill, ShadowReport};
+
+/// Fills orders of shadow deployments hypothetically with live ticks. An order is filled on the next tick
+/// of its instrument: market orders at the tick price, limit orders at the limit price once it is reached.
+/// Open orders and fills of each deployment are returned as a state to save on every change, so the
+/// comparison outlives engine restarts.
+#[derive(Default)]
+pub struct ShadowTracker {
+    state: Mutex<ShadowState>,
+}
+
+#[derive(Default)]
+struct ShadowState {
+    prices: HashMap<(Exchange, CurrencyPair), f64>,
+    deployments: HashMap<Uuid, DeploymentShadow>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct DeploymentShadow {
+    #[serde(skip)]
+    state_id: String,
+    orders: Vec<ShadowOrder>,
+    fills: Vec<ShadowFill>,
+}
+
+impl DeploymentShadow {
+    fn to_state(&self) -> (String, String) {
+        (self.state_id.clone(), serde_json::to_string(self).unwrap())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ShadowOrder {
+    exchange: Exchange,
+    order: CreateOrder,
+}
+
+impl ShadowTracker {
+    /// Tracks the shadow deployment with its saved state
+    pub fn add_deployment(&self, deployment_id: Uuid, state_id: String, saved: Option<String>) {
+        let saved: Option<DeploymentShadow> = saved.and_then(|saved| match serde_json::from_str(&saved) {
+            Ok(shadow) => Some(shadow),
+            Err(err) => {
+                error!("Invalid shadow state of deployment: '{deployment_id}': {err}");
+                None
+            }
+        });
+        let shadow = DeploymentShadow { state_id, ..saved.unwrap_or_default() };
+        self.state.lock().unwrap().deployments.insert(deployment_id, shadow);
+    }
+
+    /// Returns states of deployments with new fills
+    pub fn on_tick(&self, tick: &Tick) -> Vec<(String, String)> {
+        if tick.simulation_id.is_some() {
+            return Vec::new();
+        }
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let key = (tick.instrument_id.exchange, tick.instrument_id.pair);
+        state.prices.insert(key, tick.price);
+        let mut changed = Vec::new();
+        for shadow in state.deployments.values_mut() {
+            let fills = shadow.fills.len();
+            let DeploymentShadow { orders, fills: shadow_fills, .. } = shadow;
+            orders.retain(|order| {
+                if (order.exchange, order.order.pair) != key {
+                    return true;
+                }
+                let Some(fill) = fill(order, tick) else {
+                    return true;
+                };
+                shadow_fills.push(fill);
+                false
+            });
+            if shadow.fills.len() != fills {
+                changed.push(shadow.to_state());
+            }
+        }
+        changed
+    }
+
+    /// Returns the state of the deployment when its orders are changed
+    pub fn on_actions(&self, deployment_id: Uuid, actions: &[Action]) -> Option<(String, String)> {
+        let mut state = self.state.lock().unwrap();
+        let shadow = state.deployments.get_mut(&deployment_id)?;
+        let mut changed = false;
+        for action in actions {
+            let Action::OrderAction(order_action) = action;
+            match &order_action.order {
+                OrderActionType::CreateOrder(create_order) => shadow.orders.push(ShadowOrder {
+                    exchange: order_action.exchange,
+                    order: create_order.clone(),
+                }),
+                OrderActionType::CancelOrder(cancel_order) => shadow.orders.retain(|order| order.order.id != cancel_order.id),
+                OrderActionType::PatchOrder => continue,
+            }
+            changed = true;
+        }
+        changed.then(|| shadow.to_state())
+    }
+
+    pub fn remove_deployment(&self, deployment_id: Uuid) {
+        self.state.lock().unwrap().deployments.remove(&deployment_id);
+    }
+
+    /// Compares audit records of both deployments, fills are taken only for the shadow deployment
+    pub fn report(
+        &self,
+        shadow_id: Uuid,
+        live_id: Uuid,
+        shadow_records: &[ActionRecord],
+        live_records: &[ActionRecord],
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> ShadowReport {
+        let state = self.state.lock().unwrap();
+        let fills: Vec<_> = state.deployments
+            .get(&shadow_id)
+            .into_iter()
+            .flat_map(|shadow| &shadow.fills)
+            .filter(|fill| from.map_or(true, |from| fill.timestamp >= from))
+            .filter(|fill| to.map_or(true, |to| fill.timestamp <= to))
+            .cloned()
+            .collect();
+        let profit = profit(&fills, &state.prices);
+
+        let shadow_decisions = decisions(shadow_records);
+        let live_decisions = decisions(live_records);
+        ShadowReport {
+            shadow_id,
+            live_id,
+            shadow: summary(shadow_records),
+            live: summary(live_records),
+            agreed: shadow_decisions.intersection(&live_decisions).count() as u32,
+            shadow_only: shadow_decisions.difference(&live_decisions).count() as u32,
+            live_only: live_decisions.difference(&shadow_decisions).count() as u32,
+            fills,
+            profit,
+        }
+    }
+}
+
+fn fill(order: &ShadowOrder, tick: &Tick) -> Option<ShadowFill> {
+    let price = match (order.order.order_type, order.order.side) {
+        (OrderType::Market, _) => tick.price,
+        (OrderType::Limit(price), Side::Buy) if tick.price <= price => price,
+        (OrderType::Limit(price), Side::Sell) if tick.price >= price => price,
+        (OrderType::Limit(_), _) => return None,
+    };
+    let size = match order.order.size {
+        Size::Target(size) => size,
+        Size::Source(size) => size / price,
+    };
+    Some(ShadowFill {
+        order_id: order.order.id.clone(),
+        timestamp: tick.timestamp,
+        exchange: order.exchange,
+        pair: order.order.pair,
+        side: order.order.side,
+        price,
+        size,
+    })
+}
+
+fn profit(fills: &[ShadowFill], prices: &HashMap<(Exchange, CurrencyPair), f64>) -> f64 {
+    // quote currency balance and target currency holding of each instrument
+    let mut balances: HashMap<(Exchange, CurrencyPair), (f64, f64)> = HashMap::new();
+    for fill in fills {
+        let (quote, holding) = balances.entry((fill.exchange, fill.pair)).or_default();
+        match fill.side {
+            Side::Buy => {
+                *quote -= fill.price * fill.size;
+                *holding += fill.size;
+            }
+            Side::Sell => {
+                *quote += fill.price * fill.size;
+                *holding -= fill.size;
+            }
+        }
+    }
+    balances
+        .into_iter()
+        .map(|(key, (quote, holding))| quote + holding * prices.get(&key).copied().unwrap_or_default())
+        .sum()
+}
+
+fn summary(records: &[ActionRecord]) -> DecisionSummary {
+    let mut summary = DecisionSummary::default();
+    for record in records {
+        if matches!(record.risk, RiskDecision::Rejected(_)) {
+            summary.rejected += 1;
+        }
+        let Action::OrderAction(order_action) = &record.action;
+        match &order_action.order {
+            OrderActionType::CreateOrder(create_order) => {
+                summary.orders += 1;
+                match create_order.side {
+                    Side::Buy => summary.buys += 1,
+                    Side::Sell => summary.sells += 1,
+                }
+            }
+            OrderActionType::CancelOrder(_) => summary.cancels += 1,
+            OrderActionType::PatchOrder => {}
+        }
+    }
+    summary
+}
+
+// only tick decisions are comparable, events of the deployments differ
+fn decisions(records: &[ActionRecord]) -> HashSet<(Uuid, Exchange, CurrencyPair, Side)> {
+    records
+        .iter()
+        .filter_map(|record| {
+            let ActionTrigger::Tick(tick) = &record.trigger else {
+                return None;
+            };
+            let Action::OrderAction(order_action) = &record.action;
+            match &order_action.order {
+                OrderActionType::CreateOrder(create_order) => Some((tick.id, order_action.exchange, create_order.pair, create_order.side)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use domain_model::{CancelOrder, Currency, InstrumentId, MarketType, OrderAction, OrderMarketType, OrderStatus, PluginId};
+
+    use super::*;
+
+    const PAIR: CurrencyPair = CurrencyPair { target: Currency::BTC, source: Currency::USDT };
+
+    fn tick(price: f64) -> Tick {
+        let instrument_id = InstrumentId {
+            exchange: Exchange::OKX,
+            market_type: MarketType::Spot,
+            pair: PAIR,
+        };
+        Tick::new(None, Utc::now(), instrument_id, price)
+    }
+
+    fn order_action(order: OrderActionType) -> Action {
+        Action::OrderAction(OrderAction {
+            id: Uuid::new_v4(),
+            deployment_id: Uuid::new_v4(),
+            simulation_id: None,
+            plugin_id: PluginId::new("test", 1),
+            timestamp: Utc::now(),
+            exchange: Exchange::OKX,
+            status: OrderStatus::Created,
+            order,
+        })
+    }
+
+    fn create_order(id: &str, order_type: OrderType, side: Side, size: Size) -> Action {
+        order_action(OrderActionType::CreateOrder(CreateOrder {
+            id: id.to_string(),
+            pair: PAIR,
+            market_type: OrderMarketType::Spot,
+            order_type,
+            side,
+            size,
+            stop_loss: None,
+            take_profit: None,
+        }))
+    }
+
+    fn record(trigger: &Tick, action: Action) -> ActionRecord {
+        ActionRecord {
+            id: Uuid::new_v4(),
+            deployment_id: Uuid::new_v4(),
+            plugin_id: PluginId::new("test", 1),
+            timestamp: Utc::now(),
+            trigger: ActionTrigger::Tick(trigger.clone()),
+            action,
+            risk: RiskDecision::Unchecked,
+            result: None,
+        }
+    }
+
+    fn tracker(deployment_id: Uuid) -> ShadowTracker {
+        let tracker = ShadowTracker::default();
+        tracker.add_deployment(deployment_id, "shadow".to_string(), None);
+        tracker
+    }
+
+    fn report(tracker: &ShadowTracker, deployment_id: Uuid) -> ShadowReport {
+        tracker.report(deployment_id, Uuid::new_v4(), &[], &[], None, None)
+    }
+
+    #[test]
+    fn test_market_fill_at_tick_price() {
+        let deployment_id = Uuid::new_v4();
+        let tracker = tracker(deployment_id);
+        tracker.on_actions(deployment_id, &[create_order("buy", OrderType::Market, Side::Buy, Size::Source(100.))]);
+        tracker.on_tick(&tick(50.));
+
+        let fills = report(&tracker, deployment_id).fills;
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].price, 50.);
+        // source size is converted to the target currency by the fill price
+        assert_eq!(fills[0].size, 2.);
+    }
+
+    #[test]
+    fn test_limit_fill_when_reached() {
+        let deployment_id = Uuid::new_v4();
+        let tracker = tracker(deployment_id);
+        tracker.on_actions(deployment_id, &[
+            create_order("buy", OrderType::Limit(90.), Side::Buy, Size::Target(1.)),
+            create_order("sell", OrderType::Limit(110.), Side::Sell, Size::Target(1.)),
+        ]);
+
+        assert!(tracker.on_tick(&tick(100.)).is_empty());
+        assert!(report(&tracker, deployment_id).fills.is_empty());
+
+        tracker.on_tick(&tick(80.));
+        let fills = report(&tracker, deployment_id).fills;
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].order_id, "buy");
+        assert_eq!(fills[0].price, 90.);
+
+        tracker.on_tick(&tick(120.));
+        let fills = report(&tracker, deployment_id).fills;
+        assert_eq!(fills.len(), 2);
+        assert_eq!(fills[1].order_id, "sell");
+        assert_eq!(fills[1].price, 110.);
+    }
+
+    #[test]
+    fn test_cancelled_order_not_filled() {
+        let deployment_id = Uuid::new_v4();
+        let tracker = tracker(deployment_id);
+        tracker.on_actions(deployment_id, &[create_order("buy", OrderType::Limit(90.), Side::Buy, Size::Target(1.))]);
+        tracker.on_actions(deployment_id, &[order_action(OrderActionType::CancelOrder(CancelOrder {
+            id: "buy".to_string(),
+            pair: PAIR,
+        }))]);
+        tracker.on_tick(&tick(80.));
+
+        assert!(report(&tracker, deployment_id).fills.is_empty());
+    }
+
+    #[test]
+    fn test_simulation_ticks_ignored() {
+        let deployment_id = Uuid::new_v4();
+        let tracker = tracker(deployment_id);
+        tracker.on_actions(deployment_id, &[create_order("buy", OrderType::Market, Side::Buy, Size::Target(1.))]);
+        let mut simulation_tick = tick(50.);
+        simulation_tick.simulation_id = Some(Uuid::new_v4());
+
+        assert!(tracker.on_tick(&simulation_tick).is_empty());
+        assert!(report(&tracker, deployment_id).fills.is_empty());
+    }
+
+    #[test]
+    fn test_profit_with_open_holding_at_last_price() {
+        let deployment_id = Uuid::new_v4();
+        let tracker = tracker(deployment_id);
+        tracker.on_actions(deployment_id, &[create_order("buy", OrderType::Market, Side::Buy, Size::Target(2.))]);
+        tracker.on_tick(&tick(100.));
+        tracker.on_actions(deployment_id, &[create_order("sell", OrderType::Market, Side::Sell, Size::Target(1.))]);
+        tracker.on_tick(&tick(120.));
+        tracker.on_tick(&tick(130.));
+
+        // -200 for the buy, +120 for the sell and 1 BTC held at 130
+        assert_eq!(report(&tracker, deployment_id).profit, 50.);
+    }
+
+    #[test]
+    fn test_report_compares_tick_decisions() {
+        let deployment_id = Uuid::new_v4();
+        let tracker = tracker(deployment_id);
+        let first = tick(100.);
+        let second = tick(110.);
+        let buy = || create_order("buy", OrderType::Market, Side::Buy, Size::Target(1.));
+        let sell = || create_order("sell", OrderType::Market, Side::Sell, Size::Target(1.));
+        let shadow_records = vec![record(&first, buy()), record(&second, buy())];
+        let live_records = vec![record(&first, buy()), record(&second, sell())];
+
+        let report = tracker.report(deployment_id, Uuid::new_v4(), &shadow_records, &live_records, None, None);
+        assert_eq!(report.agreed, 1);
+        assert_eq!(report.shadow_only, 1);
+        assert_eq!(report.live_only, 1);
+        assert_eq!(report.shadow.buys, 2);
+        assert_eq!(report.live.sells, 1);
+    }
+
+    #[test]
+    fn test_saved_state_restores_orders_and_fills() {
+        let deployment_id = Uuid::new_v4();
+        let tracker = tracker(deployment_id);
+        tracker.on_actions(deployment_id, &[create_order("buy", OrderType::Market, Side::Buy, Size::Target(1.))]);
+        let (state_id, _) = tracker.on_tick(&tick(100.)).pop().unwrap();
+        let (_, state) = tracker
+            .on_actions(deployment_id, &[create_order("sell", OrderType::Limit(120.), Side::Sell, Size::Target(1.))])
+            .unwrap();
+        assert_eq!(state_id, "shadow");
+
+        let restored = ShadowTracker::default();
+        restored.add_deployment(deployment_id, state_id, Some(state));
+        assert_eq!(report(&restored, deployment_id).fills.len(), 1);
+        restored.on_tick(&tick(130.));
+        assert_eq!(report(&restored, deployment_id).fills.len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_saved_state_ignored() {
+        let deployment_id = Uuid::new_v4();
+        let tracker = ShadowTracker::default();
+        tracker.add_deployment(deployment_id, "shadow".to_string(), Some("invalid".to_string()));
+
+        assert!(report(&tracker, deployment_id).fills.is_empty());
+        assert!(tracker.on_actions(deployment_id, &[create_order("buy", OrderType::Market, Side::Buy, Size::Target(1.))]).is_some());
+    }
+}
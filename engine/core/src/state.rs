@@ -0,0 +1,320 @@
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use axum::async_trait;
+use chrono::Utc;
+use dashmap::DashMap;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::{error, info};
+
+use domain_model::state::StateSnapshot;
+use engine_persistence_api::StateRepository;
+use plugin_api::{PluginApi, StateInternalApi};
+
+use crate::metrics;
+
+/// Plugin states keyed by their state ids, the latest version of each used state is kept in memory
+/// and every changed state is saved to the repository as its next version
+pub struct StateManager {
+    repository: Arc<dyn StateRepository>,
+    // saved versions of each state, 0 keeps all versions
+    history: u64,
+    // `None` for states without saved versions
+    latest: DashMap<String, Option<StateSnapshot>>,
+}
+
+impl StateManager {
+    pub fn new(repository: Arc<dyn StateRepository>, history: u64) -> Self {
+        Self {
+            repository,
+            history,
+            latest: DashMap::new(),
+        }
+    }
+
+    pub async fn get(&self, state_id: &str) -> Option<String> {
+        self.latest(state_id)
+            .await
+            .unwrap_or_else(|err| {
+                error!("Error during state: '{state_id}' loading: {err}");
+                None
+            })
+            .map(|snapshot| snapshot.state)
+    }
+
+    /// Saves the state as a new version when it differs from the latest version
+    pub async fn set(&self, state_id: &str, state: String) {
+        let latest = match self.latest(state_id).await {
+            Ok(latest) => latest,
+            Err(err) => {
+                // the version is unknown, saving now could overwrite existing versions
+                error!("State: '{state_id}' is not saved, error during loading: {err}");
+                return;
+            }
+        };
+        if latest.as_ref().is_some_and(|latest| latest.state == state) {
+            return;
+        }
+        let snapshot = StateSnapshot {
+            state_id: state_id.to_string(),
+            version: latest.map_or(1, |latest| latest.version + 1),
+            timestamp: Utc::now(),
+            state,
+        };
+        match self.repository.save(&snapshot, self.history).await {
+            Ok(_) => {
+                metrics::STATE_SAVES.inc();
+                metrics::STATE_SIZE.set(snapshot.state.len() as i64);
+                self.latest.insert(state_id.to_string(), Some(snapshot));
+            }
+            // the latest version stays the saved one, so the state is saved again with the next change
+            Err(err) => error!("Error during state: '{state_id}' version: '{}' saving: {err}", snapshot.version),
+        }
+    }
+
+    pub async fn history(&self, state_id: &str) -> Vec<StateSnapshot> {
+        self.repository
+            .get_all(state_id)
+            .await
+            .unwrap_or_else(|err| {
+                error!("Error during state: '{state_id}' history loading: {err}");
+                Vec::new()
+            })
+    }
+
+    /// Saves the state of the version as the latest version, so a rollback can be rolled back too
+    pub async fn rollback(&self, state_id: &str, version: i64) -> Option<String> {
+        let snapshot = self
+            .history(state_id)
+            .await
+            .into_iter()
+            .find(|snapshot| snapshot.version == version)?;
+        self.set(state_id, snapshot.state.clone()).await;
+        Some(snapshot.state)
+    }
+
+    /// Latest saved state written by a plugin with the state version, the pinned state is used
+    /// when the history has no such version anymore
+    pub async fn latest_of(&self, state_id: &str, state_version: u32) -> Option<String> {
+        let mut history = self.history(state_id).await;
+        history.extend(self.history(&pinned_id(state_id)).await);
+        history
+            .into_iter()
+            .rev()
+            .map(|snapshot| snapshot.state)
+            .find(|state| StateEnvelope::parse(state).version == state_version)
+    }
+
+    /// Copies the latest version aside from the pruned history, so it can be restored however
+    /// many versions are saved after it. Only the last pinned version is kept
+    pub async fn pin(&self, state_id: &str) {
+        let Some(state) = self.get(state_id).await else {
+            return;
+        };
+        let pinned_id = pinned_id(state_id);
+        let pinned = self.history(&pinned_id).await.pop();
+        if pinned.as_ref().is_some_and(|pinned| pinned.state == state) {
+            return;
+        }
+        let snapshot = StateSnapshot {
+            state_id: pinned_id,
+            version: pinned.map_or(1, |pinned| pinned.version + 1),
+            timestamp: Utc::now(),
+            state,
+        };
+        if let Err(err) = self.repository.save(&snapshot, 1).await {
+            error!("Error during state: '{state_id}' pinning: {err}");
+        }
+    }
+
+    pub async fn remove(&self, state_id: &str) {
+        self.latest.remove(state_id);
+        for state_id in [state_id.to_string(), pinned_id(state_id)] {
+            if let Err(err) = self.repository.delete(&state_id).await {
+                error!("Error during state: '{state_id}' deletion: {err}");
+            }
+        }
+    }
+
+    async fn latest(&self, state_id: &str) -> Result<Option<StateSnapshot>> {
+        if let Some(latest) = self.latest.get(state_id) {
+            return Ok(latest.clone());
+        }
+        let latest = self.repository.get_all(state_id).await?.pop();
+        self.latest.insert(state_id.to_string(), latest.clone());
+        Ok(latest)
+    }
+}
+
+fn pinned_id(state_id: &str) -> String {
+    format!("{state_id}-pinned")
+}
+
+/// Plugin state saved with the version of its format, so a newer plugin can migrate it
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateEnvelope {
+    pub version: u32,
+    pub state: String,
+}
+
+impl StateEnvelope {
+    pub fn new(version: u32, state: String) -> Self {
+        Self { version, state }
+    }
+
+    /// States saved before versioning are plain plugin states of version 0
+    pub fn parse(saved: &str) -> Self {
+        serde_json::from_str(saved).unwrap_or_else(|_| Self::new(0, saved.to_string()))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// Passes the saved state to the plugin, a state of another version is migrated by the plugin first.
+/// Returns the migrated state to save, a panic inside the plugin is an error too
+pub async fn restore_state(plugin: &mut Box<dyn PluginApi + Send + Sync>, saved: &str) -> Result<Option<String>> {
+    let envelope = StateEnvelope::parse(saved);
+    let version = plugin.state_version();
+    let migrated = envelope.version != version;
+    let state = if migrated {
+        info!("Migrating state of plugin: '{}' from version: '{}' to '{version}'", plugin.id().name, envelope.version);
+        catch_unwind(AssertUnwindSafe(|| plugin.migrate_state(envelope.version, &envelope.state)))
+            .map_err(|_| anyhow!("plugin panicked during state migration"))??
+    } else {
+        envelope.state
+    };
+    AssertUnwindSafe(plugin.set_state(&state))
+        .catch_unwind()
+        .await
+        .map_err(|_| anyhow!("plugin panicked during state restoring"))?;
+    Ok(migrated.then(|| StateEnvelope::new(version, state).to_json()))
+}
+
+/// Key-value store of a deployment, values are saved as a JSON object state
+pub struct DeploymentStateInternals {
+    states: Arc<StateManager>,
+    state_id: String,
+}
+
+impl DeploymentStateInternals {
+    pub fn new(states: Arc<StateManager>, state_id: String) -> Self {
+        Self { states, state_id }
+    }
+
+    async fn values(&self) -> Map<String, Value> {
+        let Some(state) = self.states.get(&self.state_id).await else {
+            return Map::new();
+        };
+        serde_json::from_str(&state).unwrap_or_else(|err| {
+            error!("Invalid key-value state: '{}': {err}", self.state_id);
+            Map::new()
+        })
+    }
+}
+
+#[async_trait]
+impl StateInternalApi for DeploymentStateInternals {
+    async fn set(&self, key: &str, state: Value) {
+        let mut values = self.values().await;
+        values.insert(key.to_string(), state);
+        self.states.set(&self.state_id, Value::Object(values).to_string()).await;
+    }
+
+    async fn get(&self, key: &str) -> Option<Value> {
+        self.values().await.remove(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::bail;
+
+    use engine_inmemory_persistence::InMemoryStateRepository;
+
+    use super::*;
+
+    struct FailingStateRepository;
+
+    #[async_trait]
+    impl StateRepository for FailingStateRepository {
+        async fn save(&self, _snapshot: &StateSnapshot, _history: u64) -> Result<()> {
+            bail!("storage is unavailable")
+        }
+
+        async fn get_all(&self, _state_id: &str) -> Result<Vec<StateSnapshot>> {
+            Ok(Vec::new())
+        }
+
+        async fn delete(&self, _state_id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn versions(history: &[StateSnapshot]) -> Vec<i64> {
+        history.iter().map(|snapshot| snapshot.version).collect()
+    }
+
+    #[tokio::test]
+    async fn test_changed_state_is_next_version() {
+        let states = StateManager::new(Arc::new(InMemoryStateRepository::default()), 0);
+        states.set("state", String::from("a")).await;
+        states.set("state", String::from("a")).await;
+        states.set("state", String::from("b")).await;
+
+        assert_eq!(versions(&states.history("state").await), vec![1, 2]);
+        assert_eq!(states.get("state").await, Some(String::from("b")));
+    }
+
+    #[tokio::test]
+    async fn test_history_is_pruned() {
+        let states = StateManager::new(Arc::new(InMemoryStateRepository::default()), 2);
+        for state in ["a", "b", "c"] {
+            states.set("state", state.to_string()).await;
+        }
+
+        assert_eq!(versions(&states.history("state").await), vec![2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_saves_next_version() {
+        let states = StateManager::new(Arc::new(InMemoryStateRepository::default()), 0);
+        states.set("state", String::from("a")).await;
+        states.set("state", String::from("b")).await;
+
+        assert_eq!(states.rollback("state", 1).await, Some(String::from("a")));
+        assert_eq!(states.rollback("state", 5).await, None);
+        let history = states.history("state").await;
+        assert_eq!(versions(&history), vec![1, 2, 3]);
+        assert_eq!(history[2].state, "a");
+        assert_eq!(states.get("state").await, Some(String::from("a")));
+    }
+
+    #[tokio::test]
+    async fn test_pinned_state_outlives_pruning() {
+        let states = StateManager::new(Arc::new(InMemoryStateRepository::default()), 1);
+        let old = StateEnvelope::new(1, String::from("old")).to_json();
+        states.set("state", old.clone()).await;
+        states.pin("state").await;
+        states.set("state", StateEnvelope::new(2, String::from("new")).to_json()).await;
+
+        assert_eq!(versions(&states.history("state").await), vec![2]);
+        assert_eq!(states.latest_of("state", 1).await, Some(old));
+
+        states.remove("state").await;
+        assert_eq!(states.latest_of("state", 1).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_unsaved_state_is_not_cached() {
+        let states = StateManager::new(Arc::new(FailingStateRepository), 0);
+        states.set("state", String::from("a")).await;
+
+        assert_eq!(states.get("state").await, None);
+    }
+}
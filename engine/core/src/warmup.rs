@@ -0,0 +1,33 @@
+use chrono::Duration;
+use tracing::{debug, error};
+
+use domain_model::warmup::{WarmupGap, WarmupWindow};
+use storage_core_api::StorageApi;
+
+/// Syncs the windows and returns the windows without all their closed candles stored
+pub async fn sync<S: StorageApi>(storage_client: &S, windows: &[WarmupWindow]) -> Vec<WarmupGap> {
+    let mut gaps = Vec::new();
+    for window in windows {
+        let sync_result = storage_client
+            .sync(&window.instrument_id, &[window.timeframe], window.from, Some(window.to))
+            .await;
+        if let Err(err) = sync_result {
+            error!("Error during warmup sync of instrument: '{:?}', timeframe: '{}': {err}", window.instrument_id, window.timeframe);
+        }
+        // `to` is the open time of the current candle, only closed candles are counted
+        let candles = storage_client
+            .get_candles(&window.instrument_id, Some(window.timeframe), Some(window.from), Some(window.to - Duration::milliseconds(1)), Some(window.bars))
+            .await
+            .map_or(0, |candles| candles.len() as u64);
+        if candles < window.bars {
+            debug!("Warmup of instrument: '{:?}', timeframe: '{}' has '{candles}' of '{}' candles", window.instrument_id, window.timeframe, window.bars);
+            gaps.push(WarmupGap {
+                instrument_id: window.instrument_id.clone(),
+                timeframe: window.timeframe,
+                bars: window.bars,
+                candles,
+            });
+        }
+    }
+    gaps
+}
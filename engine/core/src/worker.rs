@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use tokio::sync::{Notify, oneshot};
+use tracing::warn;
+
+use domain_model::{Action, Backpressure, PluginEvent, Tick};
+
+pub enum Task {
+    Tick(Tick),
+    Event(PluginEvent),
+}
+
+pub struct Job {
+    pub task: Task,
+    pub reply: oneshot::Sender<Vec<Action>>,
+}
+
+/// Bounded queue of tasks waiting for a deployment worker. Backpressure applies only to ticks,
+/// events are never dropped. Jobs dropped from the queue close their reply channels,
+/// so waiters get no actions for skipped ticks.
+pub struct TaskQueue {
+    jobs: Mutex<VecDeque<Job>>,
+    notify: Notify,
+    capacity: usize,
+    backpressure: Backpressure,
+    closed: AtomicBool,
+}
+
+impl TaskQueue {
+    pub fn new(capacity: usize, backpressure: Backpressure) -> Self {
+        Self {
+            jobs: Mutex::new(VecDeque::new()),
+            notify: Notify::new(),
+            capacity: capacity.max(1),
+            backpressure,
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    pub fn push(&self, task: Task) -> oneshot::Receiver<Vec<Action>> {
+        let (reply, receiver) = oneshot::channel();
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Task::Tick(tick) = &task {
+            if self.backpressure == Backpressure::Coalesce {
+                jobs.retain(|job| !matches!(&job.task, Task::Tick(queued) if queued.instrument_id == tick.instrument_id));
+            }
+            if jobs.len() >= self.capacity {
+                let oldest_tick = jobs.iter().position(|job| matches!(job.task, Task::Tick(_)));
+                if let Some(Job { task: Task::Tick(dropped), .. }) = oldest_tick.and_then(|index| jobs.remove(index)) {
+                    warn!("Task queue is full, tick: '{}' is dropped", dropped.id);
+                }
+            }
+        }
+        jobs.push_back(Job { task, reply });
+        drop(jobs);
+        self.notify.notify_one();
+        receiver
+    }
+
+    /// Waits for the next job, `None` when the queue is closed and all queued jobs are taken
+    pub async fn pop(&self) -> Option<Job> {
+        loop {
+            if let Some(job) = self.jobs.lock().unwrap().pop_front() {
+                return Some(job);
+            }
+            if self.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.notify.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use domain_model::{Currency, CurrencyPair, Exchange, InstrumentId, MarketType, PluginEventKind};
+
+    use super::*;
+
+    fn tick(target: Currency) -> Tick {
+        let instrument_id = InstrumentId {
+            exchange: Exchange::OKX,
+            market_type: MarketType::Spot,
+            pair: CurrencyPair { target, source: Currency::USDT },
+        };
+        Tick::new(None, Utc::now(), instrument_id, 1.0)
+    }
+
+    fn event() -> PluginEvent {
+        PluginEvent {
+            simulation_id: None,
+            deployment_id: None,
+            timestamp: Utc::now(),
+            kind: PluginEventKind::Start,
+        }
+    }
+
+    async fn tick_id(queue: &TaskQueue) -> Uuid {
+        match queue.pop().await.unwrap().task {
+            Task::Tick(tick) => tick.id,
+            Task::Event(_) => panic!("Tick is expected"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_tick() {
+        let queue = TaskQueue::new(2, Backpressure::DropOldest);
+        let (first, second, third) = (tick(Currency::BTC), tick(Currency::BTC), tick(Currency::TON));
+        let dropped = queue.push(Task::Tick(first));
+        queue.push(Task::Tick(second.clone()));
+        queue.push(Task::Tick(third.clone()));
+
+        assert!(dropped.await.is_err());
+        assert_eq!(tick_id(&queue).await, second.id);
+        assert_eq!(tick_id(&queue).await, third.id);
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_ticks_of_instrument() {
+        let queue = TaskQueue::new(10, Backpressure::Coalesce);
+        let (btc, ton, latest_btc) = (tick(Currency::BTC), tick(Currency::TON), tick(Currency::BTC));
+        let replaced = queue.push(Task::Tick(btc));
+        queue.push(Task::Tick(ton.clone()));
+        queue.push(Task::Tick(latest_btc.clone()));
+
+        assert!(replaced.await.is_err());
+        assert_eq!(tick_id(&queue).await, ton.id);
+        assert_eq!(tick_id(&queue).await, latest_btc.id);
+    }
+
+    #[tokio::test]
+    async fn test_events_are_not_dropped() {
+        let queue = TaskQueue::new(1, Backpressure::DropOldest);
+        queue.push(Task::Event(event()));
+        queue.push(Task::Event(event()));
+        queue.close();
+
+        assert!(matches!(queue.pop().await.unwrap().task, Task::Event(_)));
+        assert!(matches!(queue.pop().await.unwrap().task, Task::Event(_)));
+        assert!(queue.pop().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_close_drains_queued_jobs() {
+        let queue = TaskQueue::new(10, Backpressure::DropOldest);
+        let (first, second) = (tick(Currency::BTC), tick(Currency::TON));
+        queue.push(Task::Tick(first.clone()));
+        queue.push(Task::Tick(second.clone()));
+        queue.close();
+
+        assert_eq!(tick_id(&queue).await, first.id);
+        assert_eq!(tick_id(&queue).await, second.id);
+        assert!(queue.pop().await.is_none());
+    }
+}
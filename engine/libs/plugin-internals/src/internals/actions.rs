@@ -1,20 +1,27 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
-use chrono::Utc;
 use uuid::Uuid;
 
 use domain_model::{Action, CancelOrder, CreateOrder, CurrencyPair, Exchange, OrderAction, OrderActionType, OrderMarketType, OrderStatus, OrderType, PluginId, Side, Size, Trigger};
-use plugin_api::{ActionsInternalApi, utils};
+use plugin_api::{ActionsInternalApi, ClockInternalApi, utils};
+
+use crate::clock::DefaultClockInternals;
 
 pub struct DefaultActionInternals {
+    deployment_id: Uuid,
     simulation_id: Option<Uuid>,
     plugin_id: PluginId,
+    clock: Arc<DefaultClockInternals>,
 }
 
 impl DefaultActionInternals {
-    pub fn new(simulation_id: Option<Uuid>, plugin_id: PluginId) -> Self {
+    pub fn new(deployment_id: Uuid, simulation_id: Option<Uuid>, plugin_id: PluginId, clock: Arc<DefaultClockInternals>) -> Self {
         Self {
+            deployment_id,
             simulation_id,
             plugin_id,
+            clock,
         }
     }
 }
@@ -32,15 +39,17 @@ impl ActionsInternalApi for DefaultActionInternals {
         stop_loss: Option<Trigger>,
         take_profit: Option<Trigger>,
     ) -> Action {
+        let timestamp = self.clock.now();
         Action::OrderAction(OrderAction {
             id: Uuid::new_v4(),
+            deployment_id: self.deployment_id,
             simulation_id: self.simulation_id,
             plugin_id: self.plugin_id.clone(),
-            timestamp: Utc::now(),
+            timestamp,
             status: OrderStatus::Created,
             exchange,
             order: OrderActionType::CreateOrder(CreateOrder {
-                id: utils::string_id(),
+                id: utils::string_id_at(timestamp),
                 pair,
                 market_type,
                 order_type,
@@ -59,9 +68,10 @@ impl ActionsInternalApi for DefaultActionInternals {
         order_id: &str) -> Action {
         Action::OrderAction(OrderAction {
             id: Uuid::new_v4(),
+            deployment_id: self.deployment_id,
             simulation_id: self.simulation_id,
             plugin_id: self.plugin_id.clone(),
-            timestamp: Utc::now(),
+            timestamp: self.clock.now(),
             status: OrderStatus::Created,
             exchange,
             order: OrderActionType::CancelOrder(CancelOrder {
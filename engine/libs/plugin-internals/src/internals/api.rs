@@ -7,12 +7,13 @@ use domain_model::PluginId;
 use indicators::cache::IndicatorCache;
 use indicators::Indicators;
 use interactor_core_api::InteractorApi;
-use plugin_api::{AccountInternalApi, ActionsInternalApi, CandlesInternalApi, DrawingsInternalApi, IndicatorsInternalApi, OrdersInternalApi, PluginInternalApi, PositionsInternalApi};
+use plugin_api::{AccountInternalApi, ActionsInternalApi, CandlesInternalApi, ClockInternalApi, DrawingsInternalApi, IndicatorsInternalApi, OrdersInternalApi, PluginInternalApi, PositionsInternalApi, StateInternalApi};
 use storage_core_api::StorageApi;
 
 use crate::account::DefaultAccountInternals;
 use crate::actions::DefaultActionInternals;
 use crate::candles::DefaultCandleInternals;
+use crate::clock::DefaultClockInternals;
 use crate::drawings::DefaultDrawingInternals;
 use crate::indicators::DefaultIndicatorInternals;
 use crate::orders::DefaultOrderInternals;
@@ -26,6 +27,8 @@ pub struct DefaultPluginInternals<S: StorageApi, I: InteractorApi> {
     indicators: Arc<DefaultIndicatorInternals<IndicatorCache<Indicators<S>>>>,
     drawings: Arc<DefaultDrawingInternals<S>>,
     account: Arc<DefaultAccountInternals<I>>,
+    clock: Arc<DefaultClockInternals>,
+    state: Arc<dyn StateInternalApi>,
 }
 
 impl<S: StorageApi, I: InteractorApi> DefaultPluginInternals<S, I> {
@@ -36,9 +39,11 @@ impl<S: StorageApi, I: InteractorApi> DefaultPluginInternals<S, I> {
         storage_client: Arc<S>,
         interactor_client: Arc<I>,
         timestamp: DateTime<Utc>,
+        state: Arc<dyn StateInternalApi>,
     ) -> Self {
+        let clock = Arc::new(DefaultClockInternals::new(simulation_id.is_some(), timestamp));
         Self {
-            actions: Arc::new(DefaultActionInternals::new(simulation_id, plugin_id)),
+            actions: Arc::new(DefaultActionInternals::new(deployment_id, simulation_id, plugin_id, Arc::clone(&clock))),
             orders: Arc::new(DefaultOrderInternals::new(Arc::clone(&storage_client))),
             positions: Arc::new(DefaultPositionInternals::new(Arc::clone(&storage_client))),
             candles: Arc::new(DefaultCandleInternals::new(Arc::clone(&storage_client), timestamp)),
@@ -48,6 +53,8 @@ impl<S: StorageApi, I: InteractorApi> DefaultPluginInternals<S, I> {
                 Arc::clone(&storage_client),
             )),
             account: Arc::new(DefaultAccountInternals::new(Arc::clone(&interactor_client))),
+            clock,
+            state,
         }
     }
 }
@@ -80,4 +87,12 @@ impl<S: StorageApi, I: InteractorApi> PluginInternalApi for DefaultPluginInterna
     fn account(&self) -> Arc<dyn AccountInternalApi> {
         self.account.clone()
     }
+
+    fn clock(&self) -> Arc<dyn ClockInternalApi> {
+        self.clock.clone()
+    }
+
+    fn state(&self) -> Arc<dyn StateInternalApi> {
+        self.state.clone()
+    }
 }
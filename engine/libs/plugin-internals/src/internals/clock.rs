@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+
+use plugin_api::ClockInternalApi;
+
+pub struct DefaultClockInternals {
+    // tick time of a simulation, live deployments use the wall-clock
+    simulated: Option<DateTime<Utc>>,
+}
+
+impl DefaultClockInternals {
+    pub fn new(is_simulation: bool, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            simulated: is_simulation.then_some(timestamp),
+        }
+    }
+}
+
+impl ClockInternalApi for DefaultClockInternals {
+    fn now(&self) -> DateTime<Utc> {
+        self.simulated.unwrap_or_else(Utc::now)
+    }
+}
@@ -6,3 +6,4 @@ pub mod orders;
 pub mod positions;
 pub mod candles;
 pub mod account;
+pub mod clock;
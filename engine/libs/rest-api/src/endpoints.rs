@@ -1,5 +1,18 @@
 pub const GET_DEPLOYMENTS: &str = "/api/v1/engine/deployments";
 pub const POST_CREATE_DEPLOYMENTS: &str = "/api/v1/engine/deployments";
 pub const DELETE_DEPLOYMENT: &str = "/api/v1/engine/deployments/:id";
+pub const PATCH_DEPLOYMENT: &str = "/api/v1/engine/deployments/:id";
+pub const POST_PAUSE_DEPLOYMENT: &str = "/api/v1/engine/deployments/:id/pause";
+pub const POST_RESUME_DEPLOYMENT: &str = "/api/v1/engine/deployments/:id/resume";
+pub const GET_DEPLOYMENT_STATE: &str = "/api/v1/engine/deployments/:id/state";
+pub const GET_STATE_HISTORY: &str = "/api/v1/engine/deployments/:id/state/history";
+pub const POST_ROLLBACK_STATE: &str = "/api/v1/engine/deployments/:id/state/rollback";
+pub const POST_ROLLBACK_PLUGIN: &str = "/api/v1/engine/deployments/:id/rollback";
+pub const GET_SHADOW_REPORT: &str = "/api/v1/engine/deployments/:id/shadow-report";
 pub const POST_CREATE_ACTIONS: &str = "/api/v1/engine/actions";
+pub const POST_CREATE_ACTIONS_BATCH: &str = "/api/v1/engine/actions/batch";
+pub const POST_CREATE_EVENT: &str = "/api/v1/engine/events";
+pub const GET_EVENTS_STREAM: &str = "/api/v1/engine/events/stream";
+pub const GET_RISK_REJECTIONS: &str = "/api/v1/engine/risk/rejections";
+pub const POST_KILL_SWITCH: &str = "/api/v1/engine/kill-switch";
 pub const PUT_UPDATE_PLUGIN: &str = "/api/v1/engine/plugins";
@@ -2,12 +2,22 @@ use std::collections::HashMap;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use thiserror::Error;
+use tokio::sync::broadcast::Receiver;
 use uuid::Uuid;
 
-use domain_model::{Action, DeploymentInfo, DeploymentStatus, NewDeployment, PluginId, Tick};
+use domain_model::{Action, DeploymentInfo, DeploymentMode, DeploymentPatch, DeploymentStatus, ExecutionPolicy, KillSwitch, KillSwitchReport, NewDeployment, PluginEvent, PluginId, RiskLimits, RiskRejection, Tick, UpgradePolicy};
+use domain_model::events::EngineEvent;
+use domain_model::params::ParamError;
+use domain_model::shadow::ShadowReport;
+use domain_model::state::StateSnapshot;
+use domain_model::warmup::WarmupGap;
 use plugin_loader::Plugin;
 
+/// Buffered events of each subscriber, slow subscribers skip the oldest events
+pub const EVENTS_CAPACITY: usize = 1024;
+
 #[async_trait]
 pub trait EngineApi: Send + Sync + 'static {
     async fn get_deployments_info(&self) -> Vec<DeploymentInfo>;
@@ -16,16 +26,44 @@ pub trait EngineApi: Send + Sync + 'static {
         deployments: &[NewDeployment],
     ) -> Result<Vec<DeploymentInfo>, EngineError>;
     async fn get_actions(&self, tick: &Tick) -> Vec<Action>;
+    async fn get_actions_batch(&self, ticks: &[Tick]) -> Vec<Vec<Action>>;
+    async fn handle_event(&self, event: PluginEvent) -> Vec<Action>;
     async fn delete_deployment(&self, id: Uuid) -> Option<DeploymentInfo>;
+    async fn get_deployment_state(&self, id: Uuid) -> Option<String>;
+    /// Saved versions of the deployment state, `None` if the deployment is not found or has no state id
+    async fn get_state_history(&self, id: Uuid) -> Option<Vec<StateSnapshot>>;
+    /// Restores the saved state version, `None` if the deployment or the version is not found
+    async fn rollback_state(&self, id: Uuid, version: i64) -> Option<String>;
+    /// Reconfigures the plugin with params valid for its schema, `None` if the deployment is not found
+    async fn update_deployment(&self, id: Uuid, patch: DeploymentPatch) -> Result<Option<DeploymentInfo>, EngineError>;
+    async fn pause_deployment(&self, id: Uuid) -> Option<DeploymentInfo>;
+    async fn resume_deployment(&self, id: Uuid) -> Option<DeploymentInfo>;
+    async fn get_risk_rejections(&self, deployment_id: Option<Uuid>) -> Vec<RiskRejection>;
+    /// Compares decisions of the shadow deployment with the live one, `None` if the shadow deployment is not found
+    async fn get_shadow_report(&self, id: Uuid, live_id: Uuid, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Option<ShadowReport>;
+    /// Pauses all deployments and stops trading on all exchanges, repeated calls are safe
+    async fn kill_switch(&self, request: KillSwitch) -> KillSwitchReport;
+    /// Live stream of deployment lifecycle, processed ticks, actions, risk rejections and plugin errors
+    fn subscribe_events(&self) -> Receiver<EngineEvent>;
+    /// Upgrades deployments of older versions of the plugin by their upgrade policies
     async fn update_plugin(&self, plugin_id: PluginId);
+    /// Redeploys the previous plugin version with a state saved by it, `None` if the deployment is not found
+    async fn rollback_plugin(&self, id: Uuid) -> Result<Option<DeploymentInfo>, EngineError>;
 }
 
 #[derive(Debug)]
 pub struct Deployment {
     pub id: Uuid,
+    pub status: DeploymentStatus,
     pub simulation_id: Option<Uuid>,
     pub state_id: Option<Uuid>,
     pub params: HashMap<String, String>,
+    pub execution: ExecutionPolicy,
+    pub risk: RiskLimits,
+    pub mode: DeploymentMode,
+    pub upgrade: UpgradePolicy,
+    pub ready: bool,
+    pub warmup_gaps: Vec<WarmupGap>,
     pub plugin: Plugin,
 }
 
@@ -33,13 +71,21 @@ impl From<&Deployment> for DeploymentInfo {
     fn from(value: &Deployment) -> Self {
         Self {
             id: value.id,
-            status: DeploymentStatus::Created,
+            status: value.status.clone(),
             simulation_id: value.simulation_id,
             state_id: value.state_id,
             plugin_id: value.plugin.api.id(),
             params: value.params.clone(),
             subscriptions: value.plugin.api.instruments(),
             indicators: value.plugin.api.indicators(),
+            schedules: value.plugin.api.schedules(),
+            warmup: value.plugin.api.warmup(),
+            ready: value.ready,
+            warmup_gaps: value.warmup_gaps.clone(),
+            execution: value.execution.clone(),
+            risk: value.risk.clone(),
+            mode: value.mode,
+            upgrade: value.upgrade,
         }
     }
 }
@@ -50,4 +96,10 @@ pub enum EngineError {
     PluginNotFound,
     #[error("Failed to load plugin")]
     PluginLoadingError,
+    #[error("Invalid script: {0}")]
+    InvalidScript(String),
+    #[error("Invalid params: {0:?}")]
+    InvalidParams(Vec<ParamError>),
+    #[error("State migration failed: {0}")]
+    StateMigrationError(String),
 }
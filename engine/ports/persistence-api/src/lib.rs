@@ -0,0 +1,4 @@
+pub use repositories::DeploymentRepository;
+pub use repositories::StateRepository;
+
+mod repositories;
@@ -0,0 +1,23 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use domain_model::DeploymentInfo;
+use domain_model::state::StateSnapshot;
+
+#[async_trait]
+pub trait DeploymentRepository: Send + Sync + 'static {
+    async fn get_all(&self) -> Vec<DeploymentInfo>;
+    async fn save(&self, deployment: &DeploymentInfo) -> Result<()>;
+    async fn delete(&self, id: Uuid) -> Result<()>;
+}
+
+/// Versioned plugin states, a saved snapshot is never changed
+#[async_trait]
+pub trait StateRepository: Send + Sync + 'static {
+    /// Saves the next version of the state, only the last `history` versions are kept, 0 keeps all versions
+    async fn save(&self, snapshot: &StateSnapshot, history: u64) -> Result<()>;
+    /// Saved versions of the state ordered by version
+    async fn get_all(&self, state_id: &str) -> Result<Vec<StateSnapshot>>;
+    async fn delete(&self, state_id: &str) -> Result<()>;
+}
@@ -101,8 +101,9 @@ impl<E: EngineApi, S: StorageApi> ExchangeApi for BybitExchange<E, S> {
         let id: &str = &format!("candles-{}", &inst_id);
         let already_exists = self.sockets.lock().await.borrow().contains_key(id);
         if !already_exists {
+            let engine_client = Arc::clone(&self.engine_client);
             let storage_client = Arc::clone(&self.storage_client);
-            let handler = CandleHandler::new(*currency_pair, *market_type, storage_client);
+            let handler = CandleHandler::new(engine_client, *currency_pair, *market_type, storage_client);
             let client = BybitWsClient::public(&self.ws_url, handler).await;
 
             let subscribe_command = Command::subscribe(vec![
@@ -138,8 +139,9 @@ impl<E: EngineApi, S: StorageApi> ExchangeApi for BybitExchange<E, S> {
         const ID: &str = "orders";
         let already_exists = self.sockets.lock().await.borrow().contains_key(ID);
         if !already_exists {
+            let engine_client = Arc::clone(&self.engine_client);
             let storage_client = Arc::clone(&self.storage_client);
-            let handler = OrderHandler::new(storage_client);
+            let handler = OrderHandler::new(engine_client, storage_client);
             let client = BybitWsClient::private(
                 &self.ws_url,
                 &self.api_key,
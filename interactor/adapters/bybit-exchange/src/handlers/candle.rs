@@ -6,24 +6,30 @@ use serde_json::{from_value, Value};
 use tracing::trace;
 
 use domain_model::{
-    Candle, CandleStatus, CurrencyPair, Exchange, InstrumentId, MarketType, Timeframe,
+    Candle, CandleStatus, CurrencyPair, Exchange, InstrumentId, MarketType, PluginEventKind, Timeframe,
 };
 use eac::bybit::websocket::{CandleResponse, WsMessageHandler};
+use engine_core_api::api::EngineApi;
 use storage_core_api::StorageApi;
 
-pub struct CandleHandler<S: StorageApi> {
+use super::plugin_event;
+
+pub struct CandleHandler<E: EngineApi, S: StorageApi> {
+    engine_client: Arc<E>,
     currency_pair: CurrencyPair,
     market_type: MarketType,
     storage_client: Arc<S>,
 }
 
-impl<S: StorageApi> CandleHandler<S> {
+impl<E: EngineApi, S: StorageApi> CandleHandler<E, S> {
     pub fn new(
+        engine_client: Arc<E>,
         currency_pair: CurrencyPair,
         market_type: MarketType,
         storage_client: Arc<S>,
     ) -> Self {
         Self {
+            engine_client,
             currency_pair,
             market_type,
             storage_client,
@@ -32,7 +38,7 @@ impl<S: StorageApi> CandleHandler<S> {
 }
 
 #[async_trait]
-impl<S: StorageApi> WsMessageHandler for CandleHandler<S> {
+impl<E: EngineApi, S: StorageApi> WsMessageHandler for CandleHandler<E, S> {
     type Type = Vec<Candle>;
 
     async fn convert_data(&mut self, _topic: String, data: Value) -> Option<Self::Type> {
@@ -93,7 +99,12 @@ impl<S: StorageApi> WsMessageHandler for CandleHandler<S> {
 
     async fn handle(&mut self, message: Self::Type) {
         for candle in message {
-            self.storage_client.save_candle(candle).await.unwrap();
+            let is_closed = candle.status == CandleStatus::Close;
+            self.storage_client.save_candle(candle.clone()).await.unwrap();
+            if is_closed {
+                let event = plugin_event(PluginEventKind::CandleClosed(candle));
+                let _ = self.engine_client.handle_event(event).await;
+            }
         }
     }
 }
@@ -1,3 +1,7 @@
+use chrono::Utc;
+
+use domain_model::{PluginEvent, PluginEventKind};
+
 pub use candle::CandleHandler;
 pub use order::OrderHandler;
 pub use tick::TickHandler;
@@ -5,3 +9,13 @@ pub use tick::TickHandler;
 mod tick;
 mod order;
 mod candle;
+
+/// Live event for deployments subscribed to the event instrument
+fn plugin_event(kind: PluginEventKind) -> PluginEvent {
+    PluginEvent {
+        simulation_id: None,
+        deployment_id: None,
+        timestamp: Utc::now(),
+        kind,
+    }
+}
@@ -6,23 +6,30 @@ use chrono::Utc;
 use serde_json::{from_value, Value};
 use tracing::info;
 
-use domain_model::{Currency, CurrencyPair, Exchange, MarginMode, Order, OrderMarketType, OrderStatus, OrderType, Side, Size};
+use domain_model::{Currency, CurrencyPair, Exchange, MarginMode, Order, OrderMarketType, OrderStatus, OrderType, PluginEventKind, Side, Size};
 use eac::bybit;
 use eac::bybit::websocket::{OrderDetailsResponse, WsMessageHandler};
+use engine_core_api::api::EngineApi;
 use storage_core_api::StorageApi;
 
-pub struct OrderHandler<S: StorageApi> {
+use super::plugin_event;
+
+pub struct OrderHandler<E: EngineApi, S: StorageApi> {
+    engine_client: Arc<E>,
     storage_client: Arc<S>,
 }
 
-impl<S: StorageApi> OrderHandler<S> {
-    pub fn new(storage_client: Arc<S>) -> Self {
-        Self { storage_client }
+impl<E: EngineApi, S: StorageApi> OrderHandler<E, S> {
+    pub fn new(engine_client: Arc<E>, storage_client: Arc<S>) -> Self {
+        Self {
+            engine_client,
+            storage_client,
+        }
     }
 }
 
 #[async_trait]
-impl<S: StorageApi> WsMessageHandler for OrderHandler<S> {
+impl<E: EngineApi, S: StorageApi> WsMessageHandler for OrderHandler<E, S> {
     type Type = Vec<Order>;
 
     // todo implement order conversion for all variants not only for margin market orders
@@ -122,7 +129,9 @@ impl<S: StorageApi> WsMessageHandler for OrderHandler<S> {
 
     async fn handle(&mut self, message: Self::Type) {
         for order in message {
-            self.storage_client.save_order(order).await.unwrap();
+            self.storage_client.save_order(order.clone()).await.unwrap();
+            let event = plugin_event(PluginEventKind::OrderUpdate(order));
+            let _ = self.engine_client.handle_event(event).await;
         }
     }
 }
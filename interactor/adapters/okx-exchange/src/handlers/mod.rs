@@ -1,3 +1,7 @@
+use chrono::Utc;
+
+use domain_model::{PluginEvent, PluginEventKind};
+
 pub use candle::CandleHandler;
 pub use order::OrderHandler;
 pub use position::PositionHandler;
@@ -7,3 +11,13 @@ mod tick;
 mod order;
 mod position;
 mod candle;
+
+/// Live event for deployments subscribed to the event instrument
+fn plugin_event(kind: PluginEventKind) -> PluginEvent {
+    PluginEvent {
+        simulation_id: None,
+        deployment_id: None,
+        timestamp: Utc::now(),
+        kind,
+    }
+}
@@ -6,30 +6,37 @@ use chrono::Utc;
 use serde_json::{from_value, Value};
 use tracing::info;
 
-use domain_model::{Currency, CurrencyPair, Exchange, LP, MarginMode, Order, OrderMarketType, OrderStatus, OrderType, Side, Size, Trigger};
+use domain_model::{Currency, CurrencyPair, Exchange, LP, MarginMode, Order, OrderMarketType, OrderStatus, OrderType, PluginEventKind, Side, Size, Trigger};
 use eac::okx::enums;
 use eac::okx::enums::{OrdState, OrdType, TdMode};
 use eac::okx::rest::OrderDetailsResponse;
 use eac::okx::websocket::{Action, Channel, WsMessageHandler};
+use engine_core_api::api::EngineApi;
 use storage_core_api::StorageApi;
 
+use super::plugin_event;
+
 pub enum OrderInfo {
     Order(Order),
     LP(LP),
 }
 
-pub struct OrderHandler<S: StorageApi> {
+pub struct OrderHandler<E: EngineApi, S: StorageApi> {
+    engine_client: Arc<E>,
     storage_client: Arc<S>,
 }
 
-impl<S: StorageApi> OrderHandler<S> {
-    pub fn new(storage_client: Arc<S>) -> Self {
-        Self { storage_client }
+impl<E: EngineApi, S: StorageApi> OrderHandler<E, S> {
+    pub fn new(engine_client: Arc<E>, storage_client: Arc<S>) -> Self {
+        Self {
+            engine_client,
+            storage_client,
+        }
     }
 }
 
 #[async_trait]
-impl<S: StorageApi> WsMessageHandler for OrderHandler<S> {
+impl<E: EngineApi, S: StorageApi> WsMessageHandler for OrderHandler<E, S> {
     type Type = Vec<OrderInfo>;
 
     async fn convert_data(
@@ -162,7 +169,11 @@ impl<S: StorageApi> WsMessageHandler for OrderHandler<S> {
     async fn handle(&mut self, message: Self::Type) {
         for order in message {
             match order {
-                OrderInfo::Order(order) => self.storage_client.save_order(order).await.unwrap(),
+                OrderInfo::Order(order) => {
+                    self.storage_client.save_order(order.clone()).await.unwrap();
+                    let event = plugin_event(PluginEventKind::OrderUpdate(order));
+                    let _ = self.engine_client.handle_event(event).await;
+                }
                 OrderInfo::LP(lp) => self.storage_client.save_lp(lp).await.unwrap(),
             }
         }
@@ -6,19 +6,24 @@ use async_trait::async_trait;
 use serde_json::{from_value, Value};
 use tracing::trace;
 
-use domain_model::{Currency, Exchange, Position, Side};
+use domain_model::{Currency, Exchange, PluginEventKind, Position, Side};
 use eac::okx::rest::Account;
 use eac::okx::websocket::{Action, Channel, WsMessageHandler};
+use engine_core_api::api::EngineApi;
 use storage_core_api::StorageApi;
 
-pub struct PositionHandler<S: StorageApi> {
+use super::plugin_event;
+
+pub struct PositionHandler<E: EngineApi, S: StorageApi> {
+    engine_client: Arc<E>,
     positions: HashMap<String, f64>,
     storage_client: Arc<S>,
 }
 
-impl<S: StorageApi> PositionHandler<S> {
-    pub fn new(storage_client: Arc<S>) -> Self {
+impl<E: EngineApi, S: StorageApi> PositionHandler<E, S> {
+    pub fn new(engine_client: Arc<E>, storage_client: Arc<S>) -> Self {
         Self {
+            engine_client,
             positions: HashMap::new(),
             storage_client,
         }
@@ -26,7 +31,7 @@ impl<S: StorageApi> PositionHandler<S> {
 }
 
 #[async_trait]
-impl<S: StorageApi> WsMessageHandler for PositionHandler<S> {
+impl<E: EngineApi, S: StorageApi> WsMessageHandler for PositionHandler<E, S> {
     type Type = Vec<Position>;
 
     async fn convert_data(
@@ -56,7 +61,9 @@ impl<S: StorageApi> WsMessageHandler for PositionHandler<S> {
 
     async fn handle(&mut self, message: Self::Type) {
         for position in message {
-            self.storage_client.save_position(position).await.unwrap();
+            self.storage_client.save_position(position.clone()).await.unwrap();
+            let event = plugin_event(PluginEventKind::PositionUpdate(position));
+            let _ = self.engine_client.handle_event(event).await;
         }
     }
 }
@@ -6,9 +6,10 @@ use axum::extract::{Query, State};
 use axum::routing::{delete, get, post};
 use chrono::{TimeZone, Utc};
 
-use domain_model::{Action, Candle, CurrencyPair, InstrumentId, Order, Subscription, Subscriptions};
+use domain_model::{Action, Candle, CurrencyPair, InstrumentId, KillSwitch, KillSwitchReport, Order, Subscription, Subscriptions};
+use domain_model::audit::ExecutionResult;
 use interactor_core_api::InteractorApi;
-use interactor_rest_api::endpoints::{DELETE_UNSUBSCRIBE, GET_CANDLES, GET_ORDER, GET_PRICE, GET_SUBSCRIPTIONS, GET_TOTAL_BALANCE, POST_EXECUTE_ACTIONS, POST_SUBSCRIBE};
+use interactor_rest_api::endpoints::{DELETE_UNSUBSCRIBE, GET_CANDLES, GET_ORDER, GET_PRICE, GET_SUBSCRIPTIONS, GET_TOTAL_BALANCE, POST_EXECUTE_ACTIONS, POST_STOP_TRADING, POST_SUBSCRIBE};
 use interactor_rest_api::path_queries::{CandlesQuery, OrderQuery, PriceQuery, TotalBalanceQuery};
 
 pub async fn run(port: u16, interactor: impl InteractorApi) {
@@ -22,6 +23,8 @@ pub async fn run(port: u16, interactor: impl InteractorApi) {
         .route(GET_PRICE, get(get_price))
         .route(GET_ORDER, get(get_order))
         .route(GET_TOTAL_BALANCE, get(get_total_balance))
+        .route(POST_STOP_TRADING, post(stop_trading))
+        .route(metrics::GET_METRICS, get(metrics::handler))
         .with_state(interactor);
 
     let address = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), port);
@@ -55,8 +58,9 @@ async fn unsubscribe(
 async fn execute_action(
     State(interactor): State<Arc<dyn InteractorApi>>,
     Json(actions): Json<Vec<Action>>,
-) {
-    interactor.execute_actions(actions).await.unwrap();
+) -> Json<Vec<ExecutionResult>> {
+    let results = interactor.execute_actions(actions).await.unwrap();
+    Json(results)
 }
 
 async fn get_candles(
@@ -133,3 +137,14 @@ async fn get_total_balance(
         .unwrap();
     Json(total_balance)
 }
+
+async fn stop_trading(
+    State(interactor): State<Arc<dyn InteractorApi>>,
+    Json(request): Json<KillSwitch>,
+) -> Json<KillSwitchReport> {
+    let report = interactor
+        .stop_trading(request.close_to)
+        .await
+        .unwrap();
+    Json(report)
+}
@@ -5,9 +5,10 @@ use reqwest::{Client, Url};
 use serde_urlencoded::to_string;
 use tracing::trace;
 
-use domain_model::{Action, Candle, Exchange, InstrumentId, Order, Subscription, Subscriptions, Timeframe};
+use domain_model::{Action, Candle, Currency, Exchange, InstrumentId, KillSwitch, KillSwitchReport, Order, Subscription, Subscriptions, Timeframe};
+use domain_model::audit::ExecutionResult;
 use interactor_core_api::InteractorApi;
-use interactor_rest_api::endpoints::{DELETE_UNSUBSCRIBE, GET_CANDLES, GET_ORDER, GET_PRICE, GET_SUBSCRIPTIONS, GET_TOTAL_BALANCE, POST_EXECUTE_ACTIONS, POST_SUBSCRIBE};
+use interactor_rest_api::endpoints::{DELETE_UNSUBSCRIBE, GET_CANDLES, GET_ORDER, GET_PRICE, GET_SUBSCRIPTIONS, GET_TOTAL_BALANCE, POST_EXECUTE_ACTIONS, POST_STOP_TRADING, POST_SUBSCRIBE};
 use interactor_rest_api::path_queries::{CandlesQuery, OrderQuery, PriceQuery, TotalBalanceQuery};
 
 pub struct InteractorRestClient {
@@ -64,15 +65,17 @@ impl InteractorApi for InteractorRestClient {
         Ok(())
     }
 
-    async fn execute_actions(&self, actions: Vec<Action>) -> Result<()> {
+    async fn execute_actions(&self, actions: Vec<Action>) -> Result<Vec<ExecutionResult>> {
         let endpoint = format!("{}{}", self.url, POST_EXECUTE_ACTIONS);
         let url = Url::parse(&endpoint)?;
         trace!("Request url: {url:?}");
-        self.client.post(url)
+        let result = self.client.post(url)
             .json(&actions)
             .send()
+            .await?
+            .json()
             .await?;
-        Ok(())
+        Ok(result)
     }
 
     async fn get_candles(&self, instrument_id: &InstrumentId, timeframe: Timeframe, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>, limit: Option<u8>) -> Result<Vec<Candle>> {
@@ -154,4 +157,17 @@ impl InteractorApi for InteractorRestClient {
             .await?;
         Ok(result)
     }
+
+    async fn stop_trading(&self, close_to: Option<Currency>) -> Result<KillSwitchReport> {
+        let endpoint = format!("{}{}", self.url, POST_STOP_TRADING);
+        let url = Url::parse(&endpoint)?;
+        trace!("Request url: {url:?}");
+        let result = self.client.post(url)
+            .json(&KillSwitch { close_to })
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(result)
+    }
 }
@@ -1,11 +1,16 @@
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use tracing::{debug, trace, warn};
+use futures::FutureExt;
+use tracing::{debug, error, trace, warn};
+use uuid::Uuid;
 
-use domain_model::{Action, Candle, Exchange, InstrumentId, Order, OrderAction, OrderActionType, Subscription, Subscriptions, Timeframe};
+use domain_model::{Action, CancelOrder, Candle, CreateOrder, Currency, CurrencyPair, Exchange, InstrumentId, KillSwitchReport, MarginMode, Order, OrderAction, OrderActionType, OrderMarketType, OrderType, Position, Side, Size, Subscription, Subscriptions, Timeframe};
+use domain_model::audit::ExecutionResult;
 use interactor_core_api::InteractorApi;
 use interactor_exchange_api::ExchangeApi;
 use interactor_persistence_api::SubscriptionRepository;
@@ -51,38 +56,48 @@ impl<S: StorageApi, R: SubscriptionRepository> InteractorApi for Interactor<S, R
         Ok(())
     }
 
-    async fn execute_actions(&self, actions: Vec<Action>) -> Result<()> {
+    async fn execute_actions(&self, actions: Vec<Action>) -> Result<Vec<ExecutionResult>> {
+        let mut results = Vec::with_capacity(actions.len());
         for action in actions {
             let simulation_id = match &action {
                 Action::OrderAction(order_action) => order_action.simulation_id,
             };
-            if simulation_id.is_none() {
-                debug!("Retrieved new action event");
-                trace!("Action event: {action:?}");
-                match action {
-                    Action::OrderAction(OrderAction {
-                                            order: OrderActionType::CreateOrder(create_order),
-                                            exchange,
-                                            ..
-                                        }) => {
-                        self.service_facade
-                            .place_order(exchange, create_order)
-                            .await
-                    }
-                    Action::OrderAction(OrderAction {
-                                            order: OrderActionType::CancelOrder(cancel_order),
-                                            exchange,
-                                            ..
-                                        }) => {
-                        self.service_facade
-                            .cancel_order(exchange, cancel_order)
-                            .await
-                    }
-                    action => warn!("Temporary unsupported action: {action:?}"),
-                }
+            if simulation_id.is_some() {
+                results.push(ExecutionResult::Skipped);
+                continue;
             }
+            debug!("Retrieved new action event");
+            trace!("Action event: {action:?}");
+            // exchange adapters panic on unexpected responses
+            let result = match action {
+                Action::OrderAction(OrderAction {
+                                        order: OrderActionType::CreateOrder(create_order),
+                                        exchange,
+                                        ..
+                                    }) => {
+                    AssertUnwindSafe(self.service_facade.place_order(exchange, create_order))
+                        .catch_unwind()
+                        .await
+                        .map(ExecutionResult::Placed)
+                }
+                Action::OrderAction(OrderAction {
+                                        order: OrderActionType::CancelOrder(cancel_order),
+                                        exchange,
+                                        ..
+                                    }) => {
+                    AssertUnwindSafe(self.service_facade.cancel_order(exchange, cancel_order))
+                        .catch_unwind()
+                        .await
+                        .map(|_| ExecutionResult::Cancelled)
+                }
+                action => {
+                    warn!("Temporary unsupported action: {action:?}");
+                    Ok(ExecutionResult::Failed(String::from("Unsupported action")))
+                }
+            };
+            results.push(result.unwrap_or_else(|panic| ExecutionResult::Failed(panic_message(panic))));
         }
-        Ok(())
+        Ok(results)
     }
 
     async fn get_candles(
@@ -118,4 +133,84 @@ impl<S: StorageApi, R: SubscriptionRepository> InteractorApi for Interactor<S, R
         let total_balance = self.service_facade.total_balance(exchange).await;
         Ok(total_balance)
     }
+
+    async fn stop_trading(&self, close_to: Option<Currency>) -> Result<KillSwitchReport> {
+        warn!("Stop trading on all exchanges, close positions to: '{close_to:?}'");
+        let mut report = KillSwitchReport {
+            notes: vec![String::from("Only open orders saved in storage are cancelled, orders placed outside of the platform are kept")],
+            ..Default::default()
+        };
+        for exchange in self.service_facade.exchange_ids() {
+            match self.service_facade.open_orders(exchange).await {
+                Ok(orders) => for order in orders {
+                    let cancel_order = CancelOrder {
+                        id: order.id.clone(),
+                        pair: order.pair,
+                    };
+                    // exchange adapters panic on unexpected responses
+                    let cancelled = AssertUnwindSafe(self.service_facade.cancel_order(exchange, cancel_order))
+                        .catch_unwind()
+                        .await;
+                    match cancelled {
+                        Ok(()) => report.cancelled.push(order.id),
+                        Err(_) => report.failed.push(format!("Order: '{}' on exchange: '{exchange}' is not cancelled", order.id)),
+                    }
+                },
+                Err(err) => report.failed.push(format!("Open orders on exchange: '{exchange}' are not found: {err}")),
+            }
+
+            let Some(quote) = close_to else {
+                continue;
+            };
+            match self.service_facade.positions(exchange).await {
+                Ok(positions) => for position in positions {
+                    if position.currency == quote || position.size <= 0. {
+                        continue;
+                    }
+                    let create_order = close_order(&position, quote);
+                    let closed = AssertUnwindSafe(self.service_facade.place_order(exchange, create_order))
+                        .catch_unwind()
+                        .await;
+                    match closed {
+                        Ok(_) => report.closed.push(position),
+                        Err(_) => report.failed.push(format!("Position: '{}' on exchange: '{exchange}' is not closed", position.currency)),
+                    }
+                },
+                Err(err) => report.failed.push(format!("Positions on exchange: '{exchange}' are not found: {err}")),
+            }
+        }
+        if !report.failed.is_empty() {
+            error!("Trading is not stopped completely: {:?}", report.failed);
+        }
+        Ok(report)
+    }
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    panic
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| panic.downcast_ref::<&str>().map(|message| message.to_string()))
+        .unwrap_or_else(|| String::from("Exchange request failed"))
+}
+
+/// Market order which closes the position against the quote currency, short positions are bought back with cross margin
+fn close_order(position: &Position, quote: Currency) -> CreateOrder {
+    let (side, market_type) = match position.side {
+        Side::Buy => (Side::Sell, OrderMarketType::Spot),
+        Side::Sell => (Side::Buy, OrderMarketType::Margin(MarginMode::Cross(quote))),
+    };
+    CreateOrder {
+        id: Uuid::new_v4().simple().to_string(),
+        pair: CurrencyPair {
+            target: position.currency,
+            source: quote,
+        },
+        market_type,
+        order_type: OrderType::Market,
+        side,
+        size: Size::Target(position.size),
+        stop_loss: None,
+        take_profit: None,
+    }
 }
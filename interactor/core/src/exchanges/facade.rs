@@ -1,9 +1,10 @@
 use std::sync::Arc;
 
+use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
 use tracing::{debug, info};
 
-use domain_model::{CancelOrder, Candle, CreateOrder, Exchange, InstrumentId, Order, Timeframe};
+use domain_model::{CancelOrder, Candle, CreateOrder, Exchange, InstrumentId, Order, OrderStatus, Position, Timeframe};
 use interactor_exchange_api::ExchangeApi;
 use storage_core_api::StorageApi;
 
@@ -29,6 +30,35 @@ impl<S: StorageApi> ServiceFacade<S> {
             .as_ref()
     }
 
+    pub fn exchange_ids(&self) -> Vec<Exchange> {
+        self.exchanges
+            .iter()
+            .map(|exchange| exchange.id())
+            .collect()
+    }
+
+    /// Live orders of the exchange which are not finished yet, only orders saved in storage are
+    /// found, exchanges are not asked for orders placed outside of the platform
+    pub async fn open_orders(&self, exchange: Exchange) -> Result<Vec<Order>> {
+        let mut orders = Vec::new();
+        for status in [OrderStatus::Created, OrderStatus::InProgress] {
+            let mut found = self.storage_client
+                .get_orders(None, None, Some(exchange), None, None, None, Some(status), None, None)
+                .await?;
+            orders.append(&mut found);
+        }
+        orders.retain(|order| order.simulation_id.is_none());
+        Ok(orders)
+    }
+
+    pub async fn positions(&self, exchange: Exchange) -> Result<Vec<Position>> {
+        let mut positions = self.storage_client
+            .get_positions(Some(exchange), None, None)
+            .await?;
+        positions.retain(|position| position.simulation_id.is_none());
+        Ok(positions)
+    }
+
     pub async fn subscribe_ticks(&self, instrument_id: &InstrumentId) {
         debug!(
             "Subscribe ticks for instrument: '{}-{}-{}', exchange: '{}'",
@@ -99,12 +129,13 @@ impl<S: StorageApi> ServiceFacade<S> {
         exchange.listen_positions().await;
     }
 
-    pub async fn place_order(&self, exchange: Exchange, create_order: CreateOrder) {
+    pub async fn place_order(&self, exchange: Exchange, create_order: CreateOrder) -> Order {
         info!("Placing new order with id: '{}' for exchange: '{exchange}', market type: '{:?}', pair: '{}-{}', order type: '{:?}', stop-loss: '{:?}', take-profit: '{:?}'",
             create_order.id, create_order.market_type, create_order.pair.target, create_order.pair.source, create_order.order_type, create_order.stop_loss, create_order.take_profit);
         let exchange = self.get_exchange(exchange);
         let order = exchange.place_order(&create_order).await;
-        self.storage_client.save_order(order).await.unwrap();
+        self.storage_client.save_order(order.clone()).await.unwrap();
+        order
     }
 
     pub async fn cancel_order(&self, exchange: Exchange, cancel_order: CancelOrder) {
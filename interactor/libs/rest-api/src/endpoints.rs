@@ -6,3 +6,4 @@ pub const GET_CANDLES: &str = "/api/v1/interactor/candles";
 pub const GET_PRICE: &str = "/api/v1/interactor/price";
 pub const GET_ORDER: &str = "/api/v1/interactor/order";
 pub const GET_TOTAL_BALANCE: &str = "/api/v1/interactor/total-balance";
+pub const POST_STOP_TRADING: &str = "/api/v1/interactor/stop-trading";
@@ -2,17 +2,21 @@ use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
-use domain_model::{Action, Candle, Exchange, InstrumentId, Order, Subscription, Subscriptions, Timeframe};
+use domain_model::{Action, Candle, Currency, Exchange, InstrumentId, KillSwitchReport, Order, Subscription, Subscriptions, Timeframe};
+use domain_model::audit::ExecutionResult;
 
 #[async_trait]
 pub trait InteractorApi: Send + Sync + 'static {
     async fn subscriptions(&self) -> Result<Vec<Subscriptions>>;
     async fn subscribe(&self, subscription: Subscription) -> Result<()>;
     async fn unsubscribe(&self, subscription: Subscription) -> Result<()>;
-    async fn execute_actions(&self, actions: Vec<Action>) -> Result<()>;
+    /// Executes live actions on exchanges, returns a result for each action
+    async fn execute_actions(&self, actions: Vec<Action>) -> Result<Vec<ExecutionResult>>;
     async fn get_candles(&self, instrument_id: &InstrumentId, timeframe: Timeframe, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>, limit: Option<u8>) -> Result<Vec<Candle>>;
     async fn get_price(&self, instrument_id: &InstrumentId, timestamp: Option<DateTime<Utc>>) -> Result<f64>;
     // todo maybe same bidirectional abstraction
     async fn get_order(&self, exchange: Exchange, order_id: &str) -> Result<Option<Order>>;
     async fn get_total_balance(&self, exchange: Exchange) -> Result<f64>;
+    /// Cancels all open live orders on every exchange and market-closes positions to `close_to` currency
+    async fn stop_trading(&self, close_to: Option<Currency>) -> Result<KillSwitchReport>;
 }
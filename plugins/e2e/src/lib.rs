@@ -4,6 +4,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 
 use domain_model::{Action, Currency, CurrencyPair, Exchange, Indicator, InstrumentId, MarketType, PluginId, Tick};
+use domain_model::params::{ParamKind, ParamSchema};
 use plugin_api::{PluginApi, PluginInternalApi};
 
 use crate::plugin::E2EPlugin;
@@ -12,11 +13,7 @@ mod plugin;
 mod margin;
 mod spot;
 
-#[allow(improper_ctypes_definitions)]
-#[no_mangle]
-pub extern "C" fn load() -> Box<dyn PluginApi> {
-    Box::<E2EPlugin>::default()
-}
+plugin_api::export_plugin!(E2EPlugin);
 
 const PLUGIN_NAME: &str = "e2e";
 const PLUGIN_VERSION: i64 = 2;
@@ -29,6 +26,10 @@ impl PluginApi for E2EPlugin {
         PluginId::new(PLUGIN_NAME, PLUGIN_VERSION)
     }
 
+    fn params(&self) -> Vec<ParamSchema> {
+        vec![ParamSchema::new(PARAMETER_NAME, ParamKind::String).with_description("Required by e2e tests")]
+    }
+
     fn configure(&mut self, config: &HashMap<String, String>) {
         config.get(PARAMETER_NAME).unwrap().to_string();
     }
@@ -68,6 +68,7 @@ impl BlobApi for InMemoryBlobStorage {
                     .get_mut(existing_plugin_index)
                 {
                     existing_plugin.binary = plugin.binary;
+                    existing_plugin.params = plugin.params;
                     return Ok(existing_plugin.deref().into());
                 }
             } else {
@@ -45,13 +45,13 @@ async fn get_plugin_binary(State(registry): State<Arc<dyn RegistryApi>>, Query(q
         .ok_or(StatusCode::NOT_FOUND)
 }
 
-async fn create_plugins(State(registry): State<Arc<dyn RegistryApi>>, Query(query): Query<AddPluginQuery>, mut multipart: Multipart) -> Result<Json<Vec<PluginInfo>>, StatusCode> {
+async fn create_plugins(State(registry): State<Arc<dyn RegistryApi>>, Query(query): Query<AddPluginQuery>, mut multipart: Multipart) -> Result<Json<Vec<PluginInfo>>, (StatusCode, String)> {
     let mut plugins_info = Vec::new();
     while let Ok(Some(field)) = multipart.next_field().await {
         let name = field.name().unwrap().to_string();
         if !is_lib_name_valid(&name) {
             error!("Error during plugin creation: 'Uploaded plugin have different target OS'");
-            return Err(StatusCode::BAD_REQUEST);
+            return Err((StatusCode::BAD_REQUEST, String::from("Uploaded plugin have different target OS")));
         }
 
         let data = field.bytes().await.unwrap();
@@ -59,7 +59,7 @@ async fn create_plugins(State(registry): State<Arc<dyn RegistryApi>>, Query(quer
         let plugin = registry.add_plugin(&data, force).await
             .map_err(|err| {
                 error!("Error during plugin creation: {err}");
-                StatusCode::BAD_REQUEST
+                (StatusCode::BAD_REQUEST, err.to_string())
             })?;
         plugins_info.push(plugin);
     }
@@ -67,7 +67,7 @@ async fn create_plugins(State(registry): State<Arc<dyn RegistryApi>>, Query(quer
 }
 
 fn is_lib_name_valid(name: &str) -> bool {
-    cfg!(target_os = "windows") && name.ends_with(".dll") || cfg!(target_os = "linux") && name.ends_with(".so")
+    name.ends_with(".wasm") || name.ends_with(".rhai") || cfg!(target_os = "windows") && name.ends_with(".dll") || cfg!(target_os = "linux") && name.ends_with(".so")
 }
 
 async fn delete_plugin(State(registry): State<Arc<dyn RegistryApi>>, Query(query): Query<PluginQuery>) {
@@ -37,7 +37,7 @@ impl<B: BlobApi, E: EngineApi> RegistryApi for Registry<B, E> {
         if let Some(binary) = binary {
             plugin_loader::load(&binary)
                 .ok()
-                .map(|plugin| PluginBinary::new(plugin.api.id(), &binary))
+                .map(|plugin| PluginBinary::new(plugin.api.id(), plugin.api.params(), &binary))
         } else {
             None
         }
@@ -45,7 +45,7 @@ impl<B: BlobApi, E: EngineApi> RegistryApi for Registry<B, E> {
 
     async fn add_plugin(&self, binary: &[u8], force: bool) -> Result<PluginInfo> {
         let active_plugin = plugin_loader::load(binary)?;
-        let plugin = PluginBinary::new(active_plugin.api.id(), binary);
+        let plugin = PluginBinary::new(active_plugin.api.id(), active_plugin.api.params(), binary);
         let result = self.plugins_storage.add_plugin(plugin, force).await;
         if let Ok(plugin_info) = &result {
             self.engine_client
@@ -7,6 +7,7 @@ use sea_orm::entity::prelude::*;
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub simulation_id: Uuid,
+    pub timestamp: DateTime,
     pub start: DateTime,
     pub end: DateTime,
     pub deployments: Json,
@@ -28,6 +29,7 @@ pub struct Model {
     pub tp_percent: f64,
     pub max_sl_streak: i32,
     pub max_tp_streak: i32,
+    pub deployment_reports: Json,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,12 +1,16 @@
 use sea_orm_migration::{MigrationTrait, MigratorTrait};
 
-use crate::migrations::m20231005_000001_create_tables;
+use crate::migrations::{m20231005_000001_create_tables, m20231020_000001_add_report_timestamp, m20231025_000001_add_deployment_reports};
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20231005_000001_create_tables::Migration)]
+        vec![
+            Box::new(m20231005_000001_create_tables::Migration),
+            Box::new(m20231020_000001_add_report_timestamp::Migration),
+            Box::new(m20231025_000001_add_deployment_reports::Migration),
+        ]
     }
 }
@@ -1,5 +1,7 @@
 pub use migrator::Migrator;
 
 mod m20231005_000001_create_tables;
+mod m20231020_000001_add_report_timestamp;
+mod m20231025_000001_add_deployment_reports;
 
 mod migrator;
@@ -30,6 +30,7 @@ for SimulationReportPostgresRepository<T>
     async fn save(&self, simulation_report: simulator_core_api::SimulationReport) -> Result<()> {
         let simulation_report = simulation_report::ActiveModel {
             simulation_id: ActiveValue::Set(simulation_report.simulation_id),
+            timestamp: ActiveValue::Set(simulation_report.timestamp.naive_utc()),
             start: ActiveValue::Set(simulation_report.start.naive_utc()),
             end: ActiveValue::Set(simulation_report.end.naive_utc()),
             deployments: ActiveValue::Set(json!(simulation_report.deployments)),
@@ -46,6 +47,7 @@ for SimulationReportPostgresRepository<T>
             tp_percent: ActiveValue::Set(simulation_report.tp_percent),
             max_sl_streak: ActiveValue::Set(simulation_report.max_sl_streak as i32),
             max_tp_streak: ActiveValue::Set(simulation_report.max_tp_streak as i32),
+            deployment_reports: ActiveValue::Set(json!(simulation_report.deployment_reports)),
         };
         SimulationReport::insert(simulation_report)
             .on_conflict(
@@ -65,6 +67,7 @@ for SimulationReportPostgresRepository<T>
                         simulation_report::Column::TpPercent,
                         simulation_report::Column::MaxSlStreak,
                         simulation_report::Column::MaxTpStreak,
+                        simulation_report::Column::DeploymentReports,
                     ])
                     .to_owned(),
             )
@@ -86,6 +89,7 @@ for SimulationReportPostgresRepository<T>
             .into_iter()
             .map(|model| simulator_core_api::SimulationReport {
                 simulation_id: model.simulation_id,
+                timestamp: model.timestamp.and_utc(),
                 start: model.start.and_utc(),
                 end: model.end.and_utc(),
                 deployments: serde_json::from_value(model.deployments).unwrap(),
@@ -102,7 +106,15 @@ for SimulationReportPostgresRepository<T>
                 tp_percent: model.tp_percent,
                 max_sl_streak: model.max_sl_streak as u64,
                 max_tp_streak: model.max_sl_streak as u64,
+                deployment_reports: serde_json::from_value(model.deployment_reports).unwrap(),
             })
             .collect()
     }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        simulation_report::Entity::delete_by_id(id)
+            .exec(self.db.deref())
+            .await?;
+        Ok(())
+    }
 }
@@ -3,12 +3,14 @@ use std::sync::Arc;
 
 use axum::{Json, Router};
 use axum::extract::{Path, State};
-use axum::routing::{get, post};
+use axum::http::StatusCode;
+use axum::routing::{delete, get, post};
+use tracing::warn;
 use uuid::Uuid;
 
 use domain_model::CreateSimulation;
-use simulator_core_api::{SimulationReport, SimulatorApi};
-use simulator_rest_api::endpoints::{GET_SIMULATION, GET_SIMULATIONS, POST_RUN_SIMULATION};
+use simulator_core_api::{DebugSnapshot, DebugStep, SimulationReport, SimulatorApi, SimulatorError};
+use simulator_rest_api::endpoints::{DELETE_DEBUG_SESSION, DELETE_SIMULATION, GET_DEBUG_SESSION, GET_SIMULATION, GET_SIMULATIONS, POST_DEBUG_SESSION, POST_DEBUG_STEP, POST_RUN_SIMULATION};
 
 pub async fn run(port: u16, simulator: impl SimulatorApi) {
     let simulator = Arc::new(simulator);
@@ -16,6 +18,11 @@ pub async fn run(port: u16, simulator: impl SimulatorApi) {
         .route(POST_RUN_SIMULATION, post(create_simulation))
         .route(GET_SIMULATIONS, get(get_simulation_reports))
         .route(GET_SIMULATION, get(get_simulation_report))
+        .route(DELETE_SIMULATION, delete(delete_simulation))
+        .route(POST_DEBUG_SESSION, post(create_debug_session))
+        .route(GET_DEBUG_SESSION, get(get_debug_session))
+        .route(POST_DEBUG_STEP, post(create_debug_step))
+        .route(DELETE_DEBUG_SESSION, delete(delete_debug_session))
         .with_state(simulator);
 
     let address = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), port);
@@ -28,9 +35,24 @@ pub async fn run(port: u16, simulator: impl SimulatorApi) {
 async fn create_simulation(
     State(simulator): State<Arc<dyn SimulatorApi>>,
     Json(simulation): Json<CreateSimulation>,
-) -> Json<SimulationReport> {
-    let report = simulator.run_simulation(simulation).await.unwrap();
-    Json(report)
+) -> Result<Json<SimulationReport>, StatusCode> {
+    let report = simulator.run_simulation(simulation)
+        .await
+        .map_err(|err| {
+            warn!("Simulation rejected: {err}");
+            error_status(&err)
+        })?;
+    Ok(Json(report))
+}
+
+fn error_status(err: &anyhow::Error) -> StatusCode {
+    match err.downcast_ref::<SimulatorError>() {
+        Some(SimulatorError::QueueFull(_)) => StatusCode::SERVICE_UNAVAILABLE,
+        Some(SimulatorError::InvalidSimulation(_)) => StatusCode::BAD_REQUEST,
+        Some(SimulatorError::SimulationNotFound | SimulatorError::DebugSessionNotFound) => StatusCode::NOT_FOUND,
+        Some(SimulatorError::DebugSessionBusy) => StatusCode::CONFLICT,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    }
 }
 
 async fn get_simulation_reports(
@@ -50,3 +72,67 @@ async fn get_simulation_report(
         .unwrap();
     Json(reports)
 }
+
+async fn delete_simulation(
+    State(simulator): State<Arc<dyn SimulatorApi>>,
+    Path(simulation_id): Path<Uuid>,
+) -> StatusCode {
+    match simulator.delete_simulation(simulation_id).await {
+        Ok(_) => StatusCode::OK,
+        Err(err) => {
+            warn!("Simulation: '{simulation_id}' not deleted: {err}");
+            error_status(&err)
+        }
+    }
+}
+
+async fn create_debug_session(
+    State(simulator): State<Arc<dyn SimulatorApi>>,
+    Json(simulation): Json<CreateSimulation>,
+) -> Result<Json<DebugSnapshot>, StatusCode> {
+    let snapshot = simulator
+        .start_debug_session(simulation)
+        .await
+        .map_err(|err| {
+            warn!("Debug session rejected: {err}");
+            error_status(&err)
+        })?;
+    Ok(Json(snapshot))
+}
+
+async fn get_debug_session(
+    State(simulator): State<Arc<dyn SimulatorApi>>,
+    Path(simulation_id): Path<Uuid>,
+) -> Result<Json<DebugSnapshot>, StatusCode> {
+    let snapshot = simulator
+        .get_debug_session(simulation_id)
+        .await
+        .map_err(|err| error_status(&err))?;
+    Ok(Json(snapshot))
+}
+
+async fn create_debug_step(
+    State(simulator): State<Arc<dyn SimulatorApi>>,
+    Path(simulation_id): Path<Uuid>,
+    Json(step): Json<DebugStep>,
+) -> Result<Json<DebugSnapshot>, StatusCode> {
+    let snapshot = simulator
+        .step_debug_session(simulation_id, step)
+        .await
+        .map_err(|err| error_status(&err))?;
+    Ok(Json(snapshot))
+}
+
+async fn delete_debug_session(
+    State(simulator): State<Arc<dyn SimulatorApi>>,
+    Path(simulation_id): Path<Uuid>,
+) -> Result<Json<SimulationReport>, StatusCode> {
+    let report = simulator
+        .finish_debug_session(simulation_id)
+        .await
+        .map_err(|err| {
+            warn!("Debug session: '{simulation_id}' not finished: {err}");
+            error_status(&err)
+        })?;
+    Ok(Json(report))
+}
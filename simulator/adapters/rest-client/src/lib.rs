@@ -5,8 +5,8 @@ use tracing::trace;
 use uuid::Uuid;
 
 use domain_model::CreateSimulation;
-use simulator_core_api::{SimulationReport, SimulatorApi};
-use simulator_rest_api::endpoints::{GET_SIMULATION, GET_SIMULATIONS, POST_RUN_SIMULATION};
+use simulator_core_api::{DebugSnapshot, DebugStep, SimulationReport, SimulatorApi};
+use simulator_rest_api::endpoints::{DELETE_DEBUG_SESSION, DELETE_SIMULATION, GET_DEBUG_SESSION, GET_SIMULATION, GET_SIMULATIONS, POST_DEBUG_SESSION, POST_DEBUG_STEP, POST_RUN_SIMULATION};
 
 pub struct SimulatorRestClient {
     url: String,
@@ -58,4 +58,59 @@ impl SimulatorApi for SimulatorRestClient {
         let response = self.client.get(url).send().await?.json().await?;
         Ok(response)
     }
+
+    async fn delete_simulation(&self, id: Uuid) -> Result<()> {
+        let endpoint = format!("{}{}", self.url, DELETE_SIMULATION).replace(":id", &id.to_string());
+        let url = Url::parse(&endpoint)?;
+        trace!("Request url: {url:?}");
+        self.client.delete(url).send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    async fn start_debug_session(&self, simulation: CreateSimulation) -> Result<DebugSnapshot> {
+        let endpoint = format!("{}{}", self.url, POST_DEBUG_SESSION);
+        let url = Url::parse(&endpoint)?;
+        trace!("Request url: {url:?}");
+        let response = self
+            .client
+            .post(url)
+            .json(&simulation)
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(response)
+    }
+
+    async fn step_debug_session(&self, id: Uuid, step: DebugStep) -> Result<DebugSnapshot> {
+        let endpoint = format!("{}{}", self.url, POST_DEBUG_STEP).replace(":id", &id.to_string());
+        let url = Url::parse(&endpoint)?;
+        trace!("Request url: {url:?}");
+        let response = self
+            .client
+            .post(url)
+            .json(&step)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response)
+    }
+
+    async fn get_debug_session(&self, id: Uuid) -> Result<DebugSnapshot> {
+        let endpoint = format!("{}{}", self.url, GET_DEBUG_SESSION).replace(":id", &id.to_string());
+        let url = Url::parse(&endpoint)?;
+        trace!("Request url: {url:?}");
+        let response = self.client.get(url).send().await?.error_for_status()?.json().await?;
+        Ok(response)
+    }
+
+    async fn finish_debug_session(&self, id: Uuid) -> Result<SimulationReport> {
+        let endpoint = format!("{}{}", self.url, DELETE_DEBUG_SESSION).replace(":id", &id.to_string());
+        let url = Url::parse(&endpoint)?;
+        trace!("Request url: {url:?}");
+        let response = self.client.delete(url).send().await?.error_for_status()?.json().await?;
+        Ok(response)
+    }
 }
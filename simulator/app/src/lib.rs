@@ -1,27 +1,77 @@
 use std::sync::Arc;
 
+use chrono::Duration;
 use tracing::info;
 
+use engine_core::{Engine, RiskSettings, RolloutSettings, StateManager};
+use engine_core_api::api::EngineApi;
+use engine_inmemory_persistence::{InMemoryDeploymentRepository, InMemoryStateRepository};
 use engine_rest_client::EngineRestClient;
 use interactor_rest_client::InteractorRestClient;
+use registry_rest_client::RegistryRestClient;
 use simulator_config::CONFIG;
-use simulator_core::Simulator;
+use simulator_core::{RetentionPolicy, Simulator};
 use simulator_postgres_persistence::initiator::init_db;
 use simulator_postgres_persistence::repositories::SimulationReportPostgresRepository;
+use storage_core_api::StorageApi;
+use storage_core_api_cache::StorageCoreApiPreloaded;
 use storage_rest_client::StorageRestClient;
 
 pub async fn run() {
     info!("▶ simulator running...");
+    let engine_client = Arc::new(EngineRestClient::new(&CONFIG.engine.url));
+    let storage_client = Arc::new(StorageRestClient::new(&CONFIG.storage.url));
+    run_with_engine(engine_client, storage_client).await;
+}
+
+/// Runs simulator with engine inside the same process, ticks are passed to plugins without
+/// network round trips and candles are served from a preloaded window.
+pub async fn run_embedded() {
+    info!("▶ simulator running with embedded engine...");
+    let interactor_client = Arc::new(InteractorRestClient::new(&CONFIG.interactor.url));
+    let registry_client = Arc::new(RegistryRestClient::new(&CONFIG.registry.url));
+    let storage_client = Arc::new(StorageRestClient::new(&CONFIG.storage.url));
+    let storage_client_preloaded = Arc::new(StorageCoreApiPreloaded::new(
+        storage_client,
+        Duration::days(CONFIG.engine.preload),
+        // a window per running simulation
+        CONFIG.simulation.workers,
+    ));
+    let engine = Arc::new(Engine::new(
+        interactor_client,
+        registry_client,
+        Arc::clone(&storage_client_preloaded),
+        Arc::new(InMemoryDeploymentRepository::default()),
+        std::time::Duration::from_millis(CONFIG.engine.timeout),
+        CONFIG.engine.queue,
+        // simulated actions are never sent to exchanges
+        RiskSettings::default(),
+        // simulation states are not restored after restarts
+        StateManager::new(Arc::new(InMemoryStateRepository::default()), 1),
+        RolloutSettings::default(),
+    ));
+    // the simulator syncs candles through the preloaded client, so synced periods are loaded again
+    run_with_engine(engine, storage_client_preloaded).await;
+}
+
+async fn run_with_engine<E: EngineApi, S: StorageApi>(engine_client: Arc<E>, storage_client: Arc<S>) {
     let db = init_db(&CONFIG.database.url, &CONFIG.application.name).await;
     let simulation_report_repository = Arc::new(SimulationReportPostgresRepository::new(db));
     let interactor_client = Arc::new(InteractorRestClient::new(&CONFIG.interactor.url));
-    let engine_client = Arc::new(EngineRestClient::new(&CONFIG.engine.url));
-    let storage_client = Arc::new(StorageRestClient::new(&CONFIG.storage.url));
-    let engine = Simulator::new(
+    let simulator = Simulator::new(
         engine_client,
         storage_client,
         interactor_client,
         simulation_report_repository,
+        CONFIG.simulation.workers,
+        CONFIG.simulation.queue,
+        CONFIG.simulation.batch,
     );
-    simulator_rest_api_server::run(CONFIG.application.port, engine).await;
+    simulator.start_retention(RetentionPolicy {
+        keep_last: CONFIG.retention.keep,
+        max_age: CONFIG.retention.days.map(Duration::days),
+        interval: std::time::Duration::from_secs(CONFIG.retention.interval * 60),
+    });
+    simulator.start_debug_expiry(std::time::Duration::from_secs(CONFIG.simulation.debug_idle * 60));
+    simulator_rest_api_server::run(CONFIG.application.port, simulator).await;
 }
@@ -10,8 +10,11 @@ pub struct Config {
     pub application: Application,
     pub database: Database,
     pub engine: Engine,
+    pub registry: Registry,
     pub storage: Storage,
     pub interactor: Interactor,
+    pub simulation: Simulation,
+    pub retention: Retention,
 }
 
 #[derive(Deserialize)]
@@ -29,6 +32,14 @@ pub struct Logging {
 #[derive(Deserialize)]
 pub struct Engine {
     pub url: String,
+    pub preload: i64,
+    pub timeout: u64,
+    pub queue: usize,
+}
+
+#[derive(Deserialize)]
+pub struct Registry {
+    pub url: String,
 }
 
 #[derive(Deserialize)]
@@ -41,6 +52,21 @@ pub struct Interactor {
     pub url: String,
 }
 
+#[derive(Deserialize)]
+pub struct Simulation {
+    pub workers: usize,
+    pub queue: usize,
+    pub batch: usize,
+    pub debug_idle: u64,
+}
+
+#[derive(Deserialize)]
+pub struct Retention {
+    pub keep: Option<usize>,
+    pub days: Option<i64>,
+    pub interval: u64,
+}
+
 #[derive(Deserialize)]
 pub struct Database {
     pub url: String,
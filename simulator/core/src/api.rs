@@ -1,25 +1,39 @@
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::Instant;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use axum::async_trait;
 use chrono::{DateTime, Duration, Utc};
-use tracing::debug;
+use tokio::sync::Mutex;
+use tracing::{debug, error};
 use uuid::Uuid;
 
-use domain_model::{Action, Candle, CreateSimulation, Currency, CurrencyPair, Exchange, InstrumentId, MarketType, NewDeployment, Order, OrderActionType, OrderMarketType, OrderStatus, OrderType, Position, Side, Simulation, SimulationDeployment, SimulationPosition, Size, Tick};
+use domain_model::{Action, Allocation, Candle, CreateSimulation, Currency, CurrencyPair, Exchange, InstrumentId, MarketType, NewDeployment, Order, OrderActionType, OrderMarketType, OrderStatus, OrderType, PluginEvent, PluginEventKind, Position, Side, Simulation, SimulationDeployment, SimulationPosition, Size, Tick};
 use engine_core_api::api::EngineApi;
 use interactor_core_api::InteractorApi;
-use simulator_core_api::{SimulationReport, SimulatorApi};
+use simulator_core_api::{DebugSnapshot, DebugStep, DeploymentReport, SimulationReport, SimulatorApi, SimulatorError, StopReason};
 use simulator_persistence_api::SimulationReportRepository;
 use storage_core_api::StorageApi;
 
+use crate::debugger;
+use crate::debugger::{DebugSession, DebugSessions};
 use crate::file_logger::Logger;
+use crate::portfolio;
+use crate::portfolio::Portfolio;
+use crate::retention;
+use crate::retention::RetentionPolicy;
+use crate::timers::Timers;
+use crate::worker_pool::WorkerPool;
 
 pub struct Simulator<E: EngineApi, S: StorageApi, I: InteractorApi, SR: SimulationReportRepository> {
     engine_client: Arc<E>,
     storage_client: Arc<S>,
     interactor_client: Arc<I>,
     simulation_report_repository: Arc<SR>,
+    worker_pool: WorkerPool,
+    ticks_batch_size: usize,
+    debug_sessions: DebugSessions,
 }
 
 #[async_trait]
@@ -27,6 +41,8 @@ impl<E: EngineApi, S: StorageApi, I: InteractorApi, SR: SimulationReportReposito
 for Simulator<E, S, I, SR>
 {
     async fn run_simulation(&self, simulation: CreateSimulation) -> Result<SimulationReport> {
+        validate(&simulation)?;
+        let _permit = self.worker_pool.acquire().await?;
         let simulation: Simulation = simulation.into();
         let mut logger = Logger::new(simulation.id);
         let report = self
@@ -42,13 +58,78 @@ for Simulator<E, S, I, SR>
             .await
             .first()
             .cloned()
-            .ok_or(anyhow::Error::msg("Simulation report not found"))
+            .ok_or(SimulatorError::SimulationNotFound.into())
     }
 
     async fn get_simulation_reports(&self) -> Result<Vec<SimulationReport>> {
         let reports = self.simulation_report_repository.get(None).await;
         Ok(reports)
     }
+
+    async fn delete_simulation(&self, id: Uuid) -> Result<()> {
+        let report = self.get_simulation_report(id).await?;
+        retention::delete_simulation(
+            self.storage_client.as_ref(),
+            self.simulation_report_repository.as_ref(),
+            &report,
+        ).await
+    }
+
+    async fn start_debug_session(&self, simulation: CreateSimulation) -> Result<DebugSnapshot> {
+        validate(&simulation)?;
+        let permit = self.worker_pool.acquire().await?;
+        let mut simulation: Simulation = simulation.into();
+        let mut logger = Logger::new(simulation.id);
+        let portfolio = self.prepare_simulation(&mut simulation, &mut logger).await;
+        let session = DebugSession::new(simulation, portfolio, logger, permit);
+        let snapshot = self.debug_snapshot(&session).await;
+        self.debug_sessions
+            .write()
+            .await
+            .insert(snapshot.simulation_id, Arc::new(Mutex::new(session)));
+        Ok(snapshot)
+    }
+
+    async fn step_debug_session(&self, id: Uuid, step: DebugStep) -> Result<DebugSnapshot> {
+        let session = self.debug_session(id).await?;
+        let mut session = session.lock().await;
+        self.run_debug_step(&mut session, &step).await;
+        session.used = Instant::now();
+        Ok(self.debug_snapshot(&session).await)
+    }
+
+    async fn get_debug_session(&self, id: Uuid) -> Result<DebugSnapshot> {
+        let session = self.debug_session(id).await?;
+        let mut session = session.lock().await;
+        session.used = Instant::now();
+        Ok(self.debug_snapshot(&session).await)
+    }
+
+    async fn finish_debug_session(&self, id: Uuid) -> Result<SimulationReport> {
+        let session = {
+            let mut sessions = self.debug_sessions.write().await;
+            let session = sessions
+                .remove(&id)
+                .ok_or(SimulatorError::DebugSessionNotFound)?;
+            match Arc::try_unwrap(session) {
+                Ok(session) => session.into_inner(),
+                Err(session) => {
+                    sessions.insert(id, session);
+                    bail!(SimulatorError::DebugSessionBusy);
+                }
+            }
+        };
+        let DebugSession { mut simulation, portfolio, stats, mut logger, last_tick, .. } = session;
+        // an interrupted session is reported up to the last processed tick
+        if let Some(tick) = last_tick {
+            simulation.end = simulation.end.min(tick.timestamp);
+        }
+        let report = self
+            .finish_simulation(simulation, portfolio, stats, &mut logger)
+            .await;
+        logger.save();
+        Ok(report)
+    }
 }
 
 impl<E: EngineApi, S: StorageApi, I: InteractorApi, SR: SimulationReportRepository>
@@ -59,24 +140,41 @@ Simulator<E, S, I, SR>
         storage_client: Arc<S>,
         interactor_client: Arc<I>,
         simulation_report_repository: Arc<SR>,
+        workers: usize,
+        queue_size: usize,
+        ticks_batch_size: usize,
     ) -> Self {
         Self {
             engine_client,
             storage_client,
             interactor_client,
             simulation_report_repository,
+            worker_pool: WorkerPool::new(workers, queue_size),
+            ticks_batch_size: ticks_batch_size.max(1),
+            debug_sessions: Default::default(),
         }
     }
 
+    pub fn start_retention(&self, policy: RetentionPolicy) {
+        let storage_client = Arc::clone(&self.storage_client);
+        let simulation_report_repository = Arc::clone(&self.simulation_report_repository);
+        tokio::spawn(retention::run(storage_client, simulation_report_repository, policy));
+    }
+
+    pub fn start_debug_expiry(&self, idle: std::time::Duration) {
+        let engine_client = Arc::clone(&self.engine_client);
+        let debug_sessions = Arc::clone(&self.debug_sessions);
+        tokio::spawn(debugger::expire(engine_client, debug_sessions, idle));
+    }
+
     async fn run_simulation_with_logger(
         &self,
         mut simulation: Simulation,
         logger: &mut Logger,
     ) -> SimulationReport {
-        logger.log(format!("Start simulation: '{:?}'", simulation));
-        self.create_positions(&simulation).await;
-        self.create_deployments(&mut simulation).await;
+        let mut portfolio = self.prepare_simulation(&mut simulation, logger).await;
         let mut simulation_stats = SimulationStats::default();
+        let mut timers = Timers::new(&simulation.deployments, simulation.start);
 
         let mut batch_start = simulation.start;
         let mut batch_end = simulation.start;
@@ -89,14 +187,44 @@ Simulator<E, S, I, SR>
                 simulation.end
             };
 
-            self.run_simulation_batch(logger, &mut simulation, &mut simulation_stats, batch_start, batch_end)
+            self.rebalance(&simulation, &mut portfolio, logger, batch_start).await;
+            self.run_simulation_batch(logger, &mut simulation, &mut portfolio, &mut simulation_stats, &mut timers, batch_start, batch_end)
                 .await;
 
             batch_start += Duration::days(7);
         }
+        self.finish_simulation(simulation, portfolio, simulation_stats, logger).await
+    }
+
+    async fn prepare_simulation(&self, simulation: &mut Simulation, logger: &mut Logger) -> Portfolio {
+        logger.log(format!("Start simulation: '{:?}'", simulation));
+        self.create_positions(simulation).await;
+        self.create_deployments(simulation).await;
+        let shares = self.allocation_shares(simulation, logger).await;
+        let mut portfolio = Portfolio::new(&simulation.deployments, &simulation.positions, &shares);
+        for deployment_id in simulation.deployments.iter().filter_map(|deployment| deployment.deployment_id).collect::<Vec<_>>() {
+            let start = simulation.start;
+            self.dispatch_event(PluginEventKind::Start, Some(deployment_id), start, simulation, &mut portfolio, logger)
+                .await;
+        }
+        portfolio
+    }
+
+    async fn finish_simulation(
+        &self,
+        mut simulation: Simulation,
+        mut portfolio: Portfolio,
+        simulation_stats: SimulationStats,
+        logger: &mut Logger,
+    ) -> SimulationReport {
+        for deployment_id in simulation.deployments.iter().filter_map(|deployment| deployment.deployment_id).collect::<Vec<_>>() {
+            let end = simulation.end;
+            self.dispatch_event(PluginEventKind::Stop, Some(deployment_id), end, &mut simulation, &mut portfolio, logger)
+                .await;
+        }
         self.delete_deployments(&simulation.deployments).await;
 
-        let report = self.build_report(simulation, simulation_stats).await;
+        let report = self.build_report(simulation, portfolio, simulation_stats).await;
         self.simulation_report_repository
             .save(report.clone())
             .await
@@ -105,54 +233,288 @@ Simulator<E, S, I, SR>
         report
     }
 
+    async fn rebalance(
+        &self,
+        simulation: &Simulation,
+        portfolio: &mut Portfolio,
+        logger: &mut Logger,
+        batch_start: DateTime<Utc>,
+    ) {
+        if batch_start != simulation.start {
+            let weights = self.risk_parity_weights(&simulation.deployments, batch_start - Duration::days(7), batch_start).await;
+            logger.log(format!("|> Rebalance risk parity deployments with weights: {weights:?}"));
+            portfolio.rebalance(&weights);
+        }
+    }
+
     async fn run_simulation_batch(
         &self,
         logger: &mut Logger,
         simulation: &mut Simulation,
+        portfolio: &mut Portfolio,
         simulation_stats: &mut SimulationStats,
+        timers: &mut Timers,
         batch_start: DateTime<Utc>,
         batch_end: DateTime<Utc>,
     ) {
         debug!("Batch processing from start: {batch_start} to end: {batch_end}");
-        let ticks = self
+        let (ticks, candles) = self
             .get_ticks(logger, simulation, batch_start, batch_end)
             .await;
-        let positions = &mut simulation.positions;
-        let active_orders = &mut simulation.active_orders;
+        let mut candles = VecDeque::from(candles);
         debug!("Ticks len: {}", ticks.len());
         simulation.ticks_len += ticks.len() as u32;
-        for tick in &ticks {
+        for ticks in ticks.chunks(self.ticks_batch_size) {
+            let actions_batch = if ticks.len() > 1 {
+                self.engine_client.get_actions_batch(ticks).await
+            } else {
+                Vec::new()
+            };
+            for (index, tick) in ticks.iter().enumerate() {
+                let actions = actions_batch.get(index).cloned();
+                self.close_candles(&mut candles, Some(tick.timestamp), simulation, portfolio, logger)
+                    .await;
+                self.fire_timers(timers, tick.timestamp, simulation, portfolio, logger)
+                    .await;
+                self.process_tick(tick, actions, simulation, portfolio, simulation_stats, logger)
+                    .await;
+            }
+        }
+        self.close_candles(&mut candles, None, simulation, portfolio, logger)
+            .await;
+        self.fire_timers(timers, batch_end, simulation, portfolio, logger)
+            .await;
+    }
+
+    /// Processes a single tick, actions are requested from the engine if not prefetched
+    async fn process_tick(
+        &self,
+        tick: &Tick,
+        actions: Option<Vec<Action>>,
+        simulation: &mut Simulation,
+        portfolio: &mut Portfolio,
+        simulation_stats: &mut SimulationStats,
+        logger: &mut Logger,
+    ) -> Vec<Action> {
+        let orders_state = debugger::orders_state(&simulation.active_orders);
+        let balances = portfolio.balances();
+        let positions = &mut simulation.positions;
+        let active_orders = &mut simulation.active_orders;
+        logger.log(format!(
+            "| Tick: {} '{}' {}-{}='{}'",
+            tick.instrument_id.exchange,
+            tick.timestamp,
+            tick.instrument_id.pair.target,
+            tick.instrument_id.pair.source,
+            tick.price
+        ));
+        let mut updated_orders = self
+            .check_active_orders(active_orders, tick, positions, portfolio, simulation_stats, logger)
+            .await;
+        let mut actions = match actions {
+            Some(actions) => actions,
+            None => self.engine_client.get_actions(tick).await,
+        };
+        for action in &actions {
             logger.log(format!(
-                "| Tick: {} '{}' {}-{}='{}'",
+                "|* Action: {:?} \n   for tick: {} '{}' {}-{}='{}'",
+                action,
                 tick.instrument_id.exchange,
                 tick.timestamp,
                 tick.instrument_id.pair.target,
                 tick.instrument_id.pair.source,
                 tick.price
             ));
-            self.check_active_orders(active_orders, tick, positions, simulation_stats, logger)
+            simulation.actions_count += 1;
+            self.execute_action(tick.timestamp, action, active_orders, portfolio, logger)
                 .await;
-            let actions = self.engine_client.get_actions(tick).await;
-            for action in &actions {
-                logger.log(format!(
-                    "|* Action: {:?} \n   for tick: {} '{}' {}-{}='{}'",
-                    action,
-                    tick.instrument_id.exchange,
-                    tick.timestamp,
-                    tick.instrument_id.pair.target,
-                    tick.instrument_id.pair.source,
-                    tick.price
-                ));
-                simulation.actions_count += 1;
-                self.execute_action(tick.timestamp, action, active_orders, logger)
-                    .await;
+        }
+        let mut finished_orders = self
+            .check_active_orders(active_orders, tick, positions, portfolio, simulation_stats, logger)
+            .await;
+        updated_orders.append(&mut finished_orders);
+        updated_orders.extend(active_orders
+            .iter()
+            .filter(|order| !orders_state.contains(&(order.id.clone(), order.status.clone())))
+            .cloned());
+
+        for order in updated_orders {
+            let owner = portfolio.order_owner(&order.id);
+            let mut event_actions = self
+                .dispatch_event(PluginEventKind::OrderUpdate(order), owner, tick.timestamp, simulation, portfolio, logger)
+                .await;
+            actions.append(&mut event_actions);
+        }
+        for (deployment_id, position) in portfolio.changed_positions(&balances) {
+            let event = PluginEventKind::PositionUpdate(position.into());
+            let mut event_actions = self
+                .dispatch_event(event, Some(deployment_id), tick.timestamp, simulation, portfolio, logger)
+                .await;
+            actions.append(&mut event_actions);
+        }
+        actions
+    }
+
+    /// Passes the event to the engine and executes the actions of event callbacks
+    async fn dispatch_event(
+        &self,
+        kind: PluginEventKind,
+        deployment_id: Option<Uuid>,
+        timestamp: DateTime<Utc>,
+        simulation: &mut Simulation,
+        portfolio: &mut Portfolio,
+        logger: &mut Logger,
+    ) -> Vec<Action> {
+        let event = PluginEvent {
+            simulation_id: Some(simulation.id),
+            deployment_id,
+            timestamp,
+            kind,
+        };
+        let actions = self.engine_client.handle_event(event.clone()).await;
+        for action in &actions {
+            logger.log(format!("|* Action: {:?} \n   for event: {:?} '{}'", action, event.kind, timestamp));
+            simulation.actions_count += 1;
+            self.execute_action(timestamp, action, &mut simulation.active_orders, portfolio, logger)
+                .await;
+        }
+        actions
+    }
+
+    /// Fires plugin timers scheduled not later than `until` in the order of their fire time
+    async fn fire_timers(
+        &self,
+        timers: &mut Timers,
+        until: DateTime<Utc>,
+        simulation: &mut Simulation,
+        portfolio: &mut Portfolio,
+        logger: &mut Logger,
+    ) -> Vec<Action> {
+        let mut actions = Vec::new();
+        while let Some((deployment_id, name, time)) = timers.next_fired(until) {
+            let mut event_actions = self
+                .dispatch_event(PluginEventKind::Timer(name), Some(deployment_id), time, simulation, portfolio, logger)
+                .await;
+            actions.append(&mut event_actions);
+        }
+        actions
+    }
+
+    /// Closes candles opened before `until`, or all remaining candles of the batch
+    async fn close_candles(
+        &self,
+        candles: &mut VecDeque<Candle>,
+        until: Option<DateTime<Utc>>,
+        simulation: &mut Simulation,
+        portfolio: &mut Portfolio,
+        logger: &mut Logger,
+    ) -> Vec<Action> {
+        let mut actions = Vec::new();
+        while candles.front().is_some_and(|candle| until.map_or(true, |until| candle.timestamp < until)) {
+            let candle = candles.pop_front().unwrap();
+            let close_time = candle.timestamp + Duration::seconds(candle.timeframe.as_sec());
+            let mut event_actions = self
+                .dispatch_event(PluginEventKind::CandleClosed(candle), None, close_time, simulation, portfolio, logger)
+                .await;
+            actions.append(&mut event_actions);
+        }
+        actions
+    }
+
+    async fn debug_session(&self, id: Uuid) -> Result<Arc<Mutex<DebugSession>>> {
+        self.debug_sessions
+            .read()
+            .await
+            .get(&id)
+            .cloned()
+            .ok_or(SimulatorError::DebugSessionNotFound.into())
+    }
+
+    /// Moves the session cursor tick by tick until a stop condition of the step is met
+    async fn run_debug_step(&self, session: &mut DebugSession, step: &DebugStep) {
+        let mut stepped = 0;
+        loop {
+            let Some(tick) = self.next_debug_tick(session).await else {
+                session.reason = StopReason::End;
+                return;
+            };
+            let orders_state = debugger::orders_state(&session.simulation.active_orders);
+            let mut actions = self
+                .close_candles(&mut session.candles, Some(tick.timestamp), &mut session.simulation, &mut session.portfolio, &mut session.logger)
+                .await;
+            let mut timer_actions = self
+                .fire_timers(&mut session.timers, tick.timestamp, &mut session.simulation, &mut session.portfolio, &mut session.logger)
+                .await;
+            actions.append(&mut timer_actions);
+            let mut tick_actions = self
+                .process_tick(&tick, None, &mut session.simulation, &mut session.portfolio, &mut session.stats, &mut session.logger)
+                .await;
+            actions.append(&mut tick_actions);
+            let order_event = !actions.is_empty()
+                || orders_state != debugger::orders_state(&session.simulation.active_orders);
+            stepped += 1;
+            session.processed += 1;
+            let stop_reason = debugger::stop_reason(step, &tick, stepped, order_event);
+            session.last_tick = Some(tick);
+            session.last_actions = actions;
+            if let Some(stop_reason) = stop_reason {
+                session.reason = stop_reason;
+                return;
             }
-            self.check_active_orders(active_orders, tick, positions, simulation_stats, logger)
+        }
+    }
+
+    /// Takes the next tick of the session, loading the next batch when the current one is processed
+    async fn next_debug_tick(&self, session: &mut DebugSession) -> Option<Tick> {
+        while session.ticks.is_empty() {
+            self.close_candles(&mut session.candles, None, &mut session.simulation, &mut session.portfolio, &mut session.logger)
+                .await;
+            let batch_end = session.batch_end;
+            self.fire_timers(&mut session.timers, batch_end, &mut session.simulation, &mut session.portfolio, &mut session.logger)
+                .await;
+            if session.is_finished() {
+                return None;
+            }
+            let batch_start = session.batch_end;
+            let new_batch_end = batch_start + Duration::days(7);
+            session.batch_start = batch_start;
+            session.batch_end = new_batch_end.min(session.simulation.end);
+
+            self.rebalance(&session.simulation, &mut session.portfolio, &mut session.logger, batch_start)
+                .await;
+            let (ticks, candles) = self
+                .get_ticks(&mut session.logger, &session.simulation, session.batch_start, session.batch_end)
                 .await;
+            session.simulation.ticks_len += ticks.len() as u32;
+            session.ticks = ticks.into();
+            session.candles = candles.into();
         }
+        session.ticks.pop_front()
     }
 
-    async fn build_report(&self, simulation: Simulation, simulation_stats: SimulationStats) -> SimulationReport {
+    async fn debug_snapshot(&self, session: &DebugSession) -> DebugSnapshot {
+        let mut states = HashMap::new();
+        for deployment in &session.simulation.deployments {
+            let Some(deployment_id) = deployment.deployment_id else {
+                continue;
+            };
+            if let Some(state) = self.engine_client.get_deployment_state(deployment_id).await {
+                states.insert(deployment_id, state);
+            }
+        }
+        DebugSnapshot {
+            simulation_id: session.simulation.id,
+            reason: session.reason.clone(),
+            tick: session.last_tick.clone(),
+            ticks: session.processed,
+            actions: session.last_actions.clone(),
+            states,
+            active_orders: session.simulation.active_orders.clone(),
+            assets: session.simulation.positions.clone(),
+        }
+    }
+
+    async fn build_report(&self, simulation: Simulation, portfolio: Portfolio, simulation_stats: SimulationStats) -> SimulationReport {
         let mut positions = simulation.positions;
         positions
             .iter_mut()
@@ -161,9 +523,33 @@ Simulator<E, S, I, SR>
         let profit = self.calculate_profit(&positions, simulation.end).await;
         let profit_clear = self.calculate_profit(&positions, simulation.start).await;
         let fees = self.calculate_fees(&positions, simulation.end).await;
+        let capital = self.calculate_capital(&positions, simulation.end).await;
+
+        let mut deployment_reports = Vec::new();
+        for ledger in portfolio.ledgers {
+            let mut assets = ledger.positions;
+            assets
+                .iter_mut()
+                .for_each(|position| position.diff = position.end - position.start);
+            let ledger_capital = self.calculate_capital(&assets, simulation.end).await;
+            deployment_reports.push(DeploymentReport {
+                deployment_id: ledger.deployment_id,
+                plugin_id: ledger.plugin_id,
+                allocation: ledger.allocation,
+                share: if capital > 0. { ledger_capital / capital } else { 0. },
+                actions: ledger.actions,
+                profit: self.calculate_profit(&assets, simulation.end).await,
+                profit_clear: self.calculate_profit(&assets, simulation.start).await,
+                fees: self.calculate_fees(&assets, simulation.end).await,
+                assets,
+                sl_count: ledger.stats.sl_count,
+                tp_count: ledger.stats.tp_count,
+            });
+        }
 
         SimulationReport {
             simulation_id: simulation.id,
+            timestamp: simulation.timestamp,
             start: simulation.start,
             end: simulation.end,
             deployments: simulation.deployments,
@@ -180,7 +566,109 @@ Simulator<E, S, I, SR>
             tp_percent: simulation_stats.tp_percent(),
             max_sl_streak: simulation_stats.max_sl_streak,
             max_tp_streak: simulation_stats.max_tp_streak,
+            deployment_reports,
+        }
+    }
+
+    /// Shares of portfolio capital for each deployment in the same order as deployments
+    async fn allocation_shares(&self, simulation: &Simulation, logger: &mut Logger) -> Vec<f64> {
+        let capital = self.calculate_capital(&simulation.positions, simulation.start).await;
+        let mut shares: Vec<_> = simulation.deployments
+            .iter()
+            .map(|deployment| match deployment.allocation {
+                Allocation::Fixed(amount) if capital > 0. => amount / capital,
+                Allocation::Fixed(_) => 0.,
+                Allocation::Percent(percent) => percent / 100.,
+                Allocation::RiskParity => 0.,
+            })
+            .collect();
+        let allocated: f64 = shares.iter().sum();
+        if allocated > 1. {
+            logger.log(format!("|> Allocated capital '{allocated}' exceeds portfolio, shares are scaled down"));
+            shares.iter_mut().for_each(|share| *share /= allocated);
+        }
+
+        let weights = self.risk_parity_weights(&simulation.deployments, simulation.start - Duration::days(7), simulation.start).await;
+        let weights_sum: f64 = weights.values().sum();
+        let unallocated = (1. - allocated).max(0.);
+        for (deployment, share) in simulation.deployments.iter().zip(shares.iter_mut()) {
+            let weight = deployment.deployment_id.and_then(|id| weights.get(&id));
+            if let Some(weight) = weight {
+                *share = unallocated * weight / weights_sum;
+            }
+        }
+        logger.log(format!("|> Allocation shares: {shares:?}"));
+        shares
+    }
+
+    /// Inverse volatility of subscribed instruments for each risk parity deployment
+    async fn risk_parity_weights(
+        &self,
+        deployments: &[SimulationDeployment],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> HashMap<Uuid, f64> {
+        let mut weights = HashMap::new();
+        for deployment in deployments {
+            let Some(deployment_id) = deployment.deployment_id else {
+                continue;
+            };
+            if deployment.allocation != Allocation::RiskParity {
+                continue;
+            }
+            let mut volatilities = Vec::new();
+            for instrument_id in &deployment.subscriptions {
+                if let Err(err) = self.storage_client
+                    .sync(instrument_id, &[deployment.timeframe], from, Some(to))
+                    .await
+                {
+                    error!("Error during risk parity candles sync of: '{instrument_id:?}': {err}");
+                    continue;
+                }
+                let candles: Vec<_> = match self.storage_client
+                    .get_candles(instrument_id, Some(deployment.timeframe), Some(from), Some(to), None)
+                    .await
+                {
+                    Ok(candles) => candles.into_iter().rev().collect(),
+                    Err(err) => {
+                        error!("Error during risk parity candles loading of: '{instrument_id:?}': {err}");
+                        continue;
+                    }
+                };
+                if let Some(volatility) = portfolio::volatility(&candles) {
+                    volatilities.push(volatility);
+                }
+            }
+            weights.insert(deployment_id, portfolio::inverse_volatility(&volatilities));
         }
+        weights
+    }
+
+    async fn calculate_capital(
+        &self,
+        positions: &[SimulationPosition],
+        timestamp: DateTime<Utc>,
+    ) -> f64 {
+        let mut result = 0.0;
+        for position in positions {
+            let instrument_id = InstrumentId {
+                exchange: position.exchange,
+                market_type: MarketType::Spot,
+                pair: CurrencyPair {
+                    target: position.currency,
+                    source: Currency::USDT,
+                },
+            };
+            result += self
+                .convert_currency(
+                    &instrument_id,
+                    timestamp,
+                    position.end,
+                    CurrencyConversion::ToSource,
+                )
+                .await;
+        }
+        result
     }
 
     async fn calculate_profit(
@@ -263,6 +751,7 @@ Simulator<E, S, I, SR>
         timestamp: DateTime<Utc>,
         action: &Action,
         active_orders: &mut Vec<Order>,
+        portfolio: &mut Portfolio,
         logger: &mut Logger,
     ) {
         match action {
@@ -289,6 +778,8 @@ Simulator<E, S, I, SR>
                     self.storage_client.save_order(order.clone()).await.unwrap();
                     logger.log(format!("|-> Place Order: {} {:?} {:?} '{}-{}' {} '{:?}', stop-loss: {:?}, take-profit: {:?}, id: '{}'",
                                        order.exchange, order.market_type, order.order_type, order.pair.target, order.pair.source, order.side, order.size, order.stop_loss, order.take_profit, order.id));
+                    portfolio.add_action(order_action.deployment_id);
+                    portfolio.add_order(&order.id, order_action.deployment_id);
                     active_orders.push(order);
                 }
                 OrderActionType::PatchOrder => unimplemented!(),
@@ -323,6 +814,7 @@ Simulator<E, S, I, SR>
                     simulation_deployment.deployment_id = Some(deployment.id);
                     simulation_deployment.subscriptions = deployment.subscriptions.clone();
                     simulation_deployment.indicators = deployment.indicators.clone();
+                    simulation_deployment.schedules = deployment.schedules.clone();
                 }
             }
         }
@@ -344,19 +836,20 @@ Simulator<E, S, I, SR>
         active_orders: &mut Vec<Order>,
         tick: &Tick,
         positions: &mut Vec<SimulationPosition>,
+        portfolio: &mut Portfolio,
         simulation_stats: &mut SimulationStats,
         logger: &mut Logger,
-    ) {
+    ) -> Vec<Order> {
         let mut completed_orders = Vec::new();
         for order in &mut *active_orders {
             match order.order_type {
                 OrderType::Limit(price) => {
-                    if self.check_limit_order(order, price, tick, positions, simulation_stats, logger).await {
+                    if self.check_limit_order(order, price, tick, positions, portfolio, simulation_stats, logger).await {
                         completed_orders.push(order.id.clone());
                     }
                 }
                 OrderType::Market => {
-                    if self.check_market_order(order, tick, positions, simulation_stats, logger).await {
+                    if self.check_market_order(order, tick, positions, portfolio, simulation_stats, logger).await {
                         completed_orders.push(order.id.clone());
                     }
                 }
@@ -370,9 +863,13 @@ Simulator<E, S, I, SR>
                 self.storage_client.save_order(order.clone()).await.unwrap();
             }
         }
-        active_orders.retain(|order|
-            order.status == OrderStatus::Created ||
-                order.status == OrderStatus::InProgress);
+        let (active, finished) = active_orders
+            .drain(..)
+            .partition(|order|
+                order.status == OrderStatus::Created ||
+                    order.status == OrderStatus::InProgress);
+        *active_orders = active;
+        finished
     }
 
     async fn check_limit_order(
@@ -381,6 +878,7 @@ Simulator<E, S, I, SR>
         price: f64,
         tick: &Tick,
         positions: &mut Vec<SimulationPosition>,
+        portfolio: &mut Portfolio,
         simulation_stats: &mut SimulationStats,
         logger: &mut Logger,
     ) -> bool {
@@ -391,7 +889,7 @@ Simulator<E, S, I, SR>
                         "|--> Execute limit order: {}, price: '{}'",
                         order.id, price
                     ));
-                    self.execute_order(order, price, positions, logger).await;
+                    self.execute_order(order, price, positions, portfolio, logger).await;
                     order.side = change_side(order.side);
                 }
                 Side::Sell if tick.price >= price => {
@@ -399,13 +897,13 @@ Simulator<E, S, I, SR>
                         "|--> Execute limit order: {}, price: '{}'",
                         order.id, price
                     ));
-                    self.execute_order(order, price, positions, logger).await;
+                    self.execute_order(order, price, positions, portfolio, logger).await;
                 }
                 _ => {}
             }
             false
         } else {
-            self.check_sl_and_tp(order, tick, positions, simulation_stats, logger).await
+            self.check_sl_and_tp(order, tick, positions, portfolio, simulation_stats, logger).await
         }
     }
 
@@ -414,6 +912,7 @@ Simulator<E, S, I, SR>
         order: &mut Order,
         tick: &Tick,
         positions: &mut Vec<SimulationPosition>,
+        portfolio: &mut Portfolio,
         simulation_stats: &mut SimulationStats,
         logger: &mut Logger,
     ) -> bool {
@@ -422,11 +921,11 @@ Simulator<E, S, I, SR>
                 "|--> Execute market order: {}, price: '{}'",
                 order.id, tick.price
             ));
-            self.execute_order(order, tick.price, positions, logger)
+            self.execute_order(order, tick.price, positions, portfolio, logger)
                 .await;
             false
         } else {
-            self.check_sl_and_tp(order, tick, positions, simulation_stats, logger).await
+            self.check_sl_and_tp(order, tick, positions, portfolio, simulation_stats, logger).await
         }
     }
 
@@ -435,6 +934,7 @@ Simulator<E, S, I, SR>
         order: &mut Order,
         tick: &Tick,
         positions: &mut Vec<SimulationPosition>,
+        portfolio: &mut Portfolio,
         simulation_stats: &mut SimulationStats,
         logger: &mut Logger,
     ) -> bool {
@@ -444,8 +944,11 @@ Simulator<E, S, I, SR>
                 OrderType::Limit(limit) => limit,
                 OrderType::Market => stop_loss.trigger_px
             };
-            if self.check_sl(order, price, tick, positions, logger).await {
+            if self.check_sl(order, price, tick, positions, portfolio, logger).await {
                 simulation_stats.add_sl();
+                if let Some(ledger) = portfolio.ledger(&order.id) {
+                    ledger.stats.add_sl();
+                }
                 let size = match order.size {
                     Size::Target(size) => size,
                     Size::Source(size) => size,
@@ -465,8 +968,11 @@ Simulator<E, S, I, SR>
                 OrderType::Limit(limit) => limit,
                 OrderType::Market => take_profit.trigger_px
             };
-            if self.check_tp(order, price, tick, positions, logger).await {
+            if self.check_tp(order, price, tick, positions, portfolio, logger).await {
                 simulation_stats.add_tp();
+                if let Some(ledger) = portfolio.ledger(&order.id) {
+                    ledger.stats.add_tp();
+                }
                 let size = match order.size {
                     Size::Target(size) => size,
                     Size::Source(size) => size,
@@ -490,16 +996,17 @@ Simulator<E, S, I, SR>
         price: f64,
         tick: &Tick,
         positions: &mut Vec<SimulationPosition>,
+        portfolio: &mut Portfolio,
         logger: &mut Logger,
     ) -> bool {
         match order.side {
             Side::Buy if tick.price <= price => {
-                self.execute_order(order, price, positions, logger).await;
+                self.execute_order(order, price, positions, portfolio, logger).await;
                 order.avg_sl_price = price;
                 true
             }
             Side::Sell if tick.price >= price => {
-                self.execute_order(order, price, positions, logger).await;
+                self.execute_order(order, price, positions, portfolio, logger).await;
                 order.avg_sl_price = price;
                 true
             }
@@ -513,16 +1020,17 @@ Simulator<E, S, I, SR>
         price: f64,
         tick: &Tick,
         positions: &mut Vec<SimulationPosition>,
+        portfolio: &mut Portfolio,
         logger: &mut Logger,
     ) -> bool {
         match order.side {
             Side::Buy if tick.price >= price => {
-                self.execute_order(order, price, positions, logger).await;
+                self.execute_order(order, price, positions, portfolio, logger).await;
                 order.avg_tp_price = price;
                 true
             }
             Side::Sell if tick.price <= price => {
-                self.execute_order(order, price, positions, logger).await;
+                self.execute_order(order, price, positions, portfolio, logger).await;
                 order.avg_tp_price = price;
                 true
             }
@@ -535,6 +1043,7 @@ Simulator<E, S, I, SR>
         order: &mut Order,
         quote: f64,
         positions: &mut Vec<SimulationPosition>,
+        portfolio: &mut Portfolio,
         logger: &mut Logger,
     ) {
         let target_position_index = positions
@@ -586,37 +1095,28 @@ Simulator<E, S, I, SR>
             order.avg_fill_price = quote;
         }
 
-        let fee_percent = get_fee_percent(order.exchange, order.market_type, side);
-        let (target_size, source_size) = match order.size {
-            Size::Target(size) => (size, size * quote),
-            Size::Source(size) => (size / quote, size),
-        };
-        order.fee += calculate_fee_size(source_size, fee_percent);
-
-        match side {
-            Side::Buy => {
-                self.update_positions(
-                    target_size,
-                    source_size,
-                    fee_percent,
-                    target_position,
-                    source_position,
-                    logger,
-                )
-                    .await;
-            }
-            Side::Sell => {
-                self.update_positions(
-                    source_size,
-                    target_size,
-                    fee_percent,
-                    source_position,
-                    target_position,
-                    logger,
-                )
-                    .await;
+        let (spend, receive, fee) = fill(order, side, quote);
+        if let Some(ledger) = portfolio.ledger(&order.id) {
+            let available = ledger.balance(spend.0);
+            if !is_sl_tp_execution && available < spend.1 {
+                logger.log(format!(
+                    "|--> Reject order: '{}', deployment: '{}' has '{available}' {} of required '{}'",
+                    order.id, ledger.deployment_id, spend.0, spend.1
+                ));
+                order.status = OrderStatus::Failed("Insufficient allocated capital".to_string());
+                self.storage_client.save_order(order.clone()).await.unwrap();
+                return;
             }
+            ledger.update_positions(order.exchange, spend, receive, fee);
         }
+        order.fee += source_fee(order, receive, fee, quote);
+
+        let (received_position, spent_position) = match side {
+            Side::Buy => (target_position, source_position),
+            Side::Sell => (source_position, target_position),
+        };
+        self.update_positions(receive.1, spend.1, fee, received_position, spent_position, logger)
+            .await;
         order.status = OrderStatus::InProgress;
         self.storage_client.save_order(order.clone()).await.unwrap();
     }
@@ -627,8 +1127,9 @@ Simulator<E, S, I, SR>
         simulation: &Simulation,
         from: DateTime<Utc>,
         to: DateTime<Utc>,
-    ) -> Vec<Tick> {
+    ) -> (Vec<Tick>, Vec<Candle>) {
         let mut ticks = Vec::new();
+        let mut closing_candles = Vec::new();
         let simulation_id = Some(simulation.id);
         for deployments in &simulation.deployments {
             let timeframe = deployments.timeframe;
@@ -655,24 +1156,26 @@ Simulator<E, S, I, SR>
                         tick.simulation_id = simulation_id;
                         tick
                     })
-                    .collect()
+                    .collect();
+                closing_candles = candles.into_iter().rev().collect();
             }
         }
         ticks = remove_redundancy(ticks);
-        ticks
+        (ticks, closing_candles)
     }
 
     async fn update_positions(
         &self,
         target_size: f64,
         source_size: f64,
-        fee_percent: f64,
+        fee: f64,
         target_position: Option<&mut SimulationPosition>,
         source_position: Option<&mut SimulationPosition>,
         logger: &mut Logger,
     ) {
         let source_position = source_position.expect("No source asset to execute order");
-        source_position.end -= source_size;
+        let target_position = target_position.expect("No target asset to execute order");
+        fill_positions(source_position, target_position, source_size, target_size, fee);
         self.storage_client
             .save_position(Position::from(source_position.clone()))
             .await
@@ -682,10 +1185,6 @@ Simulator<E, S, I, SR>
             source_position.exchange, source_position.currency, source_position.end, source_size
         ));
 
-        let target_position = target_position.expect("No target asset to execute order");
-        let fee = calculate_fee_size(target_size, fee_percent);
-        target_position.end += target_size - fee;
-        target_position.fees += fee;
         self.storage_client
             .save_position(Position::from(target_position.clone()))
             .await
@@ -701,6 +1200,13 @@ Simulator<E, S, I, SR>
     }
 }
 
+fn validate(simulation: &CreateSimulation) -> Result<()> {
+    if simulation.end <= simulation.start {
+        bail!(SimulatorError::InvalidSimulation(String::from("end must be after start")));
+    }
+    Ok(())
+}
+
 fn candle_to_ticks(candle: &Candle) -> Vec<Tick> {
     let open_tick = Tick::new(
         None,
@@ -784,6 +1290,31 @@ fn calculate_fee_size(size: f64, fee_percent: f64) -> f64 {
     size * fee_percent / 100.0
 }
 
+/// Spent and received amounts of the filled order, the fee is charged in the received currency
+fn fill(order: &Order, side: Side, quote: f64) -> ((Currency, f64), (Currency, f64), f64) {
+    let fee_percent = get_fee_percent(order.exchange, order.market_type, side);
+    let (target_size, source_size) = match order.size {
+        Size::Target(size) => (size, size * quote),
+        Size::Source(size) => (size / quote, size),
+    };
+    let (spend, receive) = match side {
+        Side::Buy => ((order.pair.source, source_size), (order.pair.target, target_size)),
+        Side::Sell => ((order.pair.target, target_size), (order.pair.source, source_size)),
+    };
+    (spend, receive, calculate_fee_size(receive.1, fee_percent))
+}
+
+/// Orders report fees in the source currency
+fn source_fee(order: &Order, receive: (Currency, f64), fee: f64, quote: f64) -> f64 {
+    if receive.0 == order.pair.source { fee } else { fee * quote }
+}
+
+fn fill_positions(spent: &mut SimulationPosition, received: &mut SimulationPosition, spend: f64, receive: f64, fee: f64) {
+    spent.end -= spend;
+    received.end += receive - fee;
+    received.fees += fee;
+}
+
 fn convert_to_create_deployment_dto(
     value: SimulationDeployment,
     simulation_id: Uuid,
@@ -793,6 +1324,10 @@ fn convert_to_create_deployment_dto(
         state_id: None,
         plugin_id: value.plugin_id,
         params: value.params,
+        execution: Default::default(),
+        risk: Default::default(),
+        mode: Default::default(),
+        upgrade: Default::default(),
     }
 }
 
@@ -803,7 +1338,7 @@ enum CurrencyConversion {
 }
 
 #[derive(Default)]
-struct SimulationStats {
+pub(crate) struct SimulationStats {
     sl_count: u64,
     tp_count: u64,
     current_sl_streak: u64,
@@ -848,3 +1383,108 @@ impl SimulationStats {
         } else { 0. }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use domain_model::PluginId;
+
+    use crate::portfolio::Ledger;
+
+    use super::*;
+
+    fn order(side: Side, size: Size) -> Order {
+        Order {
+            id: String::from("order"),
+            timestamp: Utc::now(),
+            simulation_id: Some(Uuid::nil()),
+            status: OrderStatus::Created,
+            exchange: Exchange::OKX,
+            pair: CurrencyPair {
+                target: Currency::BTC,
+                source: Currency::USDT,
+            },
+            market_type: OrderMarketType::Spot,
+            order_type: OrderType::Market,
+            side,
+            size,
+            fee: 0.,
+            avg_fill_price: 0.,
+            stop_loss: None,
+            avg_sl_price: 0.,
+            take_profit: None,
+            avg_tp_price: 0.,
+        }
+    }
+
+    fn position(currency: Currency, end: f64) -> SimulationPosition {
+        SimulationPosition {
+            simulation_id: Uuid::nil(),
+            exchange: Exchange::OKX,
+            currency,
+            start: end,
+            end,
+            diff: 0.,
+            fees: 0.,
+        }
+    }
+
+    fn ledger() -> Ledger {
+        Ledger {
+            deployment_id: Uuid::nil(),
+            plugin_id: PluginId::new("test", 1),
+            allocation: Allocation::RiskParity,
+            positions: vec![position(Currency::USDT, 1000.), position(Currency::BTC, 10.)],
+            stats: SimulationStats::default(),
+            actions: 0,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "'{actual}' is not '{expected}'");
+    }
+
+    /// Ledger and global positions are charged the same fee, the order reports it in USDT
+    fn assert_fill(order: &Order, side: Side, received: Currency, fee: f64, source_fee_size: f64) {
+        let (spend, receive, actual_fee) = fill(order, side, 100.);
+        assert_eq!(receive.0, received);
+        assert_close(actual_fee, fee);
+        assert_close(source_fee(order, receive, actual_fee, 100.), source_fee_size);
+
+        let mut ledger = ledger();
+        ledger.update_positions(Exchange::OKX, spend, receive, actual_fee);
+        let (mut spent_position, mut received_position) = match side {
+            Side::Buy => (position(Currency::USDT, 1000.), position(Currency::BTC, 10.)),
+            Side::Sell => (position(Currency::BTC, 10.), position(Currency::USDT, 1000.)),
+        };
+        fill_positions(&mut spent_position, &mut received_position, spend.1, receive.1, actual_fee);
+
+        let ledger_received = ledger.positions.iter().find(|position| position.currency == received).unwrap();
+        assert_eq!(ledger_received.end, received_position.end);
+        assert_eq!(ledger_received.fees, actual_fee);
+        assert_eq!(received_position.fees, actual_fee);
+        assert_eq!(ledger.balance(spend.0), spent_position.end);
+    }
+
+    #[test]
+    fn test_buy_fee() {
+        // 1 BTC for 100 USDT, 0.08% of the received BTC
+        assert_fill(&order(Side::Buy, Size::Target(1.)), Side::Buy, Currency::BTC, 0.0008, 0.08);
+        assert_fill(&order(Side::Buy, Size::Source(100.)), Side::Buy, Currency::BTC, 0.0008, 0.08);
+    }
+
+    #[test]
+    fn test_sell_fee() {
+        // 1 BTC for 100 USDT, 0.1% of the received USDT
+        assert_fill(&order(Side::Sell, Size::Target(1.)), Side::Sell, Currency::USDT, 0.1, 0.1);
+        assert_fill(&order(Side::Sell, Size::Source(100.)), Side::Sell, Currency::USDT, 0.1, 0.1);
+    }
+
+    #[test]
+    fn test_filled_positions() {
+        let mut ledger = ledger();
+        let (spend, receive, fee) = fill(&order(Side::Buy, Size::Target(1.)), Side::Buy, 100.);
+        ledger.update_positions(Exchange::OKX, spend, receive, fee);
+        assert_close(ledger.balance(Currency::USDT), 900.);
+        assert_close(ledger.balance(Currency::BTC), 11. - 0.0008);
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, RwLock};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use domain_model::{Action, Candle, Order, OrderStatus, Simulation, Tick};
+use engine_core_api::api::EngineApi;
+use simulator_core_api::{DebugStep, StopReason};
+
+use crate::api::SimulationStats;
+use crate::file_logger::Logger;
+use crate::portfolio::Portfolio;
+use crate::timers::Timers;
+
+pub(crate) type DebugSessions = Arc<RwLock<HashMap<Uuid, Arc<Mutex<DebugSession>>>>>;
+
+/// Simulation paused between ticks, the cursor is moved by debug steps
+pub(crate) struct DebugSession {
+    pub simulation: Simulation,
+    pub portfolio: Portfolio,
+    pub stats: SimulationStats,
+    pub logger: Logger,
+    // loaded but not processed ticks of the current batch
+    pub ticks: VecDeque<Tick>,
+    // candles of the current batch which are not closed yet
+    pub candles: VecDeque<Candle>,
+    pub timers: Timers,
+    pub batch_start: DateTime<Utc>,
+    pub batch_end: DateTime<Utc>,
+    pub last_tick: Option<Tick>,
+    pub last_actions: Vec<Action>,
+    pub processed: u32,
+    pub reason: StopReason,
+    // last time the session was started, stepped or viewed
+    pub used: Instant,
+    // the session occupies a simulation worker until it is finished or expired
+    _permit: OwnedSemaphorePermit,
+}
+
+impl DebugSession {
+    pub fn new(simulation: Simulation, portfolio: Portfolio, logger: Logger, permit: OwnedSemaphorePermit) -> Self {
+        let timers = Timers::new(&simulation.deployments, simulation.start);
+        Self {
+            batch_start: simulation.start,
+            batch_end: simulation.start,
+            simulation,
+            portfolio,
+            stats: SimulationStats::default(),
+            logger,
+            ticks: VecDeque::new(),
+            candles: VecDeque::new(),
+            timers,
+            last_tick: None,
+            last_actions: Vec::new(),
+            processed: 0,
+            reason: StopReason::Start,
+            used: Instant::now(),
+            _permit: permit,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.ticks.is_empty() && self.batch_end == self.simulation.end
+    }
+}
+
+/// Finds the first condition of the step met after processing `tick`
+pub(crate) fn stop_reason(step: &DebugStep, tick: &Tick, stepped: u32, order_event: bool) -> Option<StopReason> {
+    if let Some(breakpoint) = step.breakpoints.iter().find(|breakpoint| breakpoint.is_hit(tick)) {
+        return Some(StopReason::Breakpoint(breakpoint.clone()));
+    }
+    if step.on_order && order_event {
+        return Some(StopReason::Order);
+    }
+    if step.until.is_some_and(|until| tick.timestamp >= until) {
+        return Some(StopReason::Timestamp);
+    }
+    let no_conditions = step.until.is_none() && !step.on_order && step.breakpoints.is_empty();
+    let ticks = step.ticks.or(no_conditions.then_some(1));
+    if ticks.is_some_and(|ticks| stepped >= ticks) {
+        return Some(StopReason::Ticks);
+    }
+    None
+}
+
+/// Ids and statuses of active orders, a change between ticks is an order event
+pub(crate) fn orders_state(orders: &[Order]) -> Vec<(String, OrderStatus)> {
+    orders
+        .iter()
+        .map(|order| (order.id.clone(), order.status.clone()))
+        .collect()
+}
+
+/// Deletes sessions unused for `idle` together with their deployments, abandoned sessions would keep
+/// engine deployments and simulation workers forever. Sessions busy with a step are never expired
+pub(crate) async fn expire<E: EngineApi>(engine_client: Arc<E>, sessions: DebugSessions, idle: Duration) {
+    let mut interval = tokio::time::interval(idle.min(Duration::from_secs(60)));
+    loop {
+        interval.tick().await;
+        let expired: Vec<_> = {
+            let mut sessions = sessions.write().await;
+            let ids: Vec<_> = sessions
+                .iter()
+                .filter(|(_, session)| session.try_lock().is_ok_and(|session| session.used.elapsed() >= idle))
+                .map(|(id, _)| *id)
+                .collect();
+            ids.iter().filter_map(|id| sessions.remove(id)).collect()
+        };
+        for session in expired {
+            let session = session.lock().await;
+            info!("Debug session: '{}' is unused for: '{idle:?}', it is deleted", session.simulation.id);
+            for deployment_id in session.simulation.deployments.iter().filter_map(|deployment| deployment.deployment_id) {
+                if engine_client.delete_deployment(deployment_id).await.is_none() {
+                    warn!("Deployment: '{deployment_id}' of debug session: '{}' is not found", session.simulation.id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::async_trait;
+    use tokio::sync::broadcast::Receiver;
+    use tokio::sync::Semaphore;
+
+    use domain_model::{Allocation, Currency, CurrencyPair, DeploymentInfo, DeploymentPatch, Exchange, InstrumentId, KillSwitch, KillSwitchReport, MarketType, NewDeployment, PluginEvent, PluginId, RiskRejection, SimulationDeployment, Timeframe};
+    use domain_model::events::EngineEvent;
+    use domain_model::shadow::ShadowReport;
+    use domain_model::state::StateSnapshot;
+    use engine_core_api::api::EngineError;
+    use simulator_core_api::Breakpoint;
+
+    use super::*;
+
+    fn tick(price: f64) -> Tick {
+        let pair = CurrencyPair { target: Currency::BTC, source: Currency::USDT };
+        Tick::new(Some(Uuid::nil()), Utc::now(), InstrumentId::new(Exchange::OKX, MarketType::Spot, pair), price)
+    }
+
+    #[test]
+    fn test_step_without_conditions_stops_after_tick() {
+        let step = DebugStep::default();
+        assert_eq!(stop_reason(&step, &tick(100.), 1, false), Some(StopReason::Ticks));
+    }
+
+    #[test]
+    fn test_ticks_stop_after_count() {
+        let step = DebugStep {
+            ticks: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(stop_reason(&step, &tick(100.), 2, false), None);
+        assert_eq!(stop_reason(&step, &tick(100.), 3, false), Some(StopReason::Ticks));
+    }
+
+    #[test]
+    fn test_breakpoint_stops_first() {
+        let step = DebugStep {
+            on_order: true,
+            breakpoints: vec![Breakpoint::PriceAbove(200.), Breakpoint::PriceBelow(50.)],
+            ..Default::default()
+        };
+        assert_eq!(stop_reason(&step, &tick(100.), 1, false), None);
+        assert_eq!(
+            stop_reason(&step, &tick(40.), 1, true),
+            Some(StopReason::Breakpoint(Breakpoint::PriceBelow(50.)))
+        );
+    }
+
+    #[test]
+    fn test_order_event_stops() {
+        let step = DebugStep {
+            on_order: true,
+            ..Default::default()
+        };
+        assert_eq!(stop_reason(&step, &tick(100.), 5, false), None);
+        assert_eq!(stop_reason(&step, &tick(100.), 5, true), Some(StopReason::Order));
+    }
+
+    #[test]
+    fn test_until_stops_at_timestamp() {
+        let tick = tick(100.);
+        let step = DebugStep {
+            until: Some(tick.timestamp),
+            ..Default::default()
+        };
+        assert_eq!(stop_reason(&step, &tick, 1, false), Some(StopReason::Timestamp));
+        let step = DebugStep {
+            until: Some(tick.timestamp + chrono::Duration::seconds(1)),
+            ..Default::default()
+        };
+        // conditions replace the implicit single tick
+        assert_eq!(stop_reason(&step, &tick, 1, false), None);
+    }
+
+    #[derive(Default)]
+    struct TestEngine {
+        deleted: std::sync::Mutex<Vec<Uuid>>,
+    }
+
+    #[async_trait]
+    impl EngineApi for TestEngine {
+        async fn get_deployments_info(&self) -> Vec<DeploymentInfo> { unimplemented!() }
+        async fn deploy(&self, _deployments: &[NewDeployment]) -> Result<Vec<DeploymentInfo>, EngineError> { unimplemented!() }
+        async fn get_actions(&self, _tick: &Tick) -> Vec<Action> { unimplemented!() }
+        async fn get_actions_batch(&self, _ticks: &[Tick]) -> Vec<Vec<Action>> { unimplemented!() }
+        async fn handle_event(&self, _event: PluginEvent) -> Vec<Action> { unimplemented!() }
+        async fn delete_deployment(&self, id: Uuid) -> Option<DeploymentInfo> {
+            self.deleted.lock().unwrap().push(id);
+            None
+        }
+        async fn get_deployment_state(&self, _id: Uuid) -> Option<String> { unimplemented!() }
+        async fn get_state_history(&self, _id: Uuid) -> Option<Vec<StateSnapshot>> { unimplemented!() }
+        async fn rollback_state(&self, _id: Uuid, _version: i64) -> Option<String> { unimplemented!() }
+        async fn update_deployment(&self, _id: Uuid, _patch: DeploymentPatch) -> Result<Option<DeploymentInfo>, EngineError> { unimplemented!() }
+        async fn pause_deployment(&self, _id: Uuid) -> Option<DeploymentInfo> { unimplemented!() }
+        async fn resume_deployment(&self, _id: Uuid) -> Option<DeploymentInfo> { unimplemented!() }
+        async fn get_risk_rejections(&self, _deployment_id: Option<Uuid>) -> Vec<RiskRejection> { unimplemented!() }
+        async fn get_shadow_report(&self, _id: Uuid, _live_id: Uuid, _from: Option<DateTime<Utc>>, _to: Option<DateTime<Utc>>) -> Option<ShadowReport> { unimplemented!() }
+        async fn kill_switch(&self, _request: KillSwitch) -> KillSwitchReport { unimplemented!() }
+        fn subscribe_events(&self) -> Receiver<EngineEvent> { unimplemented!() }
+        async fn update_plugin(&self, _plugin_id: PluginId) { unimplemented!() }
+        async fn rollback_plugin(&self, _id: Uuid) -> Result<Option<DeploymentInfo>, EngineError> { unimplemented!() }
+    }
+
+    async fn session(deployment_id: Uuid, used: Instant) -> Arc<Mutex<DebugSession>> {
+        let simulation = Simulation {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            start: Utc::now(),
+            end: Utc::now(),
+            positions: Vec::new(),
+            deployments: vec![SimulationDeployment {
+                deployment_id: Some(deployment_id),
+                timeframe: Timeframe::OneM,
+                plugin_id: PluginId::new("test", 1),
+                params: HashMap::new(),
+                subscriptions: Vec::new(),
+                indicators: Vec::new(),
+                schedules: Vec::new(),
+                allocation: Allocation::default(),
+            }],
+            ticks_len: 0,
+            actions_count: 0,
+            active_orders: Vec::new(),
+        };
+        let portfolio = Portfolio::new(&simulation.deployments, &simulation.positions, &[1.]);
+        let logger = Logger::new(simulation.id);
+        let permit = Arc::new(Semaphore::new(1)).acquire_owned().await.unwrap();
+        let mut session = DebugSession::new(simulation, portfolio, logger, permit);
+        session.used = used;
+        Arc::new(Mutex::new(session))
+    }
+
+    #[tokio::test]
+    async fn test_expire_deletes_idle_sessions() {
+        let idle = Duration::from_secs(60);
+        let long_ago = Instant::now() - 2 * idle;
+        let (idle_deployment, busy_deployment, used_deployment) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let busy_session = session(busy_deployment, long_ago).await;
+        let sessions = DebugSessions::default();
+        {
+            let mut sessions = sessions.write().await;
+            sessions.insert(Uuid::new_v4(), session(idle_deployment, long_ago).await);
+            sessions.insert(Uuid::new_v4(), Arc::clone(&busy_session));
+            sessions.insert(Uuid::new_v4(), session(used_deployment, Instant::now()).await);
+        }
+        // a session stepped at the moment is locked
+        let step = busy_session.lock().await;
+
+        let engine = Arc::new(TestEngine::default());
+        // the first expiry check runs at once
+        let task = tokio::spawn(expire(Arc::clone(&engine), Arc::clone(&sessions), idle));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        task.abort();
+        drop(step);
+
+        assert_eq!(*engine.deleted.lock().unwrap(), vec![idle_deployment]);
+        assert_eq!(sessions.read().await.len(), 2);
+    }
+}
@@ -1,5 +1,11 @@
 pub use api::Simulator;
+pub use retention::RetentionPolicy;
 
 mod api;
+mod debugger;
 mod file_logger;
+mod portfolio;
+mod retention;
+mod timers;
+mod worker_pool;
 
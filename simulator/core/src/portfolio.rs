@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use domain_model::{Allocation, Candle, Currency, Exchange, PluginId, SimulationDeployment, SimulationPosition};
+
+use crate::api::SimulationStats;
+
+/// Splits simulation positions between deployments, each deployment trades only within its own sub-ledger
+pub struct Portfolio {
+    pub ledgers: Vec<Ledger>,
+    orders: HashMap<String, Uuid>,
+}
+
+pub struct Ledger {
+    pub deployment_id: Uuid,
+    pub plugin_id: PluginId,
+    pub allocation: Allocation,
+    pub positions: Vec<SimulationPosition>,
+    pub stats: SimulationStats,
+    pub actions: u32,
+}
+
+impl Portfolio {
+    pub fn new(deployments: &[SimulationDeployment], positions: &[SimulationPosition], shares: &[f64]) -> Self {
+        let ledgers = deployments
+            .iter()
+            .zip(shares)
+            .filter_map(|(deployment, share)| {
+                deployment.deployment_id.map(|deployment_id| Ledger {
+                    deployment_id,
+                    plugin_id: deployment.plugin_id.clone(),
+                    allocation: deployment.allocation.clone(),
+                    positions: positions
+                        .iter()
+                        .map(|position| SimulationPosition {
+                            start: position.start * share,
+                            end: position.start * share,
+                            ..position.clone()
+                        })
+                        .collect(),
+                    stats: SimulationStats::default(),
+                    actions: 0,
+                })
+            })
+            .collect();
+        Self {
+            ledgers,
+            orders: HashMap::new(),
+        }
+    }
+
+    pub fn add_action(&mut self, deployment_id: Uuid) {
+        if let Some(ledger) = self.ledger_by_deployment(deployment_id) {
+            ledger.actions += 1;
+        }
+    }
+
+    pub fn add_order(&mut self, order_id: &str, deployment_id: Uuid) {
+        self.orders.insert(order_id.to_string(), deployment_id);
+    }
+
+    pub fn order_owner(&self, order_id: &str) -> Option<Uuid> {
+        self.orders.get(order_id).copied()
+    }
+
+    /// End balances of all ledger positions, compared after a tick to find changed positions
+    pub fn balances(&self) -> Vec<(Uuid, Currency, f64)> {
+        self.ledgers
+            .iter()
+            .flat_map(|ledger| ledger.positions
+                .iter()
+                .map(|position| (ledger.deployment_id, position.currency, position.end)))
+            .collect()
+    }
+
+    pub fn changed_positions(&self, balances: &[(Uuid, Currency, f64)]) -> Vec<(Uuid, SimulationPosition)> {
+        self.ledgers
+            .iter()
+            .flat_map(|ledger| ledger.positions
+                .iter()
+                .filter(|position| !balances.contains(&(ledger.deployment_id, position.currency, position.end)))
+                .map(|position| (ledger.deployment_id, position.clone())))
+            .collect()
+    }
+
+    pub fn ledger(&mut self, order_id: &str) -> Option<&mut Ledger> {
+        let deployment_id = *self.orders.get(order_id)?;
+        self.ledger_by_deployment(deployment_id)
+    }
+
+    fn ledger_by_deployment(&mut self, deployment_id: Uuid) -> Option<&mut Ledger> {
+        self.ledgers
+            .iter_mut()
+            .find(|ledger| ledger.deployment_id == deployment_id)
+    }
+
+    /// Redistributes capital of risk parity ledgers with new weights. Transferred amounts
+    /// are applied to both start and end, so the profit of each ledger stays its own.
+    pub fn rebalance(&mut self, weights: &HashMap<Uuid, f64>) {
+        let weights_sum: f64 = weights.values().sum();
+        if weights_sum <= 0. {
+            return;
+        }
+        let mut totals: Vec<(Currency, f64)> = Vec::new();
+        self.ledgers
+            .iter()
+            .filter(|ledger| weights.contains_key(&ledger.deployment_id))
+            .flat_map(|ledger| &ledger.positions)
+            .for_each(|position| {
+                match totals.iter_mut().find(|(currency, _)| *currency == position.currency) {
+                    Some((_, total)) => *total += position.end,
+                    None => totals.push((position.currency, position.end)),
+                }
+            });
+
+        for ledger in &mut self.ledgers {
+            let Some(weight) = weights.get(&ledger.deployment_id) else {
+                continue;
+            };
+            for position in &mut ledger.positions {
+                let total = totals
+                    .iter()
+                    .find(|(currency, _)| *currency == position.currency)
+                    .map(|(_, total)| *total)
+                    .unwrap_or_default();
+                let target = total * weight / weights_sum;
+                let transfer = target - position.end;
+                position.start += transfer;
+                position.end = target;
+            }
+        }
+    }
+}
+
+impl Ledger {
+    pub fn balance(&self, currency: Currency) -> f64 {
+        self.positions
+            .iter()
+            .find(|position| position.currency == currency)
+            .map(|position| position.end)
+            .unwrap_or_default()
+    }
+
+    pub fn update_positions(&mut self, exchange: Exchange, spend: (Currency, f64), receive: (Currency, f64), fee: f64) {
+        if let Some(position) = self.position(exchange, spend.0) {
+            position.end -= spend.1;
+        }
+        if let Some(position) = self.position(exchange, receive.0) {
+            position.end += receive.1 - fee;
+            position.fees += fee;
+        }
+    }
+
+    fn position(&mut self, exchange: Exchange, currency: Currency) -> Option<&mut SimulationPosition> {
+        let index = self.positions
+            .iter()
+            .position(|position| position.currency == currency);
+        let index = match index {
+            Some(index) => index,
+            None => {
+                let position = SimulationPosition {
+                    exchange,
+                    currency,
+                    start: 0.,
+                    end: 0.,
+                    diff: 0.,
+                    fees: 0.,
+                    ..self.positions.first()?.clone()
+                };
+                self.positions.push(position);
+                self.positions.len() - 1
+            }
+        };
+        self.positions.get_mut(index)
+    }
+}
+
+/// Standard deviation of close price returns, `None` if there is not enough candles
+pub fn volatility(candles: &[Candle]) -> Option<f64> {
+    let returns: Vec<_> = candles
+        .windows(2)
+        .filter(|pair| pair[0].close_price != 0.)
+        .map(|pair| (pair[1].close_price - pair[0].close_price) / pair[0].close_price)
+        .collect();
+    if returns.len() < 2 {
+        return None;
+    }
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>() / (returns.len() - 1) as f64;
+    let volatility = variance.sqrt();
+    (volatility > 0.).then_some(volatility)
+}
+
+/// Risk parity weight of a deployment, `1` if volatility of its instruments is unknown
+pub fn inverse_volatility(volatilities: &[f64]) -> f64 {
+    if volatilities.is_empty() {
+        1.
+    } else {
+        volatilities.len() as f64 / volatilities.iter().sum::<f64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use domain_model::{CandleStatus, CurrencyPair, InstrumentId, MarketType, Timeframe};
+
+    use super::*;
+
+    fn position(currency: Currency, end: f64) -> SimulationPosition {
+        SimulationPosition {
+            simulation_id: Uuid::nil(),
+            exchange: Exchange::OKX,
+            currency,
+            start: end,
+            end,
+            diff: 0.,
+            fees: 0.,
+        }
+    }
+
+    fn ledger(deployment_id: Uuid, allocation: Allocation, usdt: f64) -> Ledger {
+        Ledger {
+            deployment_id,
+            plugin_id: PluginId::new("test", 1),
+            allocation,
+            positions: vec![position(Currency::USDT, usdt)],
+            stats: SimulationStats::default(),
+            actions: 0,
+        }
+    }
+
+    fn portfolio(ledgers: Vec<Ledger>) -> Portfolio {
+        Portfolio {
+            ledgers,
+            orders: HashMap::new(),
+        }
+    }
+
+    fn candles(prices: &[f64]) -> Vec<Candle> {
+        let start = Utc.with_ymd_and_hms(2023, 12, 1, 0, 0, 0).unwrap();
+        prices
+            .iter()
+            .enumerate()
+            .map(|(index, price)| Candle {
+                id: index.to_string(),
+                status: CandleStatus::Close,
+                instrument_id: InstrumentId {
+                    exchange: Exchange::OKX,
+                    market_type: MarketType::Spot,
+                    pair: CurrencyPair {
+                        target: Currency::BTC,
+                        source: Currency::USDT,
+                    },
+                },
+                timestamp: start + Duration::hours(index as i64),
+                timeframe: Timeframe::OneH,
+                open_price: *price,
+                highest_price: *price,
+                lowest_price: *price,
+                close_price: *price,
+                target_volume: 1.,
+                source_volume: *price,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rebalance_splits_totals_by_weights() {
+        let (first, second, fixed) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut portfolio = portfolio(vec![
+            ledger(first, Allocation::RiskParity, 120.),
+            ledger(second, Allocation::RiskParity, 80.),
+            ledger(fixed, Allocation::Fixed(50.), 50.),
+        ]);
+        portfolio.ledgers[0].positions[0].start = 100.;
+        let weights = HashMap::from([(first, 1.), (second, 3.)]);
+
+        portfolio.rebalance(&weights);
+
+        let first = &portfolio.ledgers[0].positions[0];
+        assert_eq!(first.end, 50.);
+        // the profit of 20 stays with the ledger
+        assert_eq!(first.end - first.start, 20.);
+        let second = &portfolio.ledgers[1].positions[0];
+        assert_eq!(second.end, 150.);
+        assert_eq!(second.start, 150.);
+        let fixed = &portfolio.ledgers[2].positions[0];
+        assert_eq!((fixed.start, fixed.end), (50., 50.));
+    }
+
+    #[test]
+    fn test_rebalance_ignores_zero_weights() {
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut portfolio = portfolio(vec![
+            ledger(first, Allocation::RiskParity, 120.),
+            ledger(second, Allocation::RiskParity, 80.),
+        ]);
+        let weights = HashMap::from([(first, 0.), (second, 0.)]);
+
+        portfolio.rebalance(&weights);
+
+        assert_eq!(portfolio.ledgers[0].positions[0].end, 120.);
+        assert_eq!(portfolio.ledgers[1].positions[0].end, 80.);
+    }
+
+    #[test]
+    fn test_volatility() {
+        assert_eq!(volatility(&candles(&[100., 110.])), None);
+        assert_eq!(volatility(&candles(&[100., 100., 100., 100.])), None);
+        // returns are 0.1 and -0.1
+        let volatility = volatility(&candles(&[100., 110., 99.])).unwrap();
+        assert!((volatility - 0.02_f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_inverse_volatility() {
+        assert_eq!(inverse_volatility(&[]), 1.);
+        assert_eq!(inverse_volatility(&[0.5]), 2.);
+        assert_eq!(inverse_volatility(&[0.25, 0.75]), 2.);
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use tracing::{debug, error, info};
+
+use simulator_core_api::SimulationReport;
+use simulator_persistence_api::SimulationReportRepository;
+use storage_core_api::StorageApi;
+
+pub struct RetentionPolicy {
+    pub keep_last: Option<usize>,
+    pub max_age: Option<Duration>,
+    pub interval: std::time::Duration,
+}
+
+pub async fn delete_simulation<S: StorageApi, SR: SimulationReportRepository>(
+    storage_client: &S,
+    simulation_report_repository: &SR,
+    report: &SimulationReport,
+) -> Result<()> {
+    let deployment_ids: Vec<_> = report
+        .deployments
+        .iter()
+        .filter_map(|deployment| deployment.deployment_id)
+        .collect();
+    let cleanup_report = storage_client
+        .delete_simulation(report.simulation_id, &deployment_ids)
+        .await?;
+    simulation_report_repository
+        .delete(report.simulation_id)
+        .await?;
+    info!("Simulation: '{}' deleted, artifacts: '{cleanup_report:?}'", report.simulation_id);
+    Ok(())
+}
+
+pub async fn run<S: StorageApi, SR: SimulationReportRepository>(
+    storage_client: Arc<S>,
+    simulation_report_repository: Arc<SR>,
+    policy: RetentionPolicy,
+) {
+    let mut interval = tokio::time::interval(policy.interval);
+    loop {
+        interval.tick().await;
+        let reports = simulation_report_repository.get(None).await;
+        let expired_reports = expired(reports, &policy);
+        debug!("Retention found: '{}' expired simulations", expired_reports.len());
+        for report in &expired_reports {
+            if let Err(err) = delete_simulation(
+                storage_client.as_ref(),
+                simulation_report_repository.as_ref(),
+                report,
+            ).await {
+                error!("Error during simulation: '{}' deleting: {err}", report.simulation_id);
+            }
+        }
+    }
+}
+
+fn expired(mut reports: Vec<SimulationReport>, policy: &RetentionPolicy) -> Vec<SimulationReport> {
+    reports.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    let now = Utc::now();
+    reports
+        .into_iter()
+        .enumerate()
+        .filter(|(index, report)| {
+            let is_out_of_limit = policy.keep_last.is_some_and(|keep_last| *index >= keep_last);
+            let is_too_old = policy.max_age.is_some_and(|max_age| now - report.timestamp > max_age);
+            is_out_of_limit || is_too_old
+        })
+        .map(|(_, report)| report)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn report(age: Duration) -> SimulationReport {
+        let timestamp = Utc::now() - age;
+        SimulationReport {
+            simulation_id: Uuid::new_v4(),
+            timestamp,
+            start: timestamp,
+            end: timestamp,
+            deployments: Vec::new(),
+            ticks: 0,
+            actions: 0,
+            profit: 0.,
+            profit_clear: 0.,
+            fees: 0.,
+            assets: Vec::new(),
+            active_orders: Vec::new(),
+            sl_count: 0,
+            tp_count: 0,
+            sl_percent: 0.,
+            tp_percent: 0.,
+            max_sl_streak: 0,
+            max_tp_streak: 0,
+            deployment_reports: Vec::new(),
+        }
+    }
+
+    fn policy(keep_last: Option<usize>, max_age: Option<Duration>) -> RetentionPolicy {
+        RetentionPolicy {
+            keep_last,
+            max_age,
+            interval: std::time::Duration::from_secs(60),
+        }
+    }
+
+    fn ids(reports: &[SimulationReport]) -> Vec<Uuid> {
+        reports.iter().map(|report| report.simulation_id).collect()
+    }
+
+    #[test]
+    fn test_nothing_expires_without_policy() {
+        let reports = vec![report(Duration::days(100)), report(Duration::days(1))];
+        assert!(expired(reports, &policy(None, None)).is_empty());
+    }
+
+    #[test]
+    fn test_keep_last_expires_oldest() {
+        let (newest, middle, oldest) = (report(Duration::days(1)), report(Duration::days(2)), report(Duration::days(3)));
+        let reports = vec![oldest.clone(), newest, middle.clone()];
+        let expired = expired(reports, &policy(Some(1), None));
+        assert_eq!(ids(&expired), ids(&[middle, oldest]));
+    }
+
+    #[test]
+    fn test_max_age_expires_old() {
+        let (new, old) = (report(Duration::hours(1)), report(Duration::days(8)));
+        let expired = expired(vec![new, old.clone()], &policy(None, Some(Duration::days(7))));
+        assert_eq!(ids(&expired), ids(&[old]));
+    }
+
+    #[test]
+    fn test_any_limit_expires() {
+        let (newest, middle, oldest) = (report(Duration::hours(1)), report(Duration::hours(2)), report(Duration::days(8)));
+        let reports = vec![newest, middle, oldest.clone()];
+        let expired = expired(reports, &policy(Some(2), Some(Duration::days(7))));
+        assert_eq!(ids(&expired), ids(&[oldest]));
+        let (newest, middle, oldest) = (report(Duration::hours(1)), report(Duration::hours(2)), report(Duration::days(8)));
+        let reports = vec![newest, middle.clone(), oldest.clone()];
+        let expired = expired(reports, &policy(Some(1), Some(Duration::days(7))));
+        assert_eq!(ids(&expired), ids(&[middle, oldest]));
+    }
+}
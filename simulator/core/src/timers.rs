@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use domain_model::SimulationDeployment;
+use domain_model::schedule::Schedule;
+
+/// Plugin schedules driven by simulated time
+#[derive(Default)]
+pub struct Timers {
+    timers: Vec<Timer>,
+}
+
+struct Timer {
+    deployment_id: Uuid,
+    schedule: Schedule,
+    next: DateTime<Utc>,
+}
+
+impl Timers {
+    pub fn new(deployments: &[SimulationDeployment], start: DateTime<Utc>) -> Self {
+        let timers = deployments
+            .iter()
+            .filter_map(|deployment| deployment.deployment_id.map(|deployment_id| (deployment_id, &deployment.schedules)))
+            .flat_map(|(deployment_id, schedules)| schedules
+                .iter()
+                .filter_map(move |schedule| Some(Timer {
+                    deployment_id,
+                    schedule: schedule.clone(),
+                    next: schedule.next_after(start).ok()?,
+                })))
+            .collect();
+        Self { timers }
+    }
+
+    /// Takes the earliest timer fired not later than `until` and moves it to its next fire time,
+    /// returns the deployment id, schedule name and fire time
+    pub fn next_fired(&mut self, until: DateTime<Utc>) -> Option<(Uuid, String, DateTime<Utc>)> {
+        let timer = self.timers
+            .iter_mut()
+            .filter(|timer| timer.next <= until)
+            .min_by_key(|timer| timer.next)?;
+        let fired = (timer.deployment_id, timer.schedule.name.clone(), timer.next);
+        // validated when the timer is created
+        timer.next = timer.schedule.next_after(timer.next).unwrap();
+        Some(fired)
+    }
+}
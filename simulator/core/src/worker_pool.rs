@@ -0,0 +1,90 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{bail, Result};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+
+use simulator_core_api::SimulatorError;
+
+pub struct WorkerPool {
+    workers: Arc<Semaphore>,
+    queue_size: usize,
+    queued: Arc<AtomicUsize>,
+}
+
+impl WorkerPool {
+    pub fn new(workers: usize, queue_size: usize) -> Self {
+        Self {
+            workers: Arc::new(Semaphore::new(workers)),
+            queue_size,
+            queued: Default::default(),
+        }
+    }
+
+    /// Waits for a free worker. Fails immediately if the queue of waiting simulations is full.
+    pub async fn acquire(&self) -> Result<OwnedSemaphorePermit> {
+        if let Ok(permit) = Arc::clone(&self.workers).try_acquire_owned() {
+            return Ok(permit);
+        }
+        let queued = self.queued.fetch_add(1, Ordering::SeqCst);
+        let _guard = QueueGuard(Arc::clone(&self.queued));
+        if queued >= self.queue_size {
+            bail!(SimulatorError::QueueFull(self.queue_size));
+        }
+        debug!("Simulation queued at position: '{}'", queued + 1);
+        let permit = Arc::clone(&self.workers).acquire_owned().await?;
+        Ok(permit)
+    }
+}
+
+struct QueueGuard(Arc<AtomicUsize>);
+
+impl Drop for QueueGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn is_queue_full(result: Result<OwnedSemaphorePermit>) -> bool {
+        matches!(result.err().and_then(|err| err.downcast::<SimulatorError>().ok()), Some(SimulatorError::QueueFull(1)))
+    }
+
+    #[tokio::test]
+    async fn test_free_worker_is_acquired() {
+        let pool = WorkerPool::new(2, 0);
+        let _first = pool.acquire().await.unwrap();
+        let _second = pool.acquire().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_is_rejected() {
+        let pool = Arc::new(WorkerPool::new(1, 1));
+        let permit = pool.acquire().await.unwrap();
+        let queued = tokio::spawn({
+            let pool = Arc::clone(&pool);
+            async move { pool.acquire().await.is_ok() }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(is_queue_full(pool.acquire().await));
+
+        drop(permit);
+        assert!(queued.await.unwrap());
+        assert_eq!(pool.queued.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_simulation_leaves_queue() {
+        let pool = WorkerPool::new(1, 1);
+        let _permit = pool.acquire().await.unwrap();
+        pool.queued.store(1, Ordering::SeqCst);
+        assert!(is_queue_full(pool.acquire().await));
+        assert_eq!(pool.queued.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,3 +1,8 @@
 pub const POST_RUN_SIMULATION: &str = "/api/v1/simulator/simulations";
 pub const GET_SIMULATIONS: &str = "/api/v1/simulator/simulations";
 pub const GET_SIMULATION: &str = "/api/v1/simulator/simulations/:id";
+pub const DELETE_SIMULATION: &str = "/api/v1/simulator/simulations/:id";
+pub const POST_DEBUG_SESSION: &str = "/api/v1/simulator/debug";
+pub const GET_DEBUG_SESSION: &str = "/api/v1/simulator/debug/:id";
+pub const POST_DEBUG_STEP: &str = "/api/v1/simulator/debug/:id/step";
+pub const DELETE_DEBUG_SESSION: &str = "/api/v1/simulator/debug/:id";
@@ -1,21 +1,45 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use uuid::Uuid;
 
-use domain_model::{CreateSimulation, Order, SimulationDeployment, SimulationPosition};
+use domain_model::{Action, Allocation, CreateSimulation, Order, PluginId, SimulationDeployment, SimulationPosition, Tick};
 
 #[async_trait]
 pub trait SimulatorApi: Send + Sync + 'static {
     async fn run_simulation(&self, simulation: CreateSimulation) -> Result<SimulationReport>;
     async fn get_simulation_report(&self, id: Uuid) -> Result<SimulationReport>;
     async fn get_simulation_reports(&self) -> Result<Vec<SimulationReport>>;
+    async fn delete_simulation(&self, id: Uuid) -> Result<()>;
+    async fn start_debug_session(&self, simulation: CreateSimulation) -> Result<DebugSnapshot>;
+    async fn step_debug_session(&self, id: Uuid, step: DebugStep) -> Result<DebugSnapshot>;
+    async fn get_debug_session(&self, id: Uuid) -> Result<DebugSnapshot>;
+    async fn finish_debug_session(&self, id: Uuid) -> Result<SimulationReport>;
+}
+
+/// Errors caused by the request or by the simulator load, other errors are internal
+#[derive(Error, Debug)]
+pub enum SimulatorError {
+    #[error("Simulation queue is full, max size: '{0}'")]
+    QueueFull(usize),
+    #[error("Invalid simulation: {0}")]
+    InvalidSimulation(String),
+    #[error("Simulation report not found")]
+    SimulationNotFound,
+    #[error("Debug session not found")]
+    DebugSessionNotFound,
+    #[error("Debug session is busy with a step")]
+    DebugSessionBusy,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct SimulationReport {
     pub simulation_id: Uuid,
+    pub timestamp: DateTime<Utc>,
     pub start: DateTime<Utc>,
     pub end: DateTime<Utc>,
     pub deployments: Vec<SimulationDeployment>,
@@ -33,4 +57,76 @@ pub struct SimulationReport {
     pub tp_percent: f64,
     pub max_sl_streak: u64,
     pub max_tp_streak: u64,
+
+    pub deployment_reports: Vec<DeploymentReport>,
+}
+
+/// Results of a single deployment sub-ledger, fields of [`SimulationReport`] cover the whole portfolio
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DeploymentReport {
+    pub deployment_id: Uuid,
+    pub plugin_id: PluginId,
+    pub allocation: Allocation,
+    // share of portfolio capital at the end of simulation
+    pub share: f64,
+    pub actions: u32,
+    pub profit: f64,
+    pub profit_clear: f64,
+    pub fees: f64,
+    pub assets: Vec<SimulationPosition>,
+    pub sl_count: u64,
+    pub tp_count: u64,
+}
+
+/// Stop conditions of a debug session step, the session pauses on the first met one.
+/// A step without any condition processes a single tick.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct DebugStep {
+    pub ticks: Option<u32>,
+    pub until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub on_order: bool,
+    #[serde(default)]
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum Breakpoint {
+    PriceBelow(f64),
+    PriceAbove(f64),
+}
+
+impl Breakpoint {
+    pub fn is_hit(&self, tick: &Tick) -> bool {
+        match self {
+            Breakpoint::PriceBelow(price) => tick.price < *price,
+            Breakpoint::PriceAbove(price) => tick.price > *price,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum StopReason {
+    Start,
+    Ticks,
+    Timestamp,
+    Order,
+    Breakpoint(Breakpoint),
+    End,
+}
+
+/// State of a paused debug session
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DebugSnapshot {
+    pub simulation_id: Uuid,
+    pub reason: StopReason,
+    // last processed tick
+    pub tick: Option<Tick>,
+    pub ticks: u32,
+    // actions returned by deployments for the last processed tick
+    pub actions: Vec<Action>,
+    // plugin states by deployment id
+    pub states: HashMap<Uuid, String>,
+    pub active_orders: Vec<Order>,
+    pub assets: Vec<SimulationPosition>,
 }
@@ -1,5 +1,10 @@
+pub use api::Breakpoint;
+pub use api::DebugSnapshot;
+pub use api::DebugStep;
+pub use api::DeploymentReport;
 pub use api::SimulationReport;
 pub use api::SimulatorApi;
+pub use api::SimulatorError;
+pub use api::StopReason;
 
 mod api;
-
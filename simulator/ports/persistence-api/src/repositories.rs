@@ -9,4 +9,6 @@ pub trait SimulationReportRepository: Send + Sync + 'static {
     async fn save(&self, simulation_report: SimulationReport) -> Result<()>;
 
     async fn get(&self, id: Option<Uuid>) -> Vec<SimulationReport>;
+
+    async fn delete(&self, id: Uuid) -> Result<()>;
 }
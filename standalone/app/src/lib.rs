@@ -72,7 +72,7 @@ async fn run_storage(_db: Arc<PgEmbed>) {
 
 #[tokio::main]
 async fn run_simulator() {
-    simulator_app::run().await;
+    simulator_app::run_embedded().await;
 }
 
 #[tokio::main]
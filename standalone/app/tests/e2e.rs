@@ -7,7 +7,7 @@ use tracing_subscriber::EnvFilter;
 use tracing_subscriber::fmt::SubscriberBuilder;
 
 use domain_model::{
-    CreateSimulation, CreateSimulationDeployment, CreateSimulationPosition, Currency, CurrencyPair,
+    Allocation, CreateSimulation, CreateSimulationDeployment, CreateSimulationPosition, Currency, CurrencyPair,
     Exchange, InstrumentId, MarketType, PluginId, Side, Timeframe,
 };
 use simulator_core_api::SimulatorApi;
@@ -132,6 +132,7 @@ async fn test_e2e_simulation() {
         timeframe: Timeframe::FiveM,
         plugin_id,
         params: HashMap::from([("test-parameter".to_string(), "test-value".to_string())]),
+        allocation: Allocation::default(),
     };
 
     let new_simulation = CreateSimulation {
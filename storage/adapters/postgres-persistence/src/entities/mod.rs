@@ -2,8 +2,10 @@
 
 pub mod prelude;
 
+pub mod action_record;
 pub mod candle;
 pub mod line;
 pub mod order;
 pub mod point;
 pub mod position;
+pub mod state_snapshot;
@@ -1,8 +1,10 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.3
 
+pub use super::action_record::Entity as ActionRecord;
 pub use super::candle::Entity as Candle;
 pub use super::line::Entity as Line;
 pub use super::order::Entity as Order;
 pub use super::point::Entity as Point;
 pub use super::position::Entity as Position;
+pub use super::state_snapshot::Entity as StateSnapshot;
 
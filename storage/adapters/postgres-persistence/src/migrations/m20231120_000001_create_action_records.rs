@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ActionRecord::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ActionRecord::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(ActionRecord::DeploymentId).uuid().not_null())
+                    .col(ColumnDef::new(ActionRecord::PluginName).string().not_null())
+                    .col(ColumnDef::new(ActionRecord::PluginVersion).big_integer().not_null())
+                    .col(ColumnDef::new(ActionRecord::Timestamp).timestamp().not_null())
+                    .col(ColumnDef::new(ActionRecord::Trigger).json().not_null())
+                    .col(ColumnDef::new(ActionRecord::Action).json().not_null())
+                    .col(ColumnDef::new(ActionRecord::Risk).json().not_null())
+                    .col(ColumnDef::new(ActionRecord::Result).json())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_action_record_deployment_timestamp")
+                    .table(ActionRecord::Table)
+                    .col(ActionRecord::DeploymentId)
+                    .col(ActionRecord::Timestamp)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ActionRecord::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum ActionRecord {
+    Table,
+    Id,
+    DeploymentId,
+    PluginName,
+    PluginVersion,
+    Timestamp,
+    Trigger,
+    Action,
+    Risk,
+    Result,
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StateSnapshot::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(StateSnapshot::StateId).string().not_null())
+                    .col(ColumnDef::new(StateSnapshot::Version).big_integer().not_null())
+                    .col(ColumnDef::new(StateSnapshot::Timestamp).timestamp().not_null())
+                    .col(ColumnDef::new(StateSnapshot::State).text().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(StateSnapshot::StateId)
+                            .col(StateSnapshot::Version),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StateSnapshot::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum StateSnapshot {
+    Table,
+    StateId,
+    Version,
+    Timestamp,
+    State,
+}
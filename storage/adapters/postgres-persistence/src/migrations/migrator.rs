@@ -1,12 +1,16 @@
 use sea_orm_migration::{MigrationTrait, MigratorTrait};
 
-use crate::migrations::m20220101_000001_create_tables;
+use crate::migrations::{m20220101_000001_create_tables, m20231120_000001_create_action_records, m20231201_000001_create_state_snapshots};
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_tables::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_tables::Migration),
+            Box::new(m20231120_000001_create_action_records::Migration),
+            Box::new(m20231201_000001_create_state_snapshots::Migration),
+        ]
     }
 }
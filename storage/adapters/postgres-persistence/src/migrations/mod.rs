@@ -1,6 +1,8 @@
 pub use migrator::Migrator;
 
 mod m20220101_000001_create_tables;
+mod m20231120_000001_create_action_records;
+mod m20231201_000001_create_state_snapshots;
 
 mod migrator;
 
@@ -0,0 +1,79 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::{ActiveValue, ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryOrder};
+use sea_orm::QueryFilter;
+use serde_json::json;
+use uuid::Uuid;
+
+use domain_model::PluginId;
+use storage_persistence_api::ActionRecordRepository;
+
+use crate::entities::action_record;
+use crate::entities::prelude::ActionRecord;
+
+pub struct ActionRecordPostgresRepository<T: ConnectionTrait> {
+    db: Arc<T>,
+}
+
+impl<T: ConnectionTrait> ActionRecordPostgresRepository<T> {
+    pub fn new(db: Arc<T>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl<T: ConnectionTrait + Send + 'static> ActionRecordRepository for ActionRecordPostgresRepository<T> {
+    async fn save(&self, record: domain_model::audit::ActionRecord) -> Result<()> {
+        let record = action_record::ActiveModel {
+            id: ActiveValue::Set(record.id),
+            deployment_id: ActiveValue::Set(record.deployment_id),
+            plugin_name: ActiveValue::Set(record.plugin_id.name),
+            plugin_version: ActiveValue::Set(record.plugin_id.version),
+            timestamp: ActiveValue::Set(record.timestamp.naive_utc()),
+            trigger: ActiveValue::Set(json!(record.trigger)),
+            action: ActiveValue::Set(json!(record.action)),
+            risk: ActiveValue::Set(json!(record.risk)),
+            result: ActiveValue::Set(record.result.map(|result| json!(result))),
+        };
+        ActionRecord::insert(record).exec(self.db.deref()).await?;
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        deployment_id: Uuid,
+        from_timestamp: Option<DateTime<Utc>>,
+        to_timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Vec<domain_model::audit::ActionRecord>> {
+        let mut condition = Condition::all()
+            .add(action_record::Column::DeploymentId.eq(deployment_id));
+        if let Some(from_timestamp) = from_timestamp {
+            condition = condition.add(action_record::Column::Timestamp.gte(from_timestamp));
+        }
+        if let Some(to_timestamp) = to_timestamp {
+            condition = condition.add(action_record::Column::Timestamp.lte(to_timestamp));
+        }
+        let result = action_record::Entity::find()
+            .filter(condition)
+            .order_by_asc(action_record::Column::Timestamp)
+            .all(self.db.deref())
+            .await?
+            .into_iter()
+            .map(|model| domain_model::audit::ActionRecord {
+                id: model.id,
+                deployment_id: model.deployment_id,
+                plugin_id: PluginId::new(&model.plugin_name, model.plugin_version),
+                timestamp: model.timestamp.and_utc(),
+                trigger: serde_json::from_value(model.trigger).unwrap(),
+                action: serde_json::from_value(model.action).unwrap(),
+                risk: serde_json::from_value(model.risk).unwrap(),
+                result: model.result.map(|result| serde_json::from_value(result).unwrap()),
+            })
+            .collect();
+        Ok(result)
+    }
+}
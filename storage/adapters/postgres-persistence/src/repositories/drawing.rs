@@ -133,4 +133,20 @@ impl<T: ConnectionTrait + Send + 'static> DrawingRepository for DrawingPostgresR
             .collect();
         Ok(result)
     }
+
+    async fn delete_points(&self, deployment_ids: &[Uuid]) -> Result<u64> {
+        let result = point::Entity::delete_many()
+            .filter(point::Column::DeploymentId.is_in(deployment_ids.iter().cloned()))
+            .exec(self.db.deref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn delete_lines(&self, deployment_ids: &[Uuid]) -> Result<u64> {
+        let result = line::Entity::delete_many()
+            .filter(line::Column::DeploymentId.is_in(deployment_ids.iter().cloned()))
+            .exec(self.db.deref())
+            .await?;
+        Ok(result.rows_affected)
+    }
 }
@@ -1,9 +1,13 @@
+pub use action_record::ActionRecordPostgresRepository;
 pub use candle::CandlePostgresRepository;
 pub use drawing::DrawingPostgresRepository;
 pub use order::OrderPostgresRepository;
 pub use position::PositionPostgresRepository;
+pub use state_snapshot::StateSnapshotPostgresRepository;
 
+mod action_record;
 mod candle;
 mod drawing;
 mod order;
 mod position;
+mod state_snapshot;
@@ -142,4 +142,12 @@ impl<T: ConnectionTrait + Send + 'static> OrderRepository for OrderPostgresRepos
             .collect();
         Ok(result)
     }
+
+    async fn delete_by_simulation(&self, simulation_id: Uuid) -> Result<u64> {
+        let result = order::Entity::delete_many()
+            .filter(order::Column::SimulationId.eq(simulation_id))
+            .exec(self.db.deref())
+            .await?;
+        Ok(result.rows_affected)
+    }
 }
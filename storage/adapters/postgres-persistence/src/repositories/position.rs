@@ -7,6 +7,7 @@ use async_trait::async_trait;
 use sea_orm::{ActiveValue, Condition, ConnectionTrait, EntityTrait, sea_query};
 use sea_orm::ColumnTrait;
 use sea_orm::QueryFilter;
+use uuid::Uuid;
 
 use storage_persistence_api::PositionRepository;
 
@@ -86,4 +87,12 @@ impl<T: ConnectionTrait + Send + 'static> PositionRepository for PositionPostgre
             }).collect();
         Ok(result)
     }
+
+    async fn delete_by_simulation(&self, simulation_id: Uuid) -> Result<u64> {
+        let result = position::Entity::delete_many()
+            .filter(position::Column::SimulationId.eq(simulation_id))
+            .exec(self.db.deref())
+            .await?;
+        Ok(result.rows_affected)
+    }
 }
@@ -0,0 +1,70 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use sea_orm::{ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryOrder};
+use sea_orm::QueryFilter;
+
+use storage_persistence_api::StateSnapshotRepository;
+
+use crate::entities::state_snapshot;
+use crate::entities::prelude::StateSnapshot;
+
+pub struct StateSnapshotPostgresRepository<T: ConnectionTrait> {
+    db: Arc<T>,
+}
+
+impl<T: ConnectionTrait> StateSnapshotPostgresRepository<T> {
+    pub fn new(db: Arc<T>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl<T: ConnectionTrait + Send + 'static> StateSnapshotRepository for StateSnapshotPostgresRepository<T> {
+    async fn save(&self, snapshot: domain_model::state::StateSnapshot) -> Result<()> {
+        let snapshot = state_snapshot::ActiveModel {
+            state_id: ActiveValue::Set(snapshot.state_id),
+            version: ActiveValue::Set(snapshot.version),
+            timestamp: ActiveValue::Set(snapshot.timestamp.naive_utc()),
+            state: ActiveValue::Set(snapshot.state),
+        };
+        StateSnapshot::insert(snapshot).exec(self.db.deref()).await?;
+        Ok(())
+    }
+
+    async fn get(&self, state_id: &str) -> Result<Vec<domain_model::state::StateSnapshot>> {
+        let result = state_snapshot::Entity::find()
+            .filter(state_snapshot::Column::StateId.eq(state_id))
+            .order_by_asc(state_snapshot::Column::Version)
+            .all(self.db.deref())
+            .await?
+            .into_iter()
+            .map(|model| domain_model::state::StateSnapshot {
+                state_id: model.state_id,
+                version: model.version,
+                timestamp: model.timestamp.and_utc(),
+                state: model.state,
+            })
+            .collect();
+        Ok(result)
+    }
+
+    async fn delete_before(&self, state_id: &str, version: i64) -> Result<u64> {
+        let result = state_snapshot::Entity::delete_many()
+            .filter(state_snapshot::Column::StateId.eq(state_id))
+            .filter(state_snapshot::Column::Version.lt(version))
+            .exec(self.db.deref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn delete(&self, state_id: &str) -> Result<u64> {
+        let result = state_snapshot::Entity::delete_many()
+            .filter(state_snapshot::Column::StateId.eq(state_id))
+            .exec(self.db.deref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
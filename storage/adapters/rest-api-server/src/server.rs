@@ -3,18 +3,22 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use axum::{Json, Router};
-use axum::extract::{Query, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post};
 use chrono::{TimeZone, Utc};
 use tracing::error;
+use uuid::Uuid;
 
 use domain_model::{Candle, CurrencyPair, InstrumentId, LP, Order, Position, Timeframe};
+use domain_model::audit::ActionRecord;
 use domain_model::drawing::{Line, Point};
-use storage_core_api::{StorageApi, SyncReport};
-use storage_rest_api::endpoints::{GET_CANDLES, GET_LINES, GET_ORDERS, GET_POINTS, GET_POSITIONS, POST_CANDLES, POST_LINE, POST_LP, POST_ORDERS, POST_POINT, POST_POSITIONS, POST_SYNC};
+use domain_model::state::StateSnapshot;
+use storage_core_api::{CleanupReport, StorageApi, SyncReport};
+use storage_rest_api::endpoints::{DELETE_SIMULATION, DELETE_STATE_SNAPSHOTS, GET_ACTION_RECORDS, GET_CANDLES, GET_LINES, GET_ORDERS, GET_POINTS, GET_POSITIONS, GET_STATE_SNAPSHOTS, POST_CANDLES, POST_ACTION_RECORD, POST_LINE, POST_LP, POST_ORDERS, POST_POINT, POST_POSITIONS, POST_STATE_SNAPSHOT, POST_SYNC};
 use storage_rest_api::path_queries::{
-    CandlesQuery, CandleSyncQuery, DrawingQuery, OrdersQuery, PositionsQuery,
+    ActionRecordsQuery, CandlesQuery, CandleSyncQuery, DrawingQuery, OrdersQuery, PositionsQuery,
+    StateHistoryQuery, StateSnapshotsQuery,
 };
 
 pub async fn run(port: u16, storage: impl StorageApi) {
@@ -32,6 +36,13 @@ pub async fn run(port: u16, storage: impl StorageApi) {
         .route(POST_POINT, post(create_point))
         .route(GET_LINES, get(get_lines))
         .route(POST_LINE, post(create_line))
+        .route(DELETE_SIMULATION, delete(delete_simulation))
+        .route(GET_ACTION_RECORDS, get(get_action_records))
+        .route(POST_ACTION_RECORD, post(create_action_record))
+        .route(GET_STATE_SNAPSHOTS, get(get_state_snapshots))
+        .route(POST_STATE_SNAPSHOT, post(create_state_snapshot))
+        .route(DELETE_STATE_SNAPSHOTS, delete(delete_state_snapshots))
+        .route(metrics::GET_METRICS, get(metrics::handler))
         .with_state(storage);
 
     let address = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), port);
@@ -194,3 +205,69 @@ async fn get_lines(
 async fn create_line(State(storage): State<Arc<dyn StorageApi>>, Json(line): Json<Line>) {
     storage.save_line(line).await.unwrap();
 }
+
+async fn delete_simulation(
+    State(storage): State<Arc<dyn StorageApi>>,
+    Path(simulation_id): Path<Uuid>,
+    Json(deployment_ids): Json<Vec<Uuid>>,
+) -> Result<Json<CleanupReport>, StatusCode> {
+    let result = storage
+        .delete_simulation(simulation_id, &deployment_ids)
+        .await
+        .map_err(|err| {
+            error!("Error deleting simulation: '{simulation_id}': {err}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(result))
+}
+
+async fn get_action_records(
+    Query(query_params): Query<ActionRecordsQuery>,
+    State(storage): State<Arc<dyn StorageApi>>,
+) -> Json<Vec<ActionRecord>> {
+    let from = query_params
+        .from
+        .map(|millis| Utc.timestamp_millis_opt(millis).unwrap());
+    let to = query_params
+        .to
+        .map(|millis| Utc.timestamp_millis_opt(millis).unwrap());
+    let result = storage
+        .get_action_records(query_params.deployment_id, from, to)
+        .await
+        .unwrap();
+    Json(result)
+}
+
+async fn create_action_record(State(storage): State<Arc<dyn StorageApi>>, Json(record): Json<ActionRecord>) {
+    storage.save_action_record(record).await.unwrap();
+}
+
+async fn get_state_snapshots(
+    Query(query_params): Query<StateSnapshotsQuery>,
+    State(storage): State<Arc<dyn StorageApi>>,
+) -> Json<Vec<StateSnapshot>> {
+    let result = storage
+        .get_state_snapshots(&query_params.state_id)
+        .await
+        .unwrap();
+    Json(result)
+}
+
+async fn create_state_snapshot(
+    Query(query_params): Query<StateHistoryQuery>,
+    State(storage): State<Arc<dyn StorageApi>>,
+    Json(snapshot): Json<StateSnapshot>,
+) {
+    storage.save_state_snapshot(snapshot, query_params.history).await.unwrap();
+}
+
+async fn delete_state_snapshots(
+    Query(query_params): Query<StateSnapshotsQuery>,
+    State(storage): State<Arc<dyn StorageApi>>,
+) -> Json<u64> {
+    let result = storage
+        .delete_state_snapshots(&query_params.state_id)
+        .await
+        .unwrap();
+    Json(result)
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use reqwest::Url;
@@ -8,11 +8,14 @@ use tracing::trace;
 use uuid::Uuid;
 
 use domain_model::{Candle, Currency, Exchange, InstrumentId, LP, MarketType, Order, OrderStatus, OrderType, Position, Side, Timeframe};
+use domain_model::audit::ActionRecord;
 use domain_model::drawing::{Line, Point};
-use storage_core_api::{StorageApi, SyncReport};
-use storage_rest_api::endpoints::{GET_CANDLES, GET_LINES, GET_ORDERS, GET_POINTS, GET_POSITIONS, POST_CANDLES, POST_LINE, POST_LP, POST_ORDERS, POST_POINT, POST_POSITIONS, POST_SYNC};
+use domain_model::state::StateSnapshot;
+use storage_core_api::{CleanupReport, StorageApi, SyncReport};
+use storage_rest_api::endpoints::{DELETE_SIMULATION, DELETE_STATE_SNAPSHOTS, GET_ACTION_RECORDS, GET_CANDLES, GET_LINES, GET_ORDERS, GET_POINTS, GET_POSITIONS, GET_STATE_SNAPSHOTS, POST_CANDLES, POST_ACTION_RECORD, POST_LINE, POST_LP, POST_ORDERS, POST_POINT, POST_POSITIONS, POST_STATE_SNAPSHOT, POST_SYNC};
 use storage_rest_api::path_queries::{
-    CandlesQuery, CandleSyncQuery, DrawingQuery, OrdersQuery, PositionsQuery,
+    ActionRecordsQuery, CandlesQuery, CandleSyncQuery, DrawingQuery, OrdersQuery, PositionsQuery,
+    StateHistoryQuery, StateSnapshotsQuery,
 };
 
 pub struct StorageRestClient {
@@ -294,4 +297,123 @@ impl StorageApi for StorageRestClient {
             .unwrap();
         Ok(result)
     }
+
+    async fn delete_simulation(
+        &self,
+        simulation_id: Uuid,
+        deployment_ids: &[Uuid],
+    ) -> Result<CleanupReport> {
+        let endpoint = format!("{}{}", self.url, DELETE_SIMULATION)
+            .replace(":id", &simulation_id.to_string());
+        trace!("Request: DELETE '{endpoint}'");
+        let mut response = self
+            .client
+            .delete(endpoint)
+            .body_json(&deployment_ids)
+            .map_err(|err| anyhow!("Deployment ids serialization failed: {err}"))?
+            .await
+            .map_err(|err| anyhow!("Simulation deleting request failed: {err}"))?;
+        if !response.status().is_success() {
+            bail!("Simulation deleting failed with status: '{}'", response.status());
+        }
+        response
+            .body_json()
+            .await
+            .map_err(|err| anyhow!("Cleanup report parsing failed: {err}"))
+    }
+
+    async fn save_action_record(&self, record: ActionRecord) -> Result<()> {
+        let endpoint = format!("{}{}", self.url, POST_ACTION_RECORD);
+        let endpoint = Url::parse(&endpoint)?;
+        trace!("Request: POST '{endpoint}'");
+        self.client
+            .post(endpoint)
+            .body_json(&record)
+            .unwrap()
+            .await
+            .unwrap();
+        Ok(())
+    }
+
+    async fn get_action_records(
+        &self,
+        deployment_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ActionRecord>> {
+        let query = ActionRecordsQuery {
+            deployment_id,
+            from: from.map(|timestamp| timestamp.timestamp_millis()),
+            to: to.map(|timestamp| timestamp.timestamp_millis()),
+        };
+        let endpoint = format!("{}{}", self.url, GET_ACTION_RECORDS);
+        let mut endpoint = Url::parse(&endpoint)?;
+        endpoint.set_query(Some(&to_string(&query)?));
+        trace!("Request: GET '{endpoint}'");
+        let result = self
+            .client
+            .get(endpoint)
+            .await
+            .unwrap()
+            .body_json()
+            .await
+            .unwrap();
+        Ok(result)
+    }
+
+    async fn save_state_snapshot(&self, snapshot: StateSnapshot, history: u64) -> Result<()> {
+        let query = StateHistoryQuery { history };
+        let endpoint = format!("{}{}", self.url, POST_STATE_SNAPSHOT);
+        let mut endpoint = Url::parse(&endpoint)?;
+        endpoint.set_query(Some(&to_string(&query)?));
+        trace!("Request: POST '{endpoint}'");
+        let response = self.client
+            .post(endpoint)
+            .body_json(&snapshot)
+            .unwrap()
+            .await
+            .unwrap();
+        if !response.status().is_success() {
+            bail!("State snapshot saving failed with status: '{}'", response.status());
+        }
+        Ok(())
+    }
+
+    async fn get_state_snapshots(&self, state_id: &str) -> Result<Vec<StateSnapshot>> {
+        let query = StateSnapshotsQuery {
+            state_id: state_id.to_string(),
+        };
+        let endpoint = format!("{}{}", self.url, GET_STATE_SNAPSHOTS);
+        let mut endpoint = Url::parse(&endpoint)?;
+        endpoint.set_query(Some(&to_string(&query)?));
+        trace!("Request: GET '{endpoint}'");
+        let result = self
+            .client
+            .get(endpoint)
+            .await
+            .unwrap()
+            .body_json()
+            .await
+            .unwrap();
+        Ok(result)
+    }
+
+    async fn delete_state_snapshots(&self, state_id: &str) -> Result<u64> {
+        let query = StateSnapshotsQuery {
+            state_id: state_id.to_string(),
+        };
+        let endpoint = format!("{}{}", self.url, DELETE_STATE_SNAPSHOTS);
+        let mut endpoint = Url::parse(&endpoint)?;
+        endpoint.set_query(Some(&to_string(&query)?));
+        trace!("Request: DELETE '{endpoint}'");
+        let result = self
+            .client
+            .delete(endpoint)
+            .await
+            .unwrap()
+            .body_json()
+            .await
+            .unwrap();
+        Ok(result)
+    }
 }
@@ -7,8 +7,8 @@ use storage_config::CONFIG;
 use storage_core::Storage;
 use storage_postgres_persistence::initiator::init_db;
 use storage_postgres_persistence::repositories::{
-    CandlePostgresRepository, DrawingPostgresRepository, OrderPostgresRepository,
-    PositionPostgresRepository,
+    ActionRecordPostgresRepository, CandlePostgresRepository, DrawingPostgresRepository, OrderPostgresRepository,
+    PositionPostgresRepository, StateSnapshotPostgresRepository,
 };
 
 pub async fn run() {
@@ -20,6 +20,8 @@ pub async fn run() {
     let position_repository = PositionPostgresRepository::new(Arc::clone(&db));
     let candle_repository = CandlePostgresRepository::new(Arc::clone(&db));
     let drawing_repository = DrawingPostgresRepository::new(Arc::clone(&db));
+    let action_record_repository = ActionRecordPostgresRepository::new(Arc::clone(&db));
+    let state_snapshot_repository = StateSnapshotPostgresRepository::new(Arc::clone(&db));
 
     let storage = Storage::new(
         interactor_client,
@@ -27,6 +29,8 @@ pub async fn run() {
         position_repository,
         candle_repository,
         drawing_repository,
+        action_record_repository,
+        state_snapshot_repository,
     );
     storage_rest_api_server::run(CONFIG.application.port, storage).await;
 }
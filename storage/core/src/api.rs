@@ -3,21 +3,27 @@ use std::sync::Arc;
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use tracing::info;
 use uuid::Uuid;
 
 use domain_model::{Candle, Currency, Exchange, InstrumentId, LP, MarketType, Order, OrderStatus, OrderType, Position, Side, Timeframe};
+use domain_model::audit::ActionRecord;
 use domain_model::drawing::{Line, Point};
+use domain_model::state::StateSnapshot;
 use interactor_core_api::InteractorApi;
-use storage_core_api::{StorageApi, SyncReport};
+use storage_core_api::{CleanupReport, StorageApi, SyncReport};
 use storage_persistence_api::{
-    CandleRepository, DrawingRepository, OrderRepository, PositionRepository,
+    ActionRecordRepository, CandleRepository, DrawingRepository, OrderRepository, PositionRepository,
+    StateSnapshotRepository,
 };
 
+use crate::services::action_record::ActionRecordService;
 use crate::services::candle::CandleService;
 use crate::services::candle_sync::CandleSyncService;
 use crate::services::drawing::DrawingService;
 use crate::services::order::OrderService;
 use crate::services::position::PositionService;
+use crate::services::state_snapshot::StateSnapshotService;
 
 pub struct Storage<
     I: InteractorApi,
@@ -25,12 +31,16 @@ pub struct Storage<
     P: PositionRepository,
     C: CandleRepository,
     D: DrawingRepository,
+    A: ActionRecordRepository,
+    S: StateSnapshotRepository,
 > {
     order_service: OrderService<O, I>,
     position_service: PositionService<P>,
     candle_service: Arc<CandleService<C>>,
     candle_sync_service: CandleSyncService<I, C>,
     drawing_service: DrawingService<D>,
+    action_record_service: ActionRecordService<A>,
+    state_snapshot_service: StateSnapshotService<S>,
 }
 
 impl<
@@ -39,7 +49,9 @@ impl<
     P: PositionRepository,
     C: CandleRepository,
     D: DrawingRepository,
-> Storage<I, O, P, C, D>
+    A: ActionRecordRepository,
+    S: StateSnapshotRepository,
+> Storage<I, O, P, C, D, A, S>
 {
     pub fn new(
         interactor_client: I,
@@ -47,6 +59,8 @@ impl<
         position_repository: P,
         candle_repository: C,
         drawing_repository: D,
+        action_record_repository: A,
+        state_snapshot_repository: S,
     ) -> Self {
         let interactor_client = Arc::new(interactor_client);
         let order_service = OrderService::new(order_repository, Arc::clone(&interactor_client));
@@ -55,18 +69,22 @@ impl<
         let candle_sync_service =
             CandleSyncService::new(Arc::clone(&candle_service), interactor_client);
         let drawing_service = DrawingService::new(drawing_repository);
+        let action_record_service = ActionRecordService::new(action_record_repository);
+        let state_snapshot_service = StateSnapshotService::new(state_snapshot_repository);
         Self {
             order_service,
             position_service,
             candle_service,
             candle_sync_service,
             drawing_service,
+            action_record_service,
+            state_snapshot_service,
         }
     }
 }
 
 #[async_trait]
-impl<I: InteractorApi, O: OrderRepository, P: PositionRepository, C: CandleRepository, D: DrawingRepository> StorageApi for Storage<I, O, P, C, D>
+impl<I: InteractorApi, O: OrderRepository, P: PositionRepository, C: CandleRepository, D: DrawingRepository, A: ActionRecordRepository, S: StateSnapshotRepository> StorageApi for Storage<I, O, P, C, D, A, S>
 {
     async fn save_order(&self, order: Order) -> Result<()> {
         self.order_service.save(order).await;
@@ -187,4 +205,52 @@ impl<I: InteractorApi, O: OrderRepository, P: PositionRepository, C: CandleRepos
             .await;
         Ok(lines)
     }
+
+    async fn delete_simulation(
+        &self,
+        simulation_id: Uuid,
+        deployment_ids: &[Uuid],
+    ) -> Result<CleanupReport> {
+        let report = CleanupReport {
+            orders: self.order_service.delete_by_simulation(simulation_id).await?,
+            positions: self.position_service.delete_by_simulation(simulation_id).await?,
+            points: self.drawing_service.delete_points(deployment_ids).await?,
+            lines: self.drawing_service.delete_lines(deployment_ids).await?,
+        };
+        info!("Simulation: '{simulation_id}' artifacts deleted: '{report:?}'");
+        Ok(report)
+    }
+
+    async fn save_action_record(&self, record: ActionRecord) -> Result<()> {
+        self.action_record_service.save(record).await;
+        Ok(())
+    }
+
+    async fn get_action_records(
+        &self,
+        deployment_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ActionRecord>> {
+        let records = self
+            .action_record_service
+            .get(deployment_id, from, to)
+            .await;
+        Ok(records)
+    }
+
+    async fn save_state_snapshot(&self, snapshot: StateSnapshot, history: u64) -> Result<()> {
+        self.state_snapshot_service.save(snapshot, history).await;
+        Ok(())
+    }
+
+    async fn get_state_snapshots(&self, state_id: &str) -> Result<Vec<StateSnapshot>> {
+        let snapshots = self.state_snapshot_service.get(state_id).await;
+        Ok(snapshots)
+    }
+
+    async fn delete_state_snapshots(&self, state_id: &str) -> Result<u64> {
+        let deleted = self.state_snapshot_service.delete(state_id).await;
+        Ok(deleted)
+    }
 }
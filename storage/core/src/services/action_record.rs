@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use domain_model::audit::ActionRecord;
+use storage_persistence_api::ActionRecordRepository;
+
+pub struct ActionRecordService<R: ActionRecordRepository> {
+    repository: R,
+}
+
+impl<R: ActionRecordRepository> ActionRecordService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub async fn save(&self, record: ActionRecord) {
+        self.repository
+            .save(record)
+            .await
+            .expect("Error during action record saving");
+    }
+
+    pub async fn get(
+        &self,
+        deployment_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Vec<ActionRecord> {
+        self.repository
+            .get(deployment_id, from, to)
+            .await
+            .unwrap()
+    }
+}
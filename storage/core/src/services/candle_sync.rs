@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
 use anyhow::Result;
+use metrics::HistogramVec;
+use once_cell::sync::Lazy;
 use chrono::{DateTime, Duration, DurationRound, Utc};
 use tracing::{debug, error, info, trace, warn};
 
@@ -11,6 +13,10 @@ use storage_persistence_api::CandleRepository;
 
 use crate::services::candle::CandleService;
 
+static SYNC_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    metrics::histogram_vec("storage_sync_duration_seconds", "Duration of candles sync of a timeframe", &["timeframe"])
+});
+
 pub struct CandleSyncService<I: InteractorApi, R: CandleRepository> {
     candle_service: Arc<CandleService<R>>,
     interactor_client: Arc<I>,
@@ -39,7 +45,9 @@ impl<I: InteractorApi, R: CandleRepository> CandleSyncService<I, R> {
 
         let mut reports = Vec::new();
         for timeframe in timeframes {
+            let timer = SYNC_DURATION.with_label_values(&[&timeframe.to_string()]).start_timer();
             let report = self.sync_timeframe(instrument_id, timeframe, from, to).await?;
+            timer.observe_duration();
             reports.push(report);
         }
         info!("Finish candles sync: {reports:?}");
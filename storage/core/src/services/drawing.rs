@@ -1,3 +1,4 @@
+use anyhow::Result;
 use uuid::Uuid;
 
 use domain_model::drawing::{Line, Point};
@@ -44,4 +45,16 @@ impl<R: DrawingRepository> DrawingService<R> {
             .await
             .unwrap()
     }
+
+    pub async fn delete_points(&self, deployment_ids: &[Uuid]) -> Result<u64> {
+        self.repository
+            .delete_points(deployment_ids)
+            .await
+    }
+
+    pub async fn delete_lines(&self, deployment_ids: &[Uuid]) -> Result<u64> {
+        self.repository
+            .delete_lines(deployment_ids)
+            .await
+    }
 }
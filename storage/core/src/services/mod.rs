@@ -1,5 +1,7 @@
+pub mod action_record;
 pub mod candle;
 pub mod candle_sync;
 pub mod drawing;
 pub mod order;
 pub mod position;
+pub mod state_snapshot;
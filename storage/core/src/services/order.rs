@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use anyhow::Result;
 use tracing::trace;
 use uuid::Uuid;
 
@@ -118,4 +119,10 @@ impl<R: OrderRepository, I: InteractorApi> OrderService<R, I> {
             .await
             .unwrap()
     }
+
+    pub async fn delete_by_simulation(&self, simulation_id: Uuid) -> Result<u64> {
+        self.repository
+            .delete_by_simulation(simulation_id)
+            .await
+    }
 }
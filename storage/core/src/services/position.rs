@@ -1,3 +1,6 @@
+use anyhow::Result;
+use uuid::Uuid;
+
 use domain_model::{Currency, Exchange, Side};
 use storage_persistence_api::PositionRepository;
 
@@ -25,4 +28,10 @@ impl<R: PositionRepository> PositionService<R> {
     ) -> Vec<domain_model::Position> {
         self.repository.get(exchange, currency, side).await.unwrap()
     }
+
+    pub async fn delete_by_simulation(&self, simulation_id: Uuid) -> Result<u64> {
+        self.repository
+            .delete_by_simulation(simulation_id)
+            .await
+    }
 }
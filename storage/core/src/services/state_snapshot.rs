@@ -0,0 +1,45 @@
+use tracing::debug;
+
+use domain_model::state::StateSnapshot;
+use storage_persistence_api::StateSnapshotRepository;
+
+pub struct StateSnapshotService<R: StateSnapshotRepository> {
+    repository: R,
+}
+
+impl<R: StateSnapshotRepository> StateSnapshotService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    /// Saves the snapshot and keeps only the last `history` versions of its state, 0 keeps all versions
+    pub async fn save(&self, snapshot: StateSnapshot, history: u64) {
+        let state_id = snapshot.state_id.clone();
+        let version = snapshot.version;
+        self.repository
+            .save(snapshot)
+            .await
+            .expect("Error during state snapshot saving");
+        if history > 0 {
+            let deleted = self.repository
+                .delete_before(&state_id, version - history as i64 + 1)
+                .await
+                .unwrap();
+            debug!("State: '{state_id}' versions deleted: '{deleted}'");
+        }
+    }
+
+    pub async fn get(&self, state_id: &str) -> Vec<StateSnapshot> {
+        self.repository
+            .get(state_id)
+            .await
+            .unwrap()
+    }
+
+    pub async fn delete(&self, state_id: &str) -> u64 {
+        self.repository
+            .delete(state_id)
+            .await
+            .unwrap()
+    }
+}
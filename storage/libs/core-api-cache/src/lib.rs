@@ -8,8 +8,14 @@ use moka::future::Cache;
 use uuid::Uuid;
 
 use domain_model::{Candle, Currency, Exchange, InstrumentId, LP, MarketType, Order, OrderStatus, OrderType, Position, Side, Timeframe};
+use domain_model::audit::ActionRecord;
 use domain_model::drawing::{Line, Point};
-use storage_core_api::{StorageApi, SyncReport};
+use domain_model::state::StateSnapshot;
+use storage_core_api::{CleanupReport, StorageApi, SyncReport};
+
+pub use preloaded::StorageCoreApiPreloaded;
+
+mod preloaded;
 
 pub struct StorageCoreApiCache<S: StorageApi> {
     client: Arc<S>,
@@ -94,4 +100,28 @@ impl<S: StorageApi> StorageApi for StorageCoreApiCache<S> {
     async fn get_lines(&self, deployment_id: Uuid, instrument_id: &InstrumentId) -> Result<Vec<Line>> {
         self.client.get_lines(deployment_id, instrument_id).await
     }
+
+    async fn delete_simulation(&self, simulation_id: Uuid, deployment_ids: &[Uuid]) -> Result<CleanupReport> {
+        self.client.delete_simulation(simulation_id, deployment_ids).await
+    }
+
+    async fn save_action_record(&self, record: ActionRecord) -> Result<()> {
+        self.client.save_action_record(record).await
+    }
+
+    async fn get_action_records(&self, deployment_id: Uuid, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<ActionRecord>> {
+        self.client.get_action_records(deployment_id, from, to).await
+    }
+
+    async fn save_state_snapshot(&self, snapshot: StateSnapshot, history: u64) -> Result<()> {
+        self.client.save_state_snapshot(snapshot, history).await
+    }
+
+    async fn get_state_snapshots(&self, state_id: &str) -> Result<Vec<StateSnapshot>> {
+        self.client.get_state_snapshots(state_id).await
+    }
+
+    async fn delete_state_snapshots(&self, state_id: &str) -> Result<u64> {
+        self.client.delete_state_snapshots(state_id).await
+    }
 }
@@ -0,0 +1,438 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::RwLock;
+use tracing::debug;
+use uuid::Uuid;
+
+use domain_model::{Candle, Currency, Exchange, InstrumentId, LP, MarketType, Order, OrderStatus, OrderType, Position, Side, Timeframe};
+use domain_model::audit::ActionRecord;
+use domain_model::drawing::{Line, Point};
+use domain_model::state::StateSnapshot;
+use storage_core_api::{CleanupReport, StorageApi, SyncReport};
+
+/// Storage client for simulations: candles are loaded once per window ahead of the requested
+/// timestamp and served from memory, so moving forward in time does not hit storage on each tick.
+/// Concurrent simulations request different periods, so each instrument keeps up to `windows`
+/// windows, the least recently used one is replaced. A window reaches past the synced candles,
+/// so windows overlapping a synced period are dropped and loaded again.
+pub struct StorageCoreApiPreloaded<S: StorageApi> {
+    client: Arc<S>,
+    window: Duration,
+    windows: usize,
+    // the most recently used windows last
+    candles: RwLock<HashMap<String, VecDeque<CandlesWindow>>>,
+}
+
+struct CandlesWindow {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    // sorted by timestamp descending, same as storage responses
+    candles: Vec<Candle>,
+}
+
+impl CandlesWindow {
+    fn covers(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> bool {
+        from >= self.from && to <= self.to
+    }
+
+    fn select(&self, from: Option<DateTime<Utc>>, to: DateTime<Utc>, limit: Option<u64>) -> Vec<Candle> {
+        self.candles.iter()
+            .filter(|candle| candle.timestamp <= to)
+            .filter(|candle| from.map_or(true, |from| candle.timestamp >= from))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .cloned()
+            .collect()
+    }
+}
+
+impl<S: StorageApi> StorageCoreApiPreloaded<S> {
+    pub fn new(client: Arc<S>, window: Duration, windows: usize) -> Self {
+        Self {
+            client,
+            window,
+            windows: windows.max(1),
+            candles: Default::default(),
+        }
+    }
+
+    async fn get_cached(&self, key: &str, from: Option<DateTime<Utc>>, required_from: DateTime<Utc>,
+                        to: DateTime<Utc>, limit: Option<u64>) -> Option<Vec<Candle>> {
+        let mut candles = self.candles.write().await;
+        let windows = candles.get_mut(key)?;
+        let index = windows.iter().position(|window| window.covers(required_from, to))?;
+        let window = windows.remove(index)?;
+        let result = window.select(from, to, limit);
+        windows.push_back(window);
+        Some(result)
+    }
+
+    async fn load_window(&self, instrument_id: &InstrumentId, timeframe: Timeframe,
+                         from: DateTime<Utc>, to: DateTime<Utc>) -> Result<CandlesWindow> {
+        let to = to + self.window;
+        debug!("Preload candles for instrument: '{instrument_id:?}', timeframe: '{timeframe}', from: '{from}', to: '{to}'");
+        let candles = self.client
+            .get_candles(instrument_id, Some(timeframe), Some(from), Some(to), None)
+            .await?;
+        Ok(CandlesWindow {
+            from,
+            to,
+            candles,
+        })
+    }
+}
+
+fn window_key(instrument_id: &InstrumentId, timeframe: Timeframe) -> String {
+    format!("{instrument_id:?}-{timeframe:?}")
+}
+
+#[async_trait]
+impl<S: StorageApi> StorageApi for StorageCoreApiPreloaded<S> {
+    async fn save_order(&self, order: Order) -> Result<()> {
+        self.client.save_order(order).await
+    }
+
+    async fn save_lp(&self, lp: LP) -> Result<()> {
+        self.client.save_lp(lp).await
+    }
+
+    async fn get_orders(&self, id: Option<String>,
+                        simulation_id: Option<Uuid>,
+                        exchange: Option<Exchange>,
+                        market_type: Option<MarketType>,
+                        target: Option<Currency>,
+                        source: Option<Currency>,
+                        status: Option<OrderStatus>,
+                        side: Option<Side>,
+                        order_type: Option<OrderType>) -> Result<Vec<Order>> {
+        self.client.get_orders(id, simulation_id, exchange, market_type, target, source, status, side, order_type).await
+    }
+
+    async fn save_position(&self, position: Position) -> Result<()> {
+        self.client.save_position(position).await
+    }
+
+    async fn get_positions(&self, exchange: Option<Exchange>, currency: Option<Currency>, side: Option<Side>) -> Result<Vec<Position>> {
+        self.client.get_positions(exchange, currency, side).await
+    }
+
+    async fn save_candle(&self, candle: Candle) -> Result<()> {
+        self.client.save_candle(candle).await
+    }
+
+    async fn get_candles(&self, instrument_id: &InstrumentId, timeframe: Option<Timeframe>, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>, limit: Option<u64>) -> Result<Vec<Candle>> {
+        let (Some(timeframe), Some(to)) = (timeframe, to) else {
+            return self.client.get_candles(instrument_id, timeframe, from, to, limit).await;
+        };
+        if from.is_none() && limit.is_none() {
+            return self.client.get_candles(instrument_id, Some(timeframe), from, Some(to), limit).await;
+        }
+        let required_from = from.unwrap_or_else(|| {
+            to - Duration::seconds(timeframe.as_sec() * limit.unwrap_or_default() as i64)
+        });
+        let key = window_key(instrument_id, timeframe);
+        let candles = match self.get_cached(&key, from, required_from, to, limit).await {
+            Some(candles) => candles,
+            None => {
+                let window = self.load_window(instrument_id, timeframe, required_from, to).await?;
+                let candles = window.select(from, to, limit);
+                let mut cached = self.candles.write().await;
+                let windows = cached.entry(key).or_default();
+                if windows.len() >= self.windows {
+                    windows.pop_front();
+                }
+                windows.push_back(window);
+                candles
+            }
+        };
+        // the latest `limit` candles reach further back than `limit` timeframes when the history has gaps
+        if from.is_none() && (candles.len() as u64) < limit.unwrap_or_default() {
+            return self.client.get_candles(instrument_id, Some(timeframe), None, Some(to), limit).await;
+        }
+        Ok(candles)
+    }
+
+    async fn sync(&self, instrument_id: &InstrumentId, timeframes: &[Timeframe], from: DateTime<Utc>, to: Option<DateTime<Utc>>) -> Result<Vec<SyncReport>> {
+        let reports = self.client.sync(instrument_id, timeframes, from, to).await?;
+        let mut cached = self.candles.write().await;
+        for timeframe in timeframes {
+            if let Some(windows) = cached.get_mut(&window_key(instrument_id, *timeframe)) {
+                windows.retain(|window| window.to < from || to.is_some_and(|to| window.from > to));
+            }
+        }
+        Ok(reports)
+    }
+
+    async fn save_point(&self, point: Point) -> Result<()> {
+        self.client.save_point(point).await
+    }
+
+    async fn get_points(&self, deployment_id: Uuid, instrument_id: &InstrumentId) -> Result<Vec<Point>> {
+        self.client.get_points(deployment_id, instrument_id).await
+    }
+
+    async fn save_line(&self, line: Line) -> Result<()> {
+        self.client.save_line(line).await
+    }
+
+    async fn get_lines(&self, deployment_id: Uuid, instrument_id: &InstrumentId) -> Result<Vec<Line>> {
+        self.client.get_lines(deployment_id, instrument_id).await
+    }
+
+    async fn delete_simulation(&self, simulation_id: Uuid, deployment_ids: &[Uuid]) -> Result<CleanupReport> {
+        self.client.delete_simulation(simulation_id, deployment_ids).await
+    }
+
+    async fn save_action_record(&self, record: ActionRecord) -> Result<()> {
+        self.client.save_action_record(record).await
+    }
+
+    async fn get_action_records(&self, deployment_id: Uuid, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<ActionRecord>> {
+        self.client.get_action_records(deployment_id, from, to).await
+    }
+
+    async fn save_state_snapshot(&self, snapshot: StateSnapshot, history: u64) -> Result<()> {
+        self.client.save_state_snapshot(snapshot, history).await
+    }
+
+    async fn get_state_snapshots(&self, state_id: &str) -> Result<Vec<StateSnapshot>> {
+        self.client.get_state_snapshots(state_id).await
+    }
+
+    async fn delete_state_snapshots(&self, state_id: &str) -> Result<u64> {
+        self.client.delete_state_snapshots(state_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use chrono::TimeZone;
+
+    use domain_model::{CandleStatus, CurrencyPair};
+
+    use super::*;
+
+    /// Candles of the storage and the number of candle requests it served
+    #[derive(Default)]
+    struct TestStorage {
+        candles: Mutex<Vec<Candle>>,
+        requests: AtomicUsize,
+    }
+
+    impl TestStorage {
+        fn add_hours(&self, start: DateTime<Utc>, hours: i64) {
+            let mut candles = self.candles.lock().unwrap();
+            for hour in 0..hours {
+                candles.push(candle(start + Duration::hours(hour)));
+            }
+        }
+
+        fn requests(&self) -> usize {
+            self.requests.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl StorageApi for TestStorage {
+        async fn save_order(&self, _order: Order) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn save_lp(&self, _lp: LP) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn get_orders(&self, _id: Option<String>, _simulation_id: Option<Uuid>, _exchange: Option<Exchange>,
+                            _market_type: Option<MarketType>, _target: Option<Currency>, _source: Option<Currency>,
+                            _status: Option<OrderStatus>, _side: Option<Side>, _order_type: Option<OrderType>) -> Result<Vec<Order>> {
+            unimplemented!()
+        }
+
+        async fn save_position(&self, _position: Position) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn get_positions(&self, _exchange: Option<Exchange>, _currency: Option<Currency>, _side: Option<Side>) -> Result<Vec<Position>> {
+            unimplemented!()
+        }
+
+        async fn save_candle(&self, _candle: Candle) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn get_candles(&self, _instrument_id: &InstrumentId, _timeframe: Option<Timeframe>, from: Option<DateTime<Utc>>,
+                             to: Option<DateTime<Utc>>, limit: Option<u64>) -> Result<Vec<Candle>> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            let mut candles: Vec<_> = self.candles
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|candle| from.map_or(true, |from| candle.timestamp >= from))
+                .filter(|candle| to.map_or(true, |to| candle.timestamp <= to))
+                .cloned()
+                .collect();
+            candles.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+            candles.truncate(limit.map_or(usize::MAX, |limit| limit as usize));
+            Ok(candles)
+        }
+
+        async fn sync(&self, _instrument_id: &InstrumentId, _timeframes: &[Timeframe], _from: DateTime<Utc>,
+                      _to: Option<DateTime<Utc>>) -> Result<Vec<SyncReport>> {
+            Ok(Vec::new())
+        }
+
+        async fn save_point(&self, _point: Point) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn get_points(&self, _deployment_id: Uuid, _instrument_id: &InstrumentId) -> Result<Vec<Point>> {
+            unimplemented!()
+        }
+
+        async fn save_line(&self, _line: Line) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn get_lines(&self, _deployment_id: Uuid, _instrument_id: &InstrumentId) -> Result<Vec<Line>> {
+            unimplemented!()
+        }
+
+        async fn delete_simulation(&self, _simulation_id: Uuid, _deployment_ids: &[Uuid]) -> Result<CleanupReport> {
+            unimplemented!()
+        }
+
+        async fn save_action_record(&self, _record: ActionRecord) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn get_action_records(&self, _deployment_id: Uuid, _from: Option<DateTime<Utc>>, _to: Option<DateTime<Utc>>) -> Result<Vec<ActionRecord>> {
+            unimplemented!()
+        }
+
+        async fn save_state_snapshot(&self, _snapshot: StateSnapshot, _history: u64) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn get_state_snapshots(&self, _state_id: &str) -> Result<Vec<StateSnapshot>> {
+            unimplemented!()
+        }
+
+        async fn delete_state_snapshots(&self, _state_id: &str) -> Result<u64> {
+            unimplemented!()
+        }
+    }
+
+    fn instrument_id() -> InstrumentId {
+        InstrumentId {
+            exchange: Exchange::OKX,
+            market_type: MarketType::Spot,
+            pair: CurrencyPair {
+                target: Currency::BTC,
+                source: Currency::USDT,
+            },
+        }
+    }
+
+    fn candle(timestamp: DateTime<Utc>) -> Candle {
+        Candle {
+            id: timestamp.to_string(),
+            status: CandleStatus::Close,
+            instrument_id: instrument_id(),
+            timestamp,
+            timeframe: Timeframe::OneH,
+            open_price: 1.,
+            highest_price: 1.,
+            lowest_price: 1.,
+            close_price: 1.,
+            target_volume: 1.,
+            source_volume: 1.,
+        }
+    }
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 12, day, 0, 0, 0).unwrap()
+    }
+
+    async fn get(preloaded: &StorageCoreApiPreloaded<TestStorage>, from: Option<DateTime<Utc>>, to: DateTime<Utc>, limit: Option<u64>) -> Vec<Candle> {
+        preloaded
+            .get_candles(&instrument_id(), Some(Timeframe::OneH), from, Some(to), limit)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_window_serves_later_requests() {
+        let storage = Arc::new(TestStorage::default());
+        storage.add_hours(day(1), 24 * 5);
+        let preloaded = StorageCoreApiPreloaded::new(Arc::clone(&storage), Duration::days(7), 1);
+
+        assert_eq!(get(&preloaded, Some(day(1)), day(2), None).await.len(), 25);
+        let candles = get(&preloaded, Some(day(2)), day(3), None).await;
+        assert_eq!(candles.len(), 25);
+        assert_eq!(candles[0].timestamp, day(3));
+        assert_eq!(get(&preloaded, None, day(4), Some(10)).await.len(), 10);
+        assert_eq!(storage.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_window_is_replaced() {
+        for (windows, requests) in [(1, 3), (2, 2)] {
+            let storage = Arc::new(TestStorage::default());
+            storage.add_hours(day(1), 24 * 30);
+            let preloaded = StorageCoreApiPreloaded::new(Arc::clone(&storage), Duration::days(1), windows);
+
+            get(&preloaded, Some(day(1)), day(2), None).await;
+            get(&preloaded, Some(day(10)), day(11), None).await;
+            get(&preloaded, Some(day(1)), day(2), None).await;
+            assert_eq!(storage.requests(), requests);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_drops_overlapping_windows() {
+        let storage = Arc::new(TestStorage::default());
+        storage.add_hours(day(1), 24);
+        let preloaded = StorageCoreApiPreloaded::new(Arc::clone(&storage), Duration::days(7), 1);
+        get(&preloaded, Some(day(1)), day(1) + Duration::hours(12), None).await;
+
+        // the window reaches the next day, which is synced only now
+        storage.add_hours(day(2), 24);
+        preloaded.sync(&instrument_id(), &[Timeframe::OneH], day(2), Some(day(3))).await.unwrap();
+
+        assert_eq!(get(&preloaded, Some(day(2)), day(2) + Duration::hours(12), None).await.len(), 13);
+        assert_eq!(storage.requests(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sync_keeps_other_windows() {
+        let storage = Arc::new(TestStorage::default());
+        storage.add_hours(day(1), 24 * 30);
+        let preloaded = StorageCoreApiPreloaded::new(Arc::clone(&storage), Duration::days(1), 1);
+        get(&preloaded, Some(day(1)), day(2), None).await;
+
+        preloaded.sync(&instrument_id(), &[Timeframe::OneH], day(10), Some(day(11))).await.unwrap();
+
+        get(&preloaded, Some(day(1)), day(2), None).await;
+        assert_eq!(storage.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_latest_candles_with_gaps_are_loaded_from_storage() {
+        let storage = Arc::new(TestStorage::default());
+        storage.add_hours(day(1), 10);
+        storage.add_hours(day(2), 10);
+        let preloaded = StorageCoreApiPreloaded::new(Arc::clone(&storage), Duration::days(7), 1);
+
+        // the 15 hours before the request hold only the 10 candles of the day 2
+        let candles = get(&preloaded, None, day(2) + Duration::hours(9), Some(15)).await;
+        assert_eq!(candles.len(), 15);
+        assert_eq!(candles[14].timestamp, day(1) + Duration::hours(5));
+        assert_eq!(storage.requests(), 2);
+    }
+}
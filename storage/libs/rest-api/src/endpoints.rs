@@ -10,3 +10,9 @@ pub const GET_POINTS: &str = "/api/v1/storage/drawing/points";
 pub const POST_POINT: &str = "/api/v1/storage/drawing/points";
 pub const GET_LINES: &str = "/api/v1/storage/drawing/lines";
 pub const POST_LINE: &str = "/api/v1/storage/drawing/lines";
+pub const DELETE_SIMULATION: &str = "/api/v1/storage/simulations/:id";
+pub const GET_ACTION_RECORDS: &str = "/api/v1/storage/audit/actions";
+pub const POST_ACTION_RECORD: &str = "/api/v1/storage/audit/actions";
+pub const GET_STATE_SNAPSHOTS: &str = "/api/v1/storage/states";
+pub const POST_STATE_SNAPSHOT: &str = "/api/v1/storage/states";
+pub const DELETE_STATE_SNAPSHOTS: &str = "/api/v1/storage/states";
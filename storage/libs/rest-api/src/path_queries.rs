@@ -58,3 +58,20 @@ pub struct DrawingQuery {
     pub target: Currency,
     pub source: Currency,
 }
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ActionRecordsQuery {
+    pub deployment_id: Uuid,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StateSnapshotsQuery {
+    pub state_id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StateHistoryQuery {
+    pub history: u64,
+}
@@ -5,7 +5,9 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use domain_model::{Candle, Currency, Exchange, InstrumentId, LP, MarketType, Order, OrderStatus, OrderType, Position, Side, Timeframe};
+use domain_model::audit::ActionRecord;
 use domain_model::drawing::{Line, Point};
+use domain_model::state::StateSnapshot;
 
 #[async_trait]
 pub trait StorageApi: Send + Sync + 'static {
@@ -61,6 +63,23 @@ pub trait StorageApi: Send + Sync + 'static {
         deployment_id: Uuid,
         instrument_id: &InstrumentId,
     ) -> Result<Vec<Line>>;
+    async fn delete_simulation(
+        &self,
+        simulation_id: Uuid,
+        deployment_ids: &[Uuid],
+    ) -> Result<CleanupReport>;
+    async fn save_action_record(&self, record: ActionRecord) -> Result<()>;
+    async fn get_action_records(
+        &self,
+        deployment_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ActionRecord>>;
+    /// Saves the next version of a plugin state, only the last `history` versions are kept, 0 keeps all versions
+    async fn save_state_snapshot(&self, snapshot: StateSnapshot, history: u64) -> Result<()>;
+    /// Saved versions of the state ordered by version
+    async fn get_state_snapshots(&self, state_id: &str) -> Result<Vec<StateSnapshot>>;
+    async fn delete_state_snapshots(&self, state_id: &str) -> Result<u64>;
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -70,3 +89,11 @@ pub struct SyncReport {
     pub exists: u64,
     pub synced: u64,
 }
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CleanupReport {
+    pub orders: u64,
+    pub positions: u64,
+    pub points: u64,
+    pub lines: u64,
+}
@@ -1,5 +1,6 @@
 pub use api::StorageApi;
 pub use api::SyncReport;
+pub use api::CleanupReport;
 
 mod api;
 
@@ -1,6 +1,8 @@
+pub use repositories::ActionRecordRepository;
 pub use repositories::CandleRepository;
 pub use repositories::DrawingRepository;
 pub use repositories::OrderRepository;
 pub use repositories::PositionRepository;
+pub use repositories::StateSnapshotRepository;
 
 mod repositories;
@@ -7,7 +7,9 @@ use domain_model::{
     Candle, Currency, Exchange, InstrumentId, MarketType, Order, OrderStatus, OrderType, Position,
     Side, Timeframe,
 };
+use domain_model::audit::ActionRecord;
 use domain_model::drawing::{Line, Point};
+use domain_model::state::StateSnapshot;
 
 #[async_trait]
 pub trait OrderRepository: Send + Sync + 'static {
@@ -26,6 +28,7 @@ pub trait OrderRepository: Send + Sync + 'static {
         side: Option<Side>,
         order_type: Option<OrderType>,
     ) -> Result<Vec<Order>>;
+    async fn delete_by_simulation(&self, simulation_id: Uuid) -> Result<u64>;
 }
 
 #[async_trait]
@@ -37,6 +40,7 @@ pub trait PositionRepository: Send + Sync + 'static {
         currency: Option<Currency>,
         side: Option<Side>,
     ) -> Result<Vec<Position>>;
+    async fn delete_by_simulation(&self, simulation_id: Uuid) -> Result<u64>;
 }
 
 #[async_trait]
@@ -67,4 +71,27 @@ pub trait DrawingRepository: Send + Sync + 'static {
         deployment_id: Uuid,
         instrument_id: &InstrumentId,
     ) -> Result<Vec<Line>>;
+    async fn delete_points(&self, deployment_ids: &[Uuid]) -> Result<u64>;
+    async fn delete_lines(&self, deployment_ids: &[Uuid]) -> Result<u64>;
+}
+
+#[async_trait]
+pub trait ActionRecordRepository: Send + Sync + 'static {
+    async fn save(&self, record: ActionRecord) -> Result<()>;
+    async fn get(
+        &self,
+        deployment_id: Uuid,
+        from_timestamp: Option<DateTime<Utc>>,
+        to_timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ActionRecord>>;
+}
+
+#[async_trait]
+pub trait StateSnapshotRepository: Send + Sync + 'static {
+    async fn save(&self, snapshot: StateSnapshot) -> Result<()>;
+    /// Snapshots of the state ordered by version
+    async fn get(&self, state_id: &str) -> Result<Vec<StateSnapshot>>;
+    /// Deletes versions older than the given one
+    async fn delete_before(&self, state_id: &str, version: i64) -> Result<u64>;
+    async fn delete(&self, state_id: &str) -> Result<u64>;
 }